@@ -0,0 +1,20 @@
+use mangadex::api::manga::*;
+use mangadex::blocking::Client;
+use mangadex::schema::manga::*;
+
+fn main() -> anyhow::Result<()> {
+    let client = Client::default();
+
+    let list_manga = ListMangaBuilder::default()
+        .add_status(MangaStatus::Ongoing)
+        .build()?;
+
+    let mangas = list_manga.send_blocking(&client)?;
+
+    for manga in mangas.results.iter().flatten() {
+        let title = manga.data.display_title(client.preferred_languages());
+        println!("Got manga {} with id: {:x}", title, manga.data.id);
+    }
+
+    Ok(())
+}
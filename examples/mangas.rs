@@ -5,7 +5,9 @@ use mangadex::Client;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let client = Client::default();
+    let client = Client::builder()
+        .preferred_languages(vec![LanguageCode::English, LanguageCode::JapaneseRomanized])
+        .build()?;
 
     let list_manga = ListMangaBuilder::default()
         .add_status(MangaStatus::Ongoing)
@@ -15,13 +17,8 @@ async fn main() -> anyhow::Result<()> {
 
     for manga_result in &mangas.results {
         if let Ok(manga) = &manga_result {
-            let english_title = manga
-                .data
-                .attributes
-                .title
-                .get(&LanguageCode::English)
-                .unwrap();
-            println!("Got manga {} with id: {:x}", english_title, manga.data.id);
+            let title = manga.data.display_title(client.preferred_languages());
+            println!("Got manga {} with id: {:x}", title, manga.data.id);
         }
     }
 
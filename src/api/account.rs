@@ -4,87 +4,89 @@ use crate::schema::user::*;
 use crate::schema::NoData;
 
 use crate::Result;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 /// Create account
 ///
 /// Call to `POST /account/create`
-#[derive(Debug, Serialize, Clone)]
-pub struct CreateAccount<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CreateAccount {
     /// Username (length 1 to 64)
-    pub username: &'a str,
+    pub username: String,
     /// Password (length 8 to 1024)
-    pub password: &'a str,
+    pub password: String,
     /// Email
-    pub email: &'a str,
+    pub email: String,
 }
 
 impl_endpoint! {
     POST "/account/create",
-    #[body] CreateAccount<'_>,
+    #[body] CreateAccount,
     #[flatten_result] UserResponse
 }
 
 /// Activate account
 ///
 /// Call to `GET /account/activate/{code}`
-#[derive(Debug, Clone)]
-pub struct ActivateAccount<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ActivateAccount {
     /// Account activation code
-    pub code: &'a str,
+    pub code: String,
 }
 
 impl_endpoint! {
     POST ("/account/activate/{}", code),
-    #[no_data] ActivateAccount<'_>,
+    #[no_data] ActivateAccount,
     #[discard_result] Result<NoData>
 }
 
 /// Resend activation code
 ///
 /// Call to `POST /account/activate/resend`
-#[derive(Debug, Serialize, Clone)]
-pub struct ResendActivationCode<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ResendActivationCode {
     /// Email
-    pub email: &'a str,
+    pub email: String,
 }
 
 impl_endpoint! {
     POST "/account/activate/resend",
-    #[body] ResendActivationCode<'_>,
+    #[body] ResendActivationCode,
     #[discard_result] Result<NoData>
 }
 
 /// Recover account
 ///
 /// Call to `POST /account/recover`
-#[derive(Debug, Serialize, Clone)]
-pub struct RecoverAccount<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RecoverAccount {
     /// Email
-    pub email: &'a str,
+    pub email: String,
 }
 
 impl_endpoint! {
     POST "/account/recover",
-    #[body] RecoverAccount<'_>,
+    #[body] RecoverAccount,
     #[discard_result] Result<NoData>
 }
 
 /// Complete account recover
 ///
 /// Call to `POST /account/recover`
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
-pub struct CompleteAccountRecover<'a> {
+pub struct CompleteAccountRecover {
     /// Account recovery code
-    #[serde(skip)]
-    pub code: &'a str,
+    #[serde(skip_serializing)]
+    pub code: String,
     /// New password (length 8 to 1024)
-    pub new_password: &'a str,
+    pub new_password: String,
 }
 
 impl_endpoint! {
     POST ("/account/recover/{}", code),
-    #[body] CompleteAccountRecover<'_>,
+    #[body] CompleteAccountRecover,
     #[discard_result] Result<NoData>
 }
+
+path_ids! { CompleteAccountRecover { code: "code" } }
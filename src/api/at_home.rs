@@ -1,21 +1,20 @@
 //! MangaDex@Home server
 
 use reqwest::Url;
-use serde::Serialize;
-use uuid::Uuid;
+use serde::{Deserialize, Serialize};
 
-use crate::schema::at_home::*;
+use crate::schema::{at_home::*, ChapterId};
 use crate::{Client, Result};
 
 /// Get MangaDex@Home server URL
 ///
 /// Call to `GET /at-home/server/{chapterId}
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
-pub struct GetAtHomeServer<'a> {
+pub struct GetAtHomeServer {
     /// Chapter ID
-    #[serde(skip)]
-    pub chapter_id: &'a Uuid,
+    #[serde(skip_serializing)]
+    pub chapter_id: ChapterId,
 
     /// Force selecting from MangaDex@Home servers that use the standard HTTPS port 443.
     ///
@@ -29,13 +28,21 @@ pub struct GetAtHomeServer<'a> {
 
 impl_endpoint! {
     GET ("/at-home/server/{:x}", chapter_id),
-    #[query] GetAtHomeServer<'_>,
+    #[query] GetAtHomeServer,
     #[no_send] AtHomeServer
 }
 
-impl GetAtHomeServer<'_> {
+path_ids! { GetAtHomeServer { chapter_id: "chapterId" } }
+
+impl GetAtHomeServer {
     pub async fn send(&self, client: &Client) -> Result<Url> {
-        let r = client.send_request(self).await?;
+        let r = client.send(self).await?;
+        Ok(Url::parse(&r.base_url)?)
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn send_blocking(&self, client: &crate::blocking::Client) -> Result<Url> {
+        let r = client.send(self)?;
         Ok(Url::parse(&r.base_url)?)
     }
 }
@@ -43,12 +50,13 @@ impl GetAtHomeServer<'_> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cassette::test_client;
     use pretty_assertions::assert_eq;
 
     #[tokio::test]
     async fn at_home() {
-        let client = Client::default();
-        let chapter_id = &uuid::Uuid::parse_str("0e94efb5-6cb5-49fd-b522-51b4460c9821").unwrap();
+        let client = test_client("at_home/at_home");
+        let chapter_id = "0e94efb5-6cb5-49fd-b522-51b4460c9821".parse().unwrap();
         GetAtHomeServer {
             chapter_id,
             force_port443: false,
@@ -60,8 +68,8 @@ mod tests {
 
     #[tokio::test]
     async fn at_home_force443() {
-        let client = Client::default();
-        let chapter_id = &uuid::Uuid::parse_str("0e94efb5-6cb5-49fd-b522-51b4460c9821").unwrap();
+        let client = test_client("at_home/at_home_force443");
+        let chapter_id = "0e94efb5-6cb5-49fd-b522-51b4460c9821".parse().unwrap();
         let res = GetAtHomeServer {
             chapter_id,
             force_port443: false,
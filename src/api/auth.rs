@@ -4,7 +4,7 @@ use crate::schema::auth::*;
 use crate::schema::NoData;
 use crate::Result;
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 /// Login with given username and password
 ///
@@ -13,24 +13,24 @@ use serde::Serialize;
 /// [`Client::login()`][crate::Client::login()] method.
 ///
 /// Call to `POST /auth/login`
-#[derive(Debug, Serialize, Clone)]
-pub struct Login<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Login {
     /// Username (length 1 to 64)
-    pub username: &'a str,
+    pub username: String,
     /// Password (length 8 to 1024)
-    pub password: &'a str,
+    pub password: String,
 }
 
 impl_endpoint! {
     POST "/auth/login",
-    #[body] Login<'_>,
+    #[body] Login,
     #[flatten_result] Result<LoginResponse>
 }
 
 /// Check permissions for the logged user
 ///
 /// Call to `GET /auth/check`
-#[derive(Debug, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CheckToken;
 
 impl_endpoint! {
@@ -46,7 +46,7 @@ impl_endpoint! {
 /// [`Client::logout()`][crate::Client::logout()] method.
 ///
 /// Call to `POST /auth/logout`
-#[derive(Debug, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Logout;
 
 impl_endpoint! {
@@ -62,16 +62,16 @@ impl_endpoint! {
 /// [`Client::refresh_tokens()`][crate::Client::refresh_tokens()] method.
 ///
 /// Call to `POST /auth/refresh`
-#[derive(Debug, Serialize, Clone)]
-pub struct RefreshToken<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RefreshToken {
     /// Refresh token
     #[serde(rename = "token")]
-    pub refresh_token: &'a str,
+    pub refresh_token: String,
 }
 
 impl_endpoint! {
     POST "/auth/refresh",
-    #[body] RefreshToken<'_>,
+    #[body] RefreshToken,
     #[flatten_result] Result<RefreshTokenResponse>
 }
 
@@ -307,7 +307,7 @@ mod tests {
         mock.assert_async().await;
 
         assert_matches!(errors, Errors::Api(errs) if errs.errors.len() == 1usize => {
-            let error = errs.errors.get(0).unwrap();
+            let error = errs.errors.first().unwrap();
             assert_eq!(error.id, uuid::Uuid::parse_str("5e50fc7b-e185-45b1-a692-58e8091b22d2")?);
             assert_eq!(error.title.as_deref(), Some("The service is unavailable"));
             assert_eq!(error.detail.as_deref(), Some("Servers are burning"));
@@ -400,7 +400,7 @@ mod tests {
                 mock.assert_async().await;
 
                 assert_matches!(errors, Errors::Api(errs) if errs.errors.len() == 1usize => {
-                    let error = errs.errors.get(0).unwrap();
+                    let error = errs.errors.first().unwrap();
                     assert_eq!(error.id, uuid::Uuid::parse_str("5e50fc7b-e185-45b1-a692-58e8091b22d2")?);
                     assert_eq!(error.title.as_deref(), Some("Error title"));
                     assert_eq!(error.detail.as_deref(), Some("Error detail"));
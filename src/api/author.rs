@@ -1,23 +1,22 @@
 //! Manga authors
 
 use derive_builder::Builder;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
-use uuid::Uuid;
 
 use crate::schema::author::*;
-use crate::schema::NoData;
 use crate::schema::PaginationQuery;
+use crate::schema::{AuthorId, NoData};
 use crate::Result;
 
 /// List authors
 ///
 /// Call to `GET /author`
 #[skip_serializing_none]
-#[derive(Debug, Serialize, Clone, Builder, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Builder, Default)]
 #[serde(rename_all = "camelCase")]
-#[builder(setter(strip_option), default)]
-pub struct ListAuthors<'a> {
+#[builder(setter(into, strip_option), default)]
+pub struct ListAuthors {
     /// Pagination parameters
     #[serde(flatten)]
     pub pagination: PaginationQuery,
@@ -25,10 +24,10 @@ pub struct ListAuthors<'a> {
     /// Author ids (limited to 100 per request)
     #[serde(skip_serializing_if = "Vec::is_empty")]
     #[builder(setter(each = "add_id"))]
-    pub ids: Vec<&'a Uuid>,
+    pub ids: Vec<AuthorId>,
 
     /// Author name
-    pub name: Option<&'a str>,
+    pub name: Option<String>,
 
     /// Result order
     pub order: Option<AuthorOrder>,
@@ -36,17 +35,17 @@ pub struct ListAuthors<'a> {
 
 impl_endpoint! {
     GET "/author",
-    #[query] ListAuthors<'_>,
+    #[query] ListAuthors,
     AuthorList
 }
 
 /// Create a new author (requires authentication)
 ///
 /// Call to `POST /author`
-#[derive(Debug, Serialize, Clone)]
-pub struct CreateAuthor<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CreateAuthor {
     /// Name of the author
-    pub name: &'a str,
+    pub name: String,
 
     /// Version (minimum: 1)
     pub version: i32,
@@ -54,36 +53,36 @@ pub struct CreateAuthor<'a> {
 
 impl_endpoint! {
     POST "/author",
-    #[body auth] CreateAuthor<'_>,
+    #[body auth] CreateAuthor,
     #[flatten_result] AuthorResponse
 }
 
 /// Get author information
 ///
 /// Call to `GET /author/{id}`
-#[derive(Debug, Clone)]
-pub struct GetAuthor<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GetAuthor {
     /// Author id
-    pub id: &'a Uuid,
+    pub id: AuthorId,
 }
 
 impl_endpoint! {
     GET ("/author/{:x}", id),
-    #[no_data] GetAuthor<'_>,
+    #[no_data] GetAuthor,
     #[flatten_result] AuthorResponse
 }
 
 /// Update an existing author (requires authentication)
 ///
 /// Call to `PUT /author/{id}`
-#[derive(Debug, Serialize, Clone)]
-pub struct UpdateAuthor<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UpdateAuthor {
     /// Author id
-    #[serde(skip)]
-    pub id: &'a Uuid,
+    #[serde(skip_serializing)]
+    pub id: AuthorId,
 
     /// Name of the author
-    pub name: &'a str,
+    pub name: String,
 
     /// Version (minimum: 1)
     pub version: i32,
@@ -91,21 +90,23 @@ pub struct UpdateAuthor<'a> {
 
 impl_endpoint! {
     PUT ("/author/{:x}", id),
-    #[body auth] UpdateAuthor<'_>,
+    #[body auth] UpdateAuthor,
     #[flatten_result] AuthorResponse
 }
 
+path_ids! { UpdateAuthor { id: "id" } }
+
 /// Delete author (requires authentication)
 ///
 /// Call to `DELETE /author/{id}`
-#[derive(Debug, Clone)]
-pub struct DeleteAuthor<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DeleteAuthor {
     /// Author id
-    pub id: &'a Uuid,
+    pub id: AuthorId,
 }
 
 impl_endpoint! {
     DELETE ("/author/{:x}", id),
-    #[no_data auth] DeleteAuthor<'_>,
+    #[no_data auth] DeleteAuthor,
     #[discard_result] Result<NoData>
 }
@@ -1,20 +1,20 @@
 //! Solve captcha
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{schema::NoData, Result};
 
 /// Solve captcha
 ///
 /// Call to `POST /captcha/solve`
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
-pub struct SolveCaptcha<'a> {
-    pub captcha_challenge: &'a str,
+pub struct SolveCaptcha {
+    pub captcha_challenge: String,
 }
 
 impl_endpoint! {
     POST "/captcha/solve",
-    #[body] SolveCaptcha<'_>,
+    #[body] SolveCaptcha,
     #[discard_result] Result<NoData>
 }
@@ -2,19 +2,18 @@
 
 use chrono::{DateTime, Utc};
 use derive_builder::Builder;
-use serde::Serialize;
-use uuid::Uuid;
+use serde::{Deserialize, Serialize};
 
-use crate::schema::{chapter::*, LanguageCode, NoData};
+use crate::schema::{chapter::*, ChapterId, GroupId, LanguageCode, MangaId, NoData, UserId};
 use crate::Result;
 
 /// Chapter list
 ///
 /// Call to `GET /chapter`
-#[derive(Debug, Serialize, Clone, Builder, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Builder, Default)]
 #[serde(rename_all = "camelCase")]
-#[builder(setter(strip_option), default)]
-pub struct ListChapters<'a> {
+#[builder(setter(into, strip_option), default)]
+pub struct ListChapters {
     /// Page size
     pub limit: Option<i32>,
 
@@ -24,26 +23,26 @@ pub struct ListChapters<'a> {
     /// Chapter ids
     #[serde(rename = "ids")]
     #[builder(setter(each = "add_chapter"))]
-    pub chapter_ids: Vec<&'a Uuid>,
+    pub chapter_ids: Vec<ChapterId>,
 
     /// Chapter title
-    pub title: Option<&'a str>,
+    pub title: Option<String>,
 
     /// Groups
     #[builder(setter(each = "add_group"))]
-    pub groups: Vec<&'a Uuid>,
+    pub groups: Vec<GroupId>,
 
     /// Uploader
-    pub uploader: Option<&'a Uuid>,
+    pub uploader: Option<UserId>,
 
     /// Manga
-    pub manga: Option<&'a Uuid>,
+    pub manga: Option<MangaId>,
 
     /// Volume
-    pub volume: Option<&'a str>,
+    pub volume: Option<String>,
 
     /// Chapter
-    pub chapter: Option<&'a str>,
+    pub chapter: Option<String>,
 
     /// Translated language
     pub translated_language: Option<LanguageCode>,
@@ -63,52 +62,49 @@ pub struct ListChapters<'a> {
 
 impl_endpoint! {
     GET "/chapter",
-    #[query] ListChapters<'_>,
+    #[query] ListChapters,
     ChapterList
 }
 
 /// Get chapter
 ///
 /// Call to `GET /chapter/{chapter_id}`
-#[derive(Debug, Clone)]
-pub struct GetChapter<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GetChapter {
     /// Chapter id
-    pub chapter_id: &'a Uuid,
+    pub chapter_id: ChapterId,
 }
 
 impl_endpoint! {
     GET ("/chapter/{:x}", chapter_id),
-    #[no_data] GetChapter<'_>,
+    #[no_data] GetChapter,
     #[flatten_result] ChapterResponse
 }
 
 /// Update chapter (requires authentication)
 ///
 /// Call to `PUT /chapter/{chapter_id}
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
-pub struct UpdateChapter<'a> {
+pub struct UpdateChapter {
     /// Chapter id
-    #[serde(skip)]
-    pub chapter_id: &'a Uuid,
+    #[serde(skip_serializing)]
+    pub chapter_id: ChapterId,
 
     /// Chapter title (max length: 255)
-    pub title: &'a str,
+    pub title: String,
 
     /// Chapter volume
-    pub volume: Option<&'a str>,
+    pub volume: Option<String>,
 
     /// Chapter number (max length: 8)
-    pub chapter: Option<&'a str>,
+    pub chapter: Option<String>,
 
     /// Translated language
     pub translated_language: LanguageCode,
 
-    /// Data quality image names
-    pub data: Vec<&'a str>,
-
-    /// Data-saver quality image names
-    pub data_saver: Vec<&'a str>,
+    /// Scanlation groups
+    pub groups: Vec<GroupId>,
 
     /// Chapter version (min: 1)
     pub version: i32,
@@ -116,48 +112,53 @@ pub struct UpdateChapter<'a> {
 
 impl_endpoint! {
     PUT ("/chapter/{:x}", chapter_id),
-    #[body auth] UpdateChapter<'_>,
+    #[body auth] UpdateChapter,
     #[flatten_result] ChapterResponse
 }
 
+path_ids! { UpdateChapter { chapter_id: "chapterId" } }
+
 /// Delete a chapter (requires authorization)
 ///
 /// Call to `DELETE /chapter/{id}`
-#[derive(Debug, Clone)]
-pub struct DeleteChapter<'a> {
-    chapter_id: &'a Uuid,
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DeleteChapter {
+    /// Chapter id
+    pub chapter_id: ChapterId,
 }
 
 impl_endpoint! {
     DELETE ("/chapter/{:x}", chapter_id),
-    #[no_data auth] DeleteChapter<'_>,
+    #[no_data auth] DeleteChapter,
     #[discard_result] Result<NoData>
 }
 
 /// Mark chapter as read (requires authorization)
 ///
 /// Call to `POST /chapter/{id}/read`
-#[derive(Debug, Clone)]
-pub struct MarkChapterRead<'a> {
-    chapter_id: &'a Uuid,
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MarkChapterRead {
+    /// Chapter id
+    pub chapter_id: ChapterId,
 }
 
 impl_endpoint! {
     POST ("/chapter/{:x}/read", chapter_id),
-    #[no_data auth] MarkChapterRead<'_>,
+    #[no_data auth] MarkChapterRead,
     #[discard_result] Result<NoData>
 }
 
 /// Mark chapter as unread (requires authorization)
 ///
 /// Call to `DELETE /chapter/{id}/read`
-#[derive(Debug, Clone)]
-pub struct MarkChapterUnread<'a> {
-    chapter_id: &'a Uuid,
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MarkChapterUnread {
+    /// Chapter id
+    pub chapter_id: ChapterId,
 }
 
 impl_endpoint! {
     DELETE ("/chapter/{:x}/read", chapter_id),
-    #[no_data auth] MarkChapterUnread<'_>,
+    #[no_data auth] MarkChapterUnread,
     #[discard_result] Result<NoData>
 }
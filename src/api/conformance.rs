@@ -0,0 +1,558 @@
+//! Checks of the request types against the operations of `api.yaml`.
+//!
+//! Every endpoint of `api` has a sample request below. The method, path, query parameters, body
+//! and auth requirement of each sample are compared to the matching operation of the spec, and the
+//! examples of the spec are deserialized into the response types.
+
+use std::collections::BTreeSet;
+use std::fs;
+
+use serde::de::DeserializeOwned;
+use serde_json::{json, Map, Value};
+
+use super::*;
+use crate::schema::{chapter::Chapter, manga::Manga, LocalizedString};
+use crate::{Endpoint, FromResponse};
+
+/// Known mistakes of the spec, as (failure, reason). A failure is ignored if its message contains
+/// one of these.
+const SPEC_ERRATA: &[(&str, &str)] = &[
+    (
+        "GET /manga/read example \"example-grouped\"",
+        "only returned with `grouped=true`, which the wrapper doesn't send",
+    ),
+    (
+        "Chapter example \"example\"",
+        "the example predates relationships, and has its related objects in the attributes",
+    ),
+    (
+        "GET /chapter/{id} example \"example-1\"",
+        "the example predates the `hash` attribute",
+    ),
+    (
+        "POST /captcha/solve requires auth",
+        "captchas are also solved by logged out users, e.g. when creating an account",
+    ),
+    (
+        "GET /list/{id}/feed requires auth",
+        "the feed of a public list can be read without being logged in",
+    ),
+    (
+        "GET /manga has no query parameter \"availableTranslatedLanguage\"",
+        "added to the api after this version of the spec",
+    ),
+    (
+        "Manga example \"example\"",
+        "the example uses `romaji` as a language code",
+    ),
+];
+
+struct Operation<'a> {
+    method: String,
+    path: &'a str,
+    item: &'a Value,
+    op: &'a Value,
+}
+
+impl Operation<'_> {
+    fn display(&self) -> String {
+        format!("{} {}", self.method, self.path)
+    }
+}
+
+struct Report {
+    spec: Value,
+    checked: BTreeSet<String>,
+    failures: Vec<String>,
+}
+
+impl Report {
+    fn new() -> Self {
+        let spec = serde_yaml::from_str(include_str!("../../api.yaml")).expect("invalid api.yaml");
+        Self {
+            spec,
+            checked: BTreeSet::new(),
+            failures: Vec::new(),
+        }
+    }
+
+    fn fail(&mut self, name: &str, message: String) {
+        if !SPEC_ERRATA
+            .iter()
+            .any(|(failure, _)| message.contains(failure))
+        {
+            self.failures.push(format!("{}: {}", name, message));
+        }
+    }
+
+    /// Panic with the failures, if there are any.
+    fn finish(self, what: &str) {
+        assert!(
+            self.failures.is_empty(),
+            "{}:\n{}",
+            what,
+            self.failures.join("\n")
+        );
+    }
+
+    fn operations(&self) -> Vec<Operation<'_>> {
+        let mut operations = Vec::new();
+        for (path, item) in self.spec["paths"].as_object().unwrap() {
+            for method in &["get", "post", "put", "delete"] {
+                if let Some(op) = item.get(*method) {
+                    operations.push(Operation {
+                        method: method.to_uppercase(),
+                        path,
+                        item,
+                        op,
+                    });
+                }
+            }
+        }
+        operations
+    }
+
+    /// Follow a `$ref` of the spec, if the value is one.
+    fn resolve<'a>(&'a self, value: &'a Value) -> &'a Value {
+        match value.get("$ref").and_then(Value::as_str) {
+            Some(reference) => self
+                .spec
+                .pointer(reference.trim_start_matches('#'))
+                .map_or(value, |v| self.resolve(v)),
+            None => value,
+        }
+    }
+
+    /// Check a sample request of an endpoint, given as json.
+    fn check_json<E>(&mut self, name: &str, sample: Value)
+    where
+        E: Endpoint + DeserializeOwned,
+    {
+        match serde_json::from_value::<E>(sample) {
+            Ok(endpoint) => self.check(name, endpoint),
+            Err(e) => {
+                self.checked.insert(name.to_string());
+                self.fail(name, format!("invalid sample request: {}", e));
+            }
+        }
+    }
+
+    /// Check a sample request of an endpoint, and deserialize the examples of its operation.
+    fn check<E: Endpoint>(&mut self, name: &str, endpoint: E) {
+        self.checked.insert(name.to_string());
+        let method = endpoint.method().to_string();
+        let path = endpoint.path().into_owned();
+        let mut failures = Vec::new();
+        let mut examples = Vec::new();
+
+        match self.find(&method, &path) {
+            None => failures.push(format!("no operation for {} {}", method, path)),
+            Some(op) => {
+                if endpoint.require_auth() != self.requires_auth(&op) {
+                    failures.push(format!(
+                        "{} requires auth: {}, the wrapper: {}",
+                        op.display(),
+                        self.requires_auth(&op),
+                        endpoint.require_auth()
+                    ));
+                }
+                let query = endpoint.query().map(|q| serde_json::to_value(q).unwrap());
+                let body = endpoint.body().map(|b| serde_json::to_value(b).unwrap());
+                failures.extend(self.check_query(&op, query.as_ref()));
+                failures.extend(self.check_body(&op, body, endpoint.multipart().is_some()));
+                examples = self
+                    .examples(&op)
+                    .into_iter()
+                    .map(|(example, value)| {
+                        (format!("{} example {:?}", op.display(), example), value)
+                    })
+                    .collect();
+            }
+        }
+
+        for (example, value) in examples {
+            if let Err(e) = serde_json::from_value::<<E::Response as FromResponse>::Response>(value)
+            {
+                failures.push(format!("{} doesn't deserialize: {}", example, e));
+            }
+        }
+        for failure in failures {
+            self.fail(name, failure);
+        }
+    }
+
+    /// The operation matching a request. Literal segments win over parameters, so
+    /// `/manga/random` isn't taken for `/manga/{id}`.
+    fn find(&self, method: &str, path: &str) -> Option<Operation<'_>> {
+        let segments: Vec<&str> = path.split('/').collect();
+        self.operations()
+            .into_iter()
+            .filter(|op| op.method == method)
+            .filter_map(|op| {
+                let template: Vec<&str> = op.path.split('/').collect();
+                if template.len() != segments.len() {
+                    return None;
+                }
+                let mut literals = 0;
+                for (t, s) in template.iter().zip(&segments) {
+                    if t.starts_with('{') {
+                        if s.is_empty() {
+                            return None;
+                        }
+                    } else if t == s {
+                        literals += 1;
+                    } else {
+                        return None;
+                    }
+                }
+                Some((literals, op))
+            })
+            .max_by_key(|(literals, _)| *literals)
+            .map(|(_, op)| op)
+    }
+
+    fn requires_auth(&self, op: &Operation<'_>) -> bool {
+        let security = op.op.get("security").unwrap_or(&self.spec["security"]);
+        security.as_array().is_some_and(|s| !s.is_empty())
+    }
+
+    /// The parameters of an operation, as (name, location, required).
+    fn parameters(&self, op: &Operation<'_>) -> Vec<(String, String, bool)> {
+        let mut params = Vec::new();
+        for list in &[&op.item["parameters"], &op.op["parameters"]] {
+            for param in list.as_array().into_iter().flatten() {
+                let param = self.resolve(param);
+                params.push((
+                    param["name"].as_str().unwrap_or_default().to_string(),
+                    param["in"].as_str().unwrap_or_default().to_string(),
+                    param["required"].as_bool().unwrap_or(false),
+                ));
+            }
+        }
+        params
+    }
+
+    fn check_query(&self, op: &Operation<'_>, query: Option<&Value>) -> Vec<String> {
+        let params = self.parameters(op);
+        let mut failures = Vec::new();
+        let sent: Vec<&String> = query
+            .and_then(Value::as_object)
+            .map(|q| q.keys().collect())
+            .unwrap_or_default();
+
+        for key in &sent {
+            let known = params.iter().any(|(name, location, _)| {
+                location == "query" && name.trim_end_matches("[]") == **key
+            });
+            if !known {
+                failures.push(format!("{} has no query parameter {:?}", op.display(), key));
+            }
+        }
+        for (name, location, required) in &params {
+            let name = name.trim_end_matches("[]");
+            if location == "query" && *required && !sent.iter().any(|key| *key == name) {
+                failures.push(format!("missing required query parameter {:?}", name));
+            }
+        }
+        failures
+    }
+
+    fn check_body(&self, op: &Operation<'_>, body: Option<Value>, multipart: bool) -> Vec<String> {
+        let content = op.op["requestBody"]["content"].as_object();
+        let json = content.and_then(|c| c.get("application/json"));
+        let form = content.and_then(|c| c.get("multipart/form-data"));
+
+        if multipart != form.is_some() {
+            return vec![format!(
+                "{} takes a multipart body: {}, the wrapper sends one: {}",
+                op.display(),
+                form.is_some(),
+                multipart
+            )];
+        }
+        let (body, schema) = match (body, json) {
+            (None, None) => return Vec::new(),
+            (Some(body), Some(json)) => (body, self.resolve(&json["schema"])),
+            (body, json) => {
+                return vec![format!(
+                    "{} takes a json body: {}, the wrapper sends one: {}",
+                    op.display(),
+                    json.is_some(),
+                    body.is_some()
+                )]
+            }
+        };
+
+        let mut failures = Vec::new();
+        match (&body, schema["type"].as_str()) {
+            (Value::Array(_), Some("array")) => {}
+            (Value::Object(fields), _) => {
+                let (properties, required) = self.object(schema);
+                for key in fields.keys() {
+                    if !properties.contains_key(key) {
+                        failures.push(format!("{} body has no property {:?}", op.display(), key));
+                    }
+                }
+                for name in required {
+                    let nullable = properties[&name]["nullable"].as_bool().unwrap_or(false);
+                    match fields.get(&name) {
+                        Some(Value::Null) if !nullable => {
+                            failures.push(format!("required body property {:?} is null", name))
+                        }
+                        None => failures.push(format!("missing required body property {:?}", name)),
+                        _ => {}
+                    }
+                }
+            }
+            (_, ty) => failures.push(format!("{} takes a {:?} body", op.display(), ty)),
+        }
+        failures
+    }
+
+    /// The properties and the required properties of an object schema, including the ones of
+    /// the schemas it is composed of with `allOf`.
+    fn object(&self, schema: &Value) -> (Map<String, Value>, Vec<String>) {
+        let schema = self.resolve(schema);
+        let mut properties = schema["properties"]
+            .as_object()
+            .cloned()
+            .unwrap_or_default();
+        let mut required: Vec<String> = schema["required"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|r| r.as_str().map(String::from))
+            .collect();
+        for part in schema["allOf"].as_array().into_iter().flatten() {
+            let (more_properties, more_required) = self.object(part);
+            properties.extend(more_properties);
+            required.extend(more_required);
+        }
+        (properties, required)
+    }
+
+    /// The examples of the successful response of an operation, by name.
+    fn examples(&self, op: &Operation<'_>) -> Vec<(String, Value)> {
+        let media = &op.op["responses"]["200"]["content"]["application/json"];
+        let mut examples = Vec::new();
+        if let Some(named) = media["examples"].as_object() {
+            examples.extend(named.iter().map(|(n, e)| (n.clone(), e["value"].clone())));
+        }
+        if let Some(example) = media.get("example") {
+            examples.push(("example".to_string(), example.clone()));
+        }
+        if let Some(example) = media["schema"].get("example") {
+            examples.push(("schema".to_string(), example.clone()));
+        }
+        examples
+    }
+
+    /// Deserialize the examples of a component schema.
+    fn schema_examples<T: DeserializeOwned>(&mut self, schema: &str) {
+        let value = &self.spec["components"]["schemas"][schema];
+        let mut examples: Vec<(String, Value)> = value
+            .get("example")
+            .map(|e| ("example".to_string(), e.clone()))
+            .into_iter()
+            .collect();
+        if let Some(named) = value["x-examples"].as_object() {
+            examples.extend(named.iter().map(|(n, e)| (n.clone(), e.clone())));
+        }
+        for (example, value) in examples {
+            if let Err(e) = serde_json::from_value::<T>(value) {
+                let message = format!(
+                    "{} example {:?} doesn't deserialize: {}",
+                    schema, example, e
+                );
+                self.fail(schema, message);
+            }
+        }
+    }
+}
+
+const ID: &str = "32d76d19-8a05-4db0-9fc2-e0b0648fe9d0";
+
+/// Check sample requests, given as json or as a value.
+macro_rules! check {
+    ($report:expr, $($module:ident::$ty:ident $(: $sample:tt)? $(= $value:expr)?),* $(,)?) => {
+        $(
+            let name = concat!(stringify!($module), "::", stringify!($ty));
+            $( $report.check::<$module::$ty>(name, $value); )?
+            $( $report.check_json::<$module::$ty>(name, json!($sample)); )?
+        )*
+    };
+}
+
+/// The request types that implement `Endpoint` in the modules of `api`.
+fn endpoint_types() -> BTreeSet<String> {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/src/api");
+    let mut types = BTreeSet::new();
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        let module = path.file_stem().unwrap().to_str().unwrap().to_string();
+        let source = fs::read_to_string(&path).unwrap();
+        let mut in_macro = false;
+        for line in source.lines().map(str::trim) {
+            if line.starts_with("impl_endpoint! {") {
+                in_macro = true;
+            } else if let Some(rest) = line.strip_prefix("impl Endpoint for ") {
+                let name = rest.trim_end_matches(" {");
+                types.insert(format!("{}::{}", module, name));
+            } else if in_macro && line.starts_with("#[") {
+                let name = line.split("] ").nth(1).unwrap().trim_end_matches(',');
+                types.insert(format!("{}::{}", module, name));
+                in_macro = false;
+            }
+        }
+    }
+    types
+}
+
+#[test]
+fn requests_match_spec() {
+    let mut report = Report::new();
+    let chapter_draft = json!({
+        "volume": null,
+        "chapter": "1",
+        "title": null,
+        "translatedLanguage": "en",
+    });
+    let manga_request = json!({
+        "title": { "en": "Title" },
+        "version": 1,
+    });
+
+    check!(report,
+        account::CreateAccount: { "username": "user", "password": "hunter1", "email": "a@b.c" },
+        account::ActivateAccount: { "code": "code" },
+        account::ResendActivationCode: { "email": "a@b.c" },
+        account::RecoverAccount: { "email": "a@b.c" },
+        account::CompleteAccountRecover = account::CompleteAccountRecover {
+            code: "code".to_string(),
+            new_password: "hunter2".to_string(),
+        },
+        at_home::GetAtHomeServer: { "chapterId": ID, "forcePort443": false },
+        auth::Login: { "username": "user", "password": "hunter1" },
+        auth::CheckToken: null,
+        auth::Logout: null,
+        auth::RefreshToken: { "token": "refresh" },
+        author::ListAuthors: { "ids": [] },
+        author::CreateAuthor: { "name": "name", "version": 1 },
+        author::GetAuthor: { "id": ID },
+        author::UpdateAuthor: { "id": ID, "name": "name", "version": 1 },
+        author::DeleteAuthor: { "id": ID },
+        captcha::SolveCaptcha: { "captchaChallenge": "challenge" },
+        chapter::ListChapters: { "ids": [], "groups": [] },
+        chapter::GetChapter: { "chapter_id": ID },
+        chapter::UpdateChapter: {
+            "chapterId": ID,
+            "title": "title",
+            "translatedLanguage": "en",
+            "groups": [],
+            "version": 1,
+        },
+        chapter::DeleteChapter: { "chapter_id": ID },
+        chapter::MarkChapterRead: { "chapter_id": ID },
+        chapter::MarkChapterUnread: { "chapter_id": ID },
+        cover::ListCovers: { "manga": [], "ids": [], "uploaders": [] },
+        cover::GetCover: { "cover_id": ID },
+        cover::EditCover: { "cover_id": ID, "volume": null, "version": 1 },
+        cover::DeleteCover: { "cover_id": ID },
+        cover::UploadCover: { "manga_id": ID, "image_bytes": [] },
+        feed::GetMangaFeed: { "mangaId": ID, "translatedLanguage": [] },
+        feed::FollowedMangaFeed: { "translatedLanguage": [] },
+        feed::CustomListMangaFeed: { "listId": ID, "translatedLanguage": [] },
+        group::ListGroups: { "ids": [] },
+        group::CreateGroup: { "name": "name", "leader": ID, "members": [], "version": 1 },
+        group::ViewGroup: { "id": ID },
+        group::UpdateGroup: { "id": ID, "name": "name", "leader": ID, "members": [], "version": 1 },
+        group::DeleteGroup: { "id": ID },
+        group::FollowGroup: { "id": ID },
+        group::UnfollowGroup: { "id": ID },
+        legacy::LegacyMapping: { "type": "manga", "ids": [1] },
+        list::CreateCustomList: { "name": "name", "visibility": "private", "manga": [], "version": 1 },
+        list::GetCustomList: { "id": ID },
+        list::UpdateCustomList: { "id": ID, "name": "name", "visibility": "private", "manga": [], "version": 1 },
+        list::DeleteCustomList: { "id": ID },
+        list::AddMangaToCustomList: { "manga_id": ID, "list_id": ID },
+        list::RemoveMangaFromCustomList: { "manga_id": ID, "list_id": ID },
+        list::GetLoggedUserCustomLists: {},
+        list::GetUserCustomLists: { "user_id": ID },
+        manga::ListManga: {
+            "authors": [],
+            "artists": [],
+            "includedTags": [],
+            "excludedTags": [],
+            "status": [],
+            "originalLanguage": [],
+            "publicationDemographic": [],
+            "ids": [],
+            "contentRating": [],
+            "availableTranslatedLanguage": [],
+            "order": { "title": "asc", "relevance": "desc" },
+            "includes": [],
+        },
+        manga::CreateManga: manga_request,
+        manga::GetMangaAggregate: { "mangaId": ID },
+        manga::GetManga: { "id": ID },
+        manga::UpdateManga: { "id": ID, "title": { "en": "Title" }, "version": 1 },
+        manga::DeleteManga: { "id": ID },
+        manga::UnfollowManga: { "id": ID },
+        manga::FollowManga: { "id": ID },
+        manga::GetMangaReadMarkers: { "manga_id": ID },
+        manga::GetBatchMangaReadMarkers: { "ids": [ID], "grouped": false },
+        manga::RandomManga: null,
+        manga::ListTags: null,
+        manga::AllMangaStatus: {},
+        manga::GetMangaStatus: { "id": ID },
+        manga::UpdateMangaStatus: { "id": ID, "status": "reading" },
+        report::ListReports: { "category": "manga" },
+        report::CreateReport: {
+            "category": "manga",
+            "reason": "reason",
+            "objectId": ID,
+            "details": "details",
+        },
+        upload::GetUploadSession: null,
+        upload::BeginUploadSession: { "groups": [ID], "manga": ID },
+        upload::PutUploadSessionFile: { "uploadSessionId": ID, "file": [] },
+        upload::AbandonUploadSession: { "uploadSessionId": ID },
+        upload::CommitUploadSession: {
+            "uploadSessionId": ID,
+            "chapterDraft": chapter_draft,
+            "pageOrder": [ID],
+        },
+        upload::DeleteUploadedSessionFile: { "uploadSessionId": ID, "uploadSessionFileId": ID },
+        upload::DeleteUploadedSessionFiles: { "uploadSessionId": ID, "ids": [ID] },
+        user::ListUsers: { "ids": [] },
+        user::GetUser: { "user_id": ID },
+        user::DeleteUser: { "user_id": ID },
+        user::ApproveUserDeletion: { "code": ID },
+        user::UpdatePassword: { "oldPassword": "hunter1", "newPassword": "hunter2" },
+        user::UpdateEmail: { "email": "a@b.c" },
+        user::GetLoggedUser: null,
+        user::ListFollowedGroups: {},
+        user::ListFollowedUsers: {},
+        user::ListFollowedManga: {},
+    );
+
+    let unchecked: Vec<_> = endpoint_types()
+        .difference(&report.checked)
+        .cloned()
+        .collect();
+    assert!(
+        unchecked.is_empty(),
+        "no sample request for {:?}",
+        unchecked
+    );
+    report.finish("requests don't match api.yaml");
+}
+
+#[test]
+fn schema_examples() {
+    let mut report = Report::new();
+    report.schema_examples::<Chapter>("Chapter");
+    report.schema_examples::<Manga>("Manga");
+    report.schema_examples::<LocalizedString>("LocalizedString");
+    report.finish("examples of api.yaml don't deserialize");
+}
@@ -3,23 +3,20 @@
 use std::borrow::Cow;
 
 use derive_builder::Builder;
-use reqwest::multipart::Form;
-use reqwest::multipart::Part;
 use reqwest::Method;
-use serde::Serialize;
-use uuid::Uuid;
+use serde::{Deserialize, Serialize};
 
-use crate::common::Endpoint;
-use crate::schema::{cover::*, NoData};
+use crate::common::{Endpoint, Multipart};
+use crate::schema::{cover::*, CoverId, MangaId, NoData, UserId};
 use crate::Client;
 use crate::Result;
 
 /// Cover art list
 ///
 /// Call to `GET /cover`
-#[derive(Debug, Serialize, Clone, Builder, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Builder, Default)]
 #[builder(default, setter(strip_option))]
-pub struct ListCovers<'a> {
+pub struct ListCovers {
     /// Page size (max 100)
     pub limit: Option<i32>,
 
@@ -28,16 +25,16 @@ pub struct ListCovers<'a> {
 
     /// Manga ids (max 100)
     #[builder(setter(each = "add_manga"))]
-    pub manga: Vec<&'a Uuid>,
+    pub manga: Vec<MangaId>,
 
     /// Cover ids (max 100)
     #[serde(rename = "ids")]
     #[builder(setter(each = "add_cover"))]
-    pub covers: Vec<&'a Uuid>,
+    pub covers: Vec<CoverId>,
 
     /// Uploader ids (max 100)
     #[builder(setter(each = "add_uploader"))]
-    pub uploaders: Vec<&'a Uuid>,
+    pub uploaders: Vec<UserId>,
 
     /// Sort order
     pub order: Option<CoverOrder>,
@@ -45,38 +42,38 @@ pub struct ListCovers<'a> {
 
 impl_endpoint! {
     GET "/cover",
-    #[query] ListCovers<'_>,
+    #[query] ListCovers,
     CoverList
 }
 
 /// Get cover
 ///
 /// Call to `GET /cover/{cover_id}`
-#[derive(Debug, Clone)]
-pub struct GetCover<'a> {
-    pub cover_id: &'a Uuid,
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GetCover {
+    pub cover_id: CoverId,
 }
 
 impl_endpoint! {
     GET ("/cover/{:x}", cover_id),
-    #[no_data] GetCover<'_>,
+    #[no_data] GetCover,
     #[flatten_result] CoverResponse
 }
 
 /// Edit cover
 ///
 /// Call to `PUT /cover/{cover_id}`
-#[derive(Debug, Serialize, Clone)]
-pub struct EditCover<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EditCover {
     /// Cover id
-    #[serde(skip)]
-    pub cover_id: &'a Uuid,
+    #[serde(skip_serializing)]
+    pub cover_id: CoverId,
 
     /// Cover volume
-    pub volume: Option<&'a str>,
+    pub volume: Option<String>,
 
     /// Cover description
-    pub description: Option<&'a str>,
+    pub description: Option<String>,
 
     /// Cover version
     pub version: i32,
@@ -84,40 +81,44 @@ pub struct EditCover<'a> {
 
 impl_endpoint! {
     PUT ("/cover/{:x}", cover_id),
-    #[body auth] EditCover<'_>,
+    #[body auth] EditCover,
     #[flatten_result] CoverResponse
 }
 
+path_ids! { EditCover { cover_id: "cover_id" } }
+
 /// Delete cover
 ///
 /// Call to `DELETE /cover/{cover_id}`
-pub struct DeleteCover<'a> {
-    pub cover_id: &'a Uuid,
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DeleteCover {
+    pub cover_id: CoverId,
 }
 
 impl_endpoint! {
     DELETE ("/cover/{:x}", cover_id),
-    #[no_data auth] DeleteCover<'_>,
+    #[no_data auth] DeleteCover,
     #[discard_result] Result<NoData>
 }
 
 /// Upload cover (requires authentication)
 ///
 /// Call to `POST /cover/{manga_id}`
-pub struct UploadCover<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UploadCover {
     /// Manga id
-    pub manga_id: &'a Uuid,
+    pub manga_id: MangaId,
 
     /// Image bytes
     pub image_bytes: Cow<'static, [u8]>,
 }
 
-impl Endpoint for UploadCover<'_> {
+impl Endpoint for UploadCover {
     type Query = ();
     type Body = ();
     type Response = CoverResponse;
 
-    fn path(&self) -> Cow<str> {
+    fn path(&self) -> Cow<'_, str> {
         Cow::Owned(format!("/cover/{:x}", self.manga_id))
     }
     fn method(&self) -> Method {
@@ -128,14 +129,18 @@ impl Endpoint for UploadCover<'_> {
         true
     }
 
-    fn multipart(&self) -> Option<Form> {
-        let part = Part::bytes(self.image_bytes.clone());
-        Some(Form::new().part("file", part))
+    fn multipart(&self) -> Option<Multipart> {
+        Some(Multipart::new().bytes("file", self.image_bytes.clone()))
     }
 }
 
-impl UploadCover<'_> {
+impl UploadCover {
     pub async fn send(&self, client: &Client) -> CoverResponse {
-        client.send_request(self).await?
+        client.send(self).await?
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn send_blocking(&self, client: &crate::blocking::Client) -> CoverResponse {
+        client.send(self)?
     }
 }
@@ -2,23 +2,22 @@
 
 use chrono::{DateTime, Utc};
 use derive_builder::Builder;
-use serde::Serialize;
-use uuid::Uuid;
+use serde::{Deserialize, Serialize};
 
-use crate::schema::{chapter::ChapterList, feed::*, LanguageCode};
+use crate::schema::{chapter::ChapterList, feed::*, LanguageCode, ListId, MangaId};
 
 /// Manga feed
 ///
 /// Returns the feed (chapters list) for the specified manga
 ///
 /// Call to `GET /manga/{id}/feed`
-#[derive(Debug, Serialize, Clone, Builder)]
+#[derive(Debug, Serialize, Deserialize, Clone, Builder)]
 #[builder(setter(strip_option))]
 #[serde(rename_all = "camelCase")]
-pub struct GetMangaFeed<'a> {
+pub struct GetMangaFeed {
     /// Manga id
-    #[serde(skip)]
-    pub manga_id: &'a Uuid,
+    #[serde(skip_serializing)]
+    pub manga_id: MangaId,
 
     /// Page size
     pub limit: Option<i32>,
@@ -50,16 +49,18 @@ pub struct GetMangaFeed<'a> {
 
 impl_endpoint! {
     GET ("/manga/{:x}/feed", manga_id),
-    #[query] GetMangaFeed<'_>,
+    #[query] GetMangaFeed,
     ChapterList
 }
 
+path_ids! { GetMangaFeed { manga_id: "mangaId" } }
+
 /// Get logged user followed manga feed (requires authentication)
 ///
 /// Returns the feed (chapters list) for the logged user
 ///
 /// Call to `GET /user/follows/manga/feed`
-#[derive(Debug, Serialize, Clone, Builder)]
+#[derive(Debug, Serialize, Deserialize, Clone, Builder)]
 #[builder(setter(strip_option))]
 #[serde(rename_all = "camelCase")]
 pub struct FollowedMangaFeed {
@@ -93,20 +94,20 @@ pub struct FollowedMangaFeed {
 
 impl_endpoint! {
     GET "/user/follows/manga/feed",
-    #[body auth] FollowedMangaFeed,
+    #[query auth] FollowedMangaFeed,
     ChapterList
 }
 
 /// Custom list manga feed
 ///
 /// Call to `GET /list/{list_id}/feed`
-#[derive(Debug, Serialize, Clone, Builder)]
+#[derive(Debug, Serialize, Deserialize, Clone, Builder)]
 #[builder(setter(strip_option))]
 #[serde(rename_all = "camelCase")]
-pub struct CustomListMangaFeed<'a> {
+pub struct CustomListMangaFeed {
     /// List id
-    #[serde(skip)]
-    pub list_id: &'a Uuid,
+    #[serde(skip_serializing)]
+    pub list_id: ListId,
 
     /// Page size
     pub limit: Option<i32>,
@@ -138,6 +139,8 @@ pub struct CustomListMangaFeed<'a> {
 
 impl_endpoint! {
     GET ("/list/{:x}/feed", list_id),
-    #[query] CustomListMangaFeed<'_>,
+    #[query] CustomListMangaFeed,
     ChapterList
 }
+
+path_ids! { CustomListMangaFeed { list_id: "listId" } }
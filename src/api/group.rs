@@ -2,17 +2,16 @@
 
 use crate::{schema::NoData, Result};
 use derive_builder::Builder;
-use serde::Serialize;
-use uuid::Uuid;
+use serde::{Deserialize, Serialize};
 
-use crate::schema::group::*;
+use crate::schema::{group::*, GroupId, UserId};
 
 /// Lists canlation groups
 ///
 /// Call to `GET /group`
-#[derive(Debug, Serialize, Builder, Default)]
-#[builder(setter(strip_option), default)]
-pub struct ListGroups<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone, Builder, Default)]
+#[builder(setter(into, strip_option), default)]
+pub struct ListGroups {
     /// Page size
     pub limit: Option<i32>,
 
@@ -22,36 +21,36 @@ pub struct ListGroups<'a> {
     /// Scanlation group ids (limited to 100 per request)
     #[builder(setter(each = "add_group"))]
     #[serde(rename = "ids")]
-    pub group_ids: Vec<&'a Uuid>,
+    pub group_ids: Vec<GroupId>,
 
     /// Author name
-    pub name: Option<&'a str>,
+    pub name: Option<String>,
 
     /// Includes
-    pub includes: Option<&'a str>,
+    pub includes: Option<String>,
 }
 
 impl_endpoint! {
     GET "/group",
-    #[query] ListGroups<'_>,
+    #[query] ListGroups,
     ScanlationGroupList
 }
 
 /// Create scanlation group (requires authentication)
 ///
 /// Call to `POST /group`
-#[derive(Debug, Serialize, Clone, Builder)]
-#[builder(setter(strip_option))]
-pub struct CreateGroup<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone, Builder)]
+#[builder(setter(into, strip_option))]
+pub struct CreateGroup {
     /// Group name
-    pub name: &'a str,
+    pub name: String,
 
     /// Group leader
-    pub leader: &'a Uuid,
+    pub leader: UserId,
 
     /// Memebers list
     #[builder(setter(each = "add_member"))]
-    pub members: Vec<&'a Uuid>,
+    pub members: Vec<UserId>,
 
     /// Group version (minimum: 1)
     pub version: i32,
@@ -59,42 +58,44 @@ pub struct CreateGroup<'a> {
 
 impl_endpoint! {
     POST "/group",
-    #[body auth] CreateGroup<'_>,
+    #[body auth] CreateGroup,
     #[flatten_result] Result<ScanlationGroupData>
 }
 
 /// View scanlation group
 ///
 /// Call to `GET /group/{id}`
-pub struct ViewGroup<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ViewGroup {
     /// Group id
-    pub id: &'a Uuid,
+    pub id: GroupId,
 }
 
 impl_endpoint! {
     GET ("/group/{:x}", id),
-    #[no_data] ViewGroup<'_>,
+    #[no_data] ViewGroup,
     #[flatten_result] Result<ScanlationGroupData>
 }
 
 /// Update scanlation group (requires auth)
 ///
 /// Call to `PUT /group/{id}`
-#[derive(Debug, Serialize, Clone, Builder)]
-#[builder(setter(strip_option))]
-pub struct UpdateGroup<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone, Builder)]
+#[builder(setter(into, strip_option))]
+pub struct UpdateGroup {
     /// Group id
-    pub id: &'a Uuid,
+    #[serde(skip_serializing)]
+    pub id: GroupId,
 
     /// Group name
-    pub name: &'a str,
+    pub name: String,
 
     /// Group leader
-    pub leader: Uuid,
+    pub leader: UserId,
 
     /// Member list
     #[builder(setter(each = "add_member"))]
-    pub members: Vec<Uuid>,
+    pub members: Vec<UserId>,
 
     /// Group version (minimum: 1)
     pub version: i32,
@@ -102,62 +103,67 @@ pub struct UpdateGroup<'a> {
 
 impl_endpoint! {
     PUT ("/group/{:x}", id),
-    #[body auth] UpdateGroup<'_>,
+    #[body auth] UpdateGroup,
     #[flatten_result] Result<ScanlationGroupData>
 }
 
+path_ids! { UpdateGroup { id: "id" } }
+
 /// Delete scanlation group (requires authentication)
 ///
 /// Call to `DELETE /group/{id}`
-pub struct DeleteGroup<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DeleteGroup {
     /// Group id
-    pub id: &'a Uuid,
+    pub id: GroupId,
 }
 
 impl_endpoint! {
     DELETE ("/group/{:x}", id),
-    #[no_data auth] DeleteGroup<'_>,
+    #[no_data auth] DeleteGroup,
     #[discard_result] Result<NoData>
 }
 
 /// Follow scanlation group
 ///
 /// Call to `POST /group/{id}/follow`
-pub struct FollowGroup<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FollowGroup {
     /// Group id
-    pub id: &'a Uuid,
+    pub id: GroupId,
 }
 
 impl_endpoint! {
     POST ("/group/{:x}/follow", id),
-    #[no_data auth] FollowGroup<'_>,
+    #[no_data auth] FollowGroup,
     #[discard_result] Result<NoData>
 }
 
 /// Unfollow scanlation group
 ///
 /// Call to `DELETE /group/{id}/follow`
-pub struct UnfollowGroup<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UnfollowGroup {
     /// Group id
-    pub id: &'a Uuid,
+    pub id: GroupId,
 }
 
 impl_endpoint! {
     DELETE ("/group/{:x}/follow", id),
-    #[no_data auth] UnfollowGroup<'_>,
+    #[no_data auth] UnfollowGroup,
     #[discard_result] Result<NoData>
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::Client;
+    use crate::cassette::test_client;
 
     use super::*;
     use pretty_assertions::assert_eq;
 
     #[tokio::test]
     async fn list_group() {
-        let client = Client::default();
+        let client = test_client("group/list_group");
         let groups = ListGroupsBuilder::default()
             .build()
             .unwrap()
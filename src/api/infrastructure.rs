@@ -1,10 +1,13 @@
 //! Server status
 
+use serde::{Deserialize, Serialize};
+
 use crate::{Client, Result};
 
 /// Ping the server
 ///
 /// Call to `GET /ping`
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Ping;
 
 impl Ping {
@@ -13,4 +16,9 @@ impl Ping {
         // it's a special snow-flake. So we get this...
         client.ping().await
     }
+
+    #[cfg(feature = "blocking")]
+    pub fn send_blocking(&self, client: &crate::blocking::Client) -> Result<()> {
+        client.ping()
+    }
 }
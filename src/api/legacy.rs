@@ -1,14 +1,14 @@
 //! Legacy mapping
 
 use derive_builder::Builder;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::schema::legacy::*;
 
 /// Legacy id mapping
 ///
 /// Call to `POST /legacy/mapping`
-#[derive(Debug, Builder, Serialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Builder, PartialEq, Eq)]
 #[builder(setter(into))]
 #[serde(rename_all = "camelCase")]
 pub struct LegacyMapping {
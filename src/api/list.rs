@@ -1,11 +1,10 @@
 //! Custom lists
 
 use derive_builder::Builder;
-use serde::Serialize;
-use uuid::Uuid;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    schema::{NoData, PaginationQuery},
+    schema::{ListId, MangaId, NoData, PaginationQuery, UserId},
     Result,
 };
 
@@ -14,10 +13,11 @@ use crate::schema::list::*;
 /// Create custom list (requires authentication)
 ///
 /// Call to `POST /list`
-#[derive(Debug, Serialize, Clone, Builder)]
-pub struct CreateCustomList<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone, Builder)]
+#[builder(setter(into))]
+pub struct CreateCustomList {
     /// List name
-    pub name: &'a str,
+    pub name: String,
 
     /// List visibility
     #[builder(default = "CustomListVisibility::Private")]
@@ -25,7 +25,7 @@ pub struct CreateCustomList<'a> {
 
     /// List of manga ids
     #[builder(setter(each = "add_manga"))]
-    pub manga: Vec<&'a Uuid>,
+    pub manga: Vec<MangaId>,
 
     /// List version
     pub version: i32,
@@ -33,43 +33,44 @@ pub struct CreateCustomList<'a> {
 
 impl_endpoint! {
     POST "/list",
-    #[body auth] CreateCustomList<'_>,
+    #[body auth] CreateCustomList,
     #[flatten_result] CustomListResponse
 }
 
 /// Get custom list
 ///
 /// Call to `GET /list/{id}`
-#[derive(Debug, Clone)]
-pub struct GetCustomList<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GetCustomList {
     /// Custom list id
-    id: &'a Uuid,
+    pub id: ListId,
 }
 
 impl_endpoint! {
     GET ("/list/{:x}", id),
-    #[no_data] GetCustomList<'_>,
+    #[no_data] GetCustomList,
     #[flatten_result] CustomListResponse
 }
 
 /// Update custom list (requires authentication)
 ///
 /// Call to `PUT /list/{id}`
-#[derive(Debug, Serialize, Clone, Builder)]
-pub struct UpdateCustomList<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone, Builder)]
+#[builder(setter(into))]
+pub struct UpdateCustomList {
     /// List id
-    #[serde(skip)]
-    pub id: &'a Uuid,
+    #[serde(skip_serializing)]
+    pub id: ListId,
 
     /// List name
-    pub name: &'a str,
+    pub name: String,
 
     /// List visibility
     pub visibility: CustomListVisibility,
 
     /// List of manga ids
     #[builder(setter(each = "add_manga"))]
-    pub manga: Vec<&'a Uuid>,
+    pub manga: Vec<MangaId>,
 
     /// List version
     pub version: i32,
@@ -77,58 +78,60 @@ pub struct UpdateCustomList<'a> {
 
 impl_endpoint! {
     PUT ("/list/{:x}", id),
-    #[body auth] UpdateCustomList<'_>,
+    #[body auth] UpdateCustomList,
     #[flatten_result] CustomListResponse
 }
 
+path_ids! { UpdateCustomList { id: "id" } }
+
 /// Delete custom list (requires authentication)
 ///
 /// Call to `DELETE /list/{id}`
-#[derive(Debug, Clone)]
-pub struct DeleteCustomList<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DeleteCustomList {
     /// List id
-    pub id: &'a Uuid,
+    pub id: ListId,
 }
 
 impl_endpoint! {
     DELETE ("/list/{:x}", id),
-    #[no_data auth] DeleteCustomList<'_>,
+    #[no_data auth] DeleteCustomList,
     #[discard_result] Result<NoData>
 }
 
 /// Add manga to custom list (requires authentication)
 ///
 /// Call to `POST /manga/{id}/list/{id}`
-#[derive(Debug, Clone)]
-pub struct AddMangaToCustomList<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AddMangaToCustomList {
     /// Manga to add
-    pub manga_id: &'a Uuid,
+    pub manga_id: MangaId,
 
     /// List to edit
-    pub list_id: &'a Uuid,
+    pub list_id: ListId,
 }
 
 impl_endpoint! {
     POST ("/manga/{:x}/list/{:x}", manga_id, list_id),
-    #[no_data auth] AddMangaToCustomList<'_>,
+    #[no_data auth] AddMangaToCustomList,
     #[discard_result] Result<NoData>
 }
 
 /// Remove manga from custom list (requires authentication)
 ///
 /// Call to `DELETE /manga/{id}/list/{id}`
-#[derive(Debug, Clone)]
-pub struct RemoveMangaFromCustomList<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RemoveMangaFromCustomList {
     /// Manga to remove
-    pub manga_id: &'a Uuid,
+    pub manga_id: MangaId,
 
     /// List to edit
-    pub list_id: &'a Uuid,
+    pub list_id: ListId,
 }
 
 impl_endpoint! {
     DELETE ("/manga/{:x}/list/{:x}", manga_id, list_id),
-    #[no_data auth] RemoveMangaFromCustomList<'_>,
+    #[no_data auth] RemoveMangaFromCustomList,
     #[discard_result] Result<NoData>
 }
 
@@ -137,7 +140,7 @@ impl_endpoint! {
 /// This will list public and private custom lists
 ///
 /// Call to `GET /user/list`
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GetLoggedUserCustomLists {
     /// Pagination parameters
     #[serde(flatten)]
@@ -153,11 +156,11 @@ impl_endpoint! {
 /// Get public custom lists for specific user (requires authentication)
 ///
 /// Call to `GET /user/{user_id}/list`
-#[derive(Debug, Serialize, Clone)]
-pub struct GetUserCustomLists<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GetUserCustomLists {
     /// User id
-    #[serde(skip)]
-    pub user_id: &'a Uuid,
+    #[serde(skip_serializing)]
+    pub user_id: UserId,
 
     /// Pagination parameters
     #[serde(flatten)]
@@ -166,6 +169,8 @@ pub struct GetUserCustomLists<'a> {
 
 impl_endpoint! {
     GET ("/user/{:x}/list", user_id),
-    #[query auth] GetUserCustomLists<'_>,
+    #[query auth] GetUserCustomLists,
     CustomListList
 }
+
+path_ids! { GetUserCustomLists { user_id: "user_id" } }
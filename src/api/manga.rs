@@ -2,10 +2,10 @@
 
 use chrono::{DateTime, Utc};
 use derive_builder::Builder;
-use serde::Serialize;
-use uuid::Uuid;
+use serde::{Deserialize, Serialize};
 
-use crate::schema::{manga::*, LanguageCode, NoData};
+use crate::schema::{manga::*, AuthorId, LanguageCode, MangaId, NoData, ResourceType, TagId};
+use crate::tags::{TagCatalog, UnknownTagError};
 use crate::{Client, Result};
 
 /// Manga list
@@ -13,10 +13,10 @@ use crate::{Client, Result};
 /// Search a list of manga
 ///
 /// Call to `GET /manga`
-#[derive(Debug, Serialize, Clone, Builder, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Builder, Default)]
 #[serde(rename_all = "camelCase")]
 #[builder(setter(into, strip_option), default)]
-pub struct ListManga<'a> {
+pub struct ListManga {
     /// Page size
     pub limit: Option<i32>,
 
@@ -28,47 +28,62 @@ pub struct ListManga<'a> {
 
     /// Manga authors
     #[builder(setter(each = "add_author"))]
-    pub authors: Vec<&'a Uuid>,
+    #[serde(default)]
+    pub authors: Vec<AuthorId>,
 
     /// Manga artists
     #[builder(setter(each = "add_artist"))]
-    pub artists: Vec<&'a Uuid>,
+    #[serde(default)]
+    pub artists: Vec<AuthorId>,
 
     /// Year of release
     pub year: Option<i32>,
 
     /// Included tags
     #[builder(setter(each = "include_tag"))]
-    pub included_tags: Vec<&'a Uuid>,
+    #[serde(default)]
+    pub included_tags: Vec<TagId>,
 
     /// Tag inclusion mode
     pub included_tags_mode: Option<TagMode>,
 
     /// Excluded tags
     #[builder(setter(each = "exclude_tag"))]
-    pub excluded_tags: Vec<&'a Uuid>,
+    #[serde(default)]
+    pub excluded_tags: Vec<TagId>,
 
     /// Tag exclusion mode
     pub excluded_tags_mode: Option<TagMode>,
 
     /// Manga status
     #[builder(setter(each = "add_status"))]
+    #[serde(default)]
     pub status: Vec<MangaStatus>,
 
     /// Original language
-    pub original_language: Vec<String>,
+    #[builder(setter(each = "add_original_language"))]
+    #[serde(default)]
+    pub original_language: Vec<LanguageCode>,
 
     /// Publication demographic
     #[builder(setter(each = "add_demographic"))]
+    #[serde(default)]
     pub publication_demographic: Vec<Demographic>,
 
     /// Manga ids
     #[builder(setter(each = "add_manga"))]
-    #[serde(rename = "ids")]
-    pub manga_ids: Vec<Uuid>,
+    #[serde(rename = "ids", default)]
+    pub manga_ids: Vec<MangaId>,
 
-    /// Content rating
-    pub content_rating: Option<ContentRating>,
+    /// Content ratings
+    #[builder(setter(each = "add_content_rating"))]
+    #[serde(default)]
+    pub content_rating: Vec<ContentRating>,
+
+    /// Languages the manga has chapters in
+    #[builder(setter(each = "add_available_translated_language"))]
+    #[serde(default)]
+    pub available_translated_language: Vec<LanguageCode>,
 
     /// Created after this time
     pub created_at_since: Option<DateTime<Utc>>,
@@ -76,46 +91,70 @@ pub struct ListManga<'a> {
     /// Updated after this time
     pub updated_at_since: Option<DateTime<Utc>>,
 
-    /// Sorting order
-    pub order: Option<MangaOrder>,
+    /// Sorting order, by decreasing priority
+    #[builder(setter(each = "add_order"))]
+    #[serde(with = "crate::common::order_map", default)]
+    pub order: Vec<MangaOrder>,
 
-    /// Includes.
-    pub includes: Option<Vec<String>>,
+    /// Related objects to include in the response
+    #[builder(setter(each = "add_include"))]
+    #[serde(default)]
+    pub includes: Vec<ResourceType>,
 }
 
 impl_endpoint! {
     GET "/manga",
-    #[query] ListManga<'_>,
+    #[query] ListManga,
     MangaList
 }
 
+impl ListMangaBuilder {
+    /// Include a tag, looked up by name in `catalog`.
+    pub fn include_tag_named(
+        &mut self,
+        catalog: &TagCatalog,
+        name: &str,
+    ) -> std::result::Result<&mut Self, UnknownTagError> {
+        Ok(self.include_tag(catalog.require(name)?))
+    }
+
+    /// Exclude a tag, looked up by name in `catalog`.
+    pub fn exclude_tag_named(
+        &mut self,
+        catalog: &TagCatalog,
+        name: &str,
+    ) -> std::result::Result<&mut Self, UnknownTagError> {
+        Ok(self.exclude_tag(catalog.require(name)?))
+    }
+}
+
 /// Create manga (requires authentication)
 ///
 /// Create a new manga
 ///
 /// Call to `POST /manga`
-#[derive(Debug, Serialize, Clone)]
-pub struct CreateManga<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CreateManga {
     /// The request.
     #[serde(flatten)]
-    pub request: &'a MangaRequest,
+    pub request: MangaRequest,
 }
 
 impl_endpoint! {
     POST "/manga",
-    #[body auth] CreateManga<'_>,
+    #[body auth] CreateManga,
     #[flatten_result] MangaResponse
 }
 
 /// Get manga chapters & volumes
 ///
 /// Call to `GET /manga/{id}/aggregate`
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
-pub struct GetMangaAggregate<'a> {
+pub struct GetMangaAggregate {
     /// Manga id
-    #[serde(skip)]
-    pub manga_id: &'a Uuid,
+    #[serde(skip_serializing)]
+    pub manga_id: MangaId,
 
     /// Translated language
     pub translated_language: Option<LanguageCode>,
@@ -123,22 +162,24 @@ pub struct GetMangaAggregate<'a> {
 
 impl_endpoint! {
     GET ("/manga/{:x}/aggregate", manga_id),
-    #[query] GetMangaAggregate<'_>,
+    #[query] GetMangaAggregate,
     #[flatten_result] MangaAggregateResponse
 }
 
+path_ids! { GetMangaAggregate { manga_id: "mangaId" } }
+
 /// View manga
 ///
 /// Call to `GET /manga/{id}`
-#[derive(Debug, Clone)]
-pub struct GetManga<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GetManga {
     /// The manga id.
-    pub id: &'a Uuid,
+    pub id: MangaId,
 }
 
 impl_endpoint! {
     GET ("/manga/{:x}", id),
-    #[no_data] GetManga<'_>,
+    #[no_data] GetManga,
     #[flatten_result] MangaResponse
 }
 
@@ -147,60 +188,63 @@ impl_endpoint! {
 /// Update an existing manga
 ///
 /// Call to `PUT /manga/{id}`
-#[derive(Debug, Serialize, Clone)]
-pub struct UpdateManga<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UpdateManga {
     /// The manga id.
-    #[serde(skip)]
-    pub id: &'a Uuid,
+    #[serde(skip_serializing)]
+    pub id: MangaId,
     /// The request.
     #[serde(flatten)]
-    pub request: &'a MangaRequest,
+    pub request: MangaRequest,
 }
 
 impl_endpoint! {
     PUT ("/manga/{:x}", id),
-    #[body auth] UpdateManga<'_>,
+    #[body auth] UpdateManga,
     #[flatten_result] MangaResponse
 }
 
+path_ids! { UpdateManga { id: "id" } }
+
 /// Delete manga (requires authentication)
 ///
 /// Call to `DELETE /manga/{id}`
-#[derive(Debug, Clone)]
-pub struct DeleteManga<'a> {
-    pub id: &'a Uuid,
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DeleteManga {
+    pub id: MangaId,
 }
 
 impl_endpoint! {
     DELETE ("/manga/{:x}", id),
-    #[no_data auth] DeleteManga<'_>,
+    #[no_data auth] DeleteManga,
     #[discard_result] Result<NoData>
 }
 
 /// Unfollow manga (requires authentication)
 ///
 /// Call to `DELETE /manga/{id}/follow`
-pub struct UnfollowManga<'a> {
-    pub id: &'a Uuid,
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UnfollowManga {
+    pub id: MangaId,
 }
 
 impl_endpoint! {
     DELETE ("/manga/{:x}/follow", id),
-    #[no_data auth] UnfollowManga<'_>,
+    #[no_data auth] UnfollowManga,
     #[discard_result] Result<NoData>
 }
 
 /// Follow manga (requires authentication)
 ///
 /// Call to `POST /manga/{id}/follow`
-#[derive(Debug, Clone)]
-pub struct FollowManga<'a> {
-    pub id: &'a Uuid,
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FollowManga {
+    pub id: MangaId,
 }
 
 impl_endpoint! {
     POST ("/manga/{:x}/follow", id),
-    #[no_data auth] FollowManga<'_>,
+    #[no_data auth] FollowManga,
     #[discard_result] Result<NoData>
 }
 
@@ -209,15 +253,15 @@ impl_endpoint! {
 /// A list of chapter ids that are marked as read for the specified manga
 ///
 /// Call to `GET /manga/{manga_id}/read`
-#[derive(Debug, Clone)]
-pub struct GetMangaReadMarkers<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GetMangaReadMarkers {
     /// Manga id
-    pub manga_id: &'a Uuid,
+    pub manga_id: MangaId,
 }
 
 impl_endpoint! {
     GET ("/manga/{:x}/read", manga_id),
-    #[no_data auth] GetMangaReadMarkers<'_>,
+    #[no_data auth] GetMangaReadMarkers,
     #[flatten_result] MangaReadMarkerResponse
 }
 
@@ -226,21 +270,22 @@ impl_endpoint! {
 /// A list of chapter ids that are marked as read for the given manga ids
 ///
 /// Call to `GET /manga/read`
-#[derive(Serialize)]
-pub struct GetBatchMangaReadMarkers<'a> {
-    pub ids: Vec<&'a Uuid>,
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GetBatchMangaReadMarkers {
+    pub ids: Vec<MangaId>,
     // pub grouped: Option<bool>,
 }
 
 impl_endpoint! {
     GET "/manga/read",
-    #[query auth] GetBatchMangaReadMarkers<'_>,
+    #[query auth] GetBatchMangaReadMarkers,
     #[flatten_result] MangaReadMarkerResponse
 }
 
 /// Get a random manga
 ///
 /// Call to `GET /manga/random`
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RandomManga;
 
 impl_endpoint! {
@@ -252,6 +297,7 @@ impl_endpoint! {
 /// Global tag list
 ///
 /// Call to `GET /manga/tags`
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ListTags;
 
 impl_endpoint! {
@@ -263,7 +309,7 @@ impl_endpoint! {
 /// Get all manga reading status for logged user (requires authentication)
 ///
 /// Call to `GET /manga/status`
-#[derive(Serialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AllMangaStatus {
     pub status: Option<MangaReadingStatus>,
 }
@@ -277,45 +323,52 @@ impl_endpoint! {
 /// Get manga reading status (requires authentication)
 ///
 /// Call to `GET /manga/{id}/status`
-#[derive(Debug, Clone)]
-pub struct GetMangaStatus<'a> {
-    pub id: &'a Uuid,
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GetMangaStatus {
+    pub id: MangaId,
 }
 
 impl_endpoint! {
     GET ("/manga/{:x}/status", id),
-    #[no_data auth] GetMangaStatus<'_>,
+    #[no_data auth] GetMangaStatus,
     #[no_send] Result<MangaReadingStatusBody>
 }
 
-impl GetMangaStatus<'_> {
+impl GetMangaStatus {
     pub async fn send(&self, client: &Client) -> Result<MangaReadingStatus> {
-        client.send_request(self).await?.map(|r| r.status)
+        client.send(self).await?.map(|r| r.status)
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn send_blocking(&self, client: &crate::blocking::Client) -> Result<MangaReadingStatus> {
+        client.send(self)?.map(|r| r.status)
     }
 }
 
 /// Update manga reading status (requires authentication)
 ///
 /// Call to `POST /manga/{id}/status`
-#[derive(Debug, Serialize, Clone)]
-pub struct UpdateMangaStatus<'a> {
-    #[serde(skip)]
-    pub id: &'a Uuid,
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UpdateMangaStatus {
+    #[serde(skip_serializing)]
+    pub id: MangaId,
     pub status: MangaReadingStatus,
 }
 
 impl_endpoint! {
     POST ("/manga/{:x}/status", id),
-    #[body auth] UpdateMangaStatus<'_>,
+    #[body auth] UpdateMangaStatus,
     #[discard_result] Result<NoData>
 }
 
+path_ids! { UpdateMangaStatus { id: "id" } }
+
 #[cfg(test)]
 mod tests {
 
     use crate::{
-        schema::{LanguageCode, ResourceType},
-        Client,
+        cassette::test_client,
+        schema::{LanguageCode, OrderType, ResourceType},
     };
 
     use super::*;
@@ -324,17 +377,48 @@ mod tests {
 
     #[tokio::test]
     async fn list_manga() {
-        let client = Client::default();
+        let client = test_client("manga/list_manga");
         let manga = ListManga::default().send(&client).await.unwrap();
         assert_eq!(manga.offset, 0);
         assert_eq!(manga.limit, 10);
     }
 
+    #[test]
+    fn list_manga_query() {
+        let request = ListMangaBuilder::default()
+            .add_content_rating(ContentRating::Safe)
+            .add_content_rating(ContentRating::Suggestive)
+            .add_available_translated_language(LanguageCode::English)
+            .add_order(MangaOrder::FollowedCount(OrderType::Descending))
+            .add_order(MangaOrder::Title(OrderType::Ascending))
+            .build()
+            .unwrap();
+
+        let query = serde_qs::to_string(&request).unwrap();
+        assert_eq!(
+            query,
+            "contentRating[0]=safe&contentRating[1]=suggestive\
+             &availableTranslatedLanguage[0]=en\
+             &order[followedCount]=desc&order[title]=asc"
+        );
+    }
+
+    #[test]
+    fn restore_partial_list_manga() {
+        let request: ListManga =
+            serde_json::from_value(serde_json::json!({ "title": "solo leveling" })).unwrap();
+        assert_eq!(request.title.as_deref(), Some("solo leveling"));
+        assert!(request.included_tags.is_empty() && request.order.is_empty());
+
+        let request: ListManga = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert!(request.manga_ids.is_empty() && request.includes.is_empty());
+    }
+
     #[tokio::test]
     async fn view_manga() {
-        let id = Uuid::parse_str("32d76d19-8a05-4db0-9fc2-e0b0648fe9d0").unwrap();
-        let client = Client::default();
-        let manga_result = GetManga { id: &id }.send(&client).await.unwrap();
+        let id: MangaId = "32d76d19-8a05-4db0-9fc2-e0b0648fe9d0".parse().unwrap();
+        let client = test_client("manga/view_manga");
+        let manga_result = GetManga { id }.send(&client).await.unwrap();
 
         let manga = manga_result.data;
         assert_eq!(manga.id, id);
@@ -346,17 +430,32 @@ mod tests {
                 .map(String::as_str),
             Some("Solo Leveling")
         );
-        assert_eq!(manga.attributes.original_language.as_str(), "ko");
+        assert!(manga.attributes.description[&LanguageCode::English]
+            .starts_with("10 years ago, after \"the Gate\""));
+        assert_eq!(manga.attributes.original_language, LanguageCode::Korean);
+        assert_eq!(
+            manga.display_title(&[LanguageCode::Korean]),
+            "나 혼자만 레벨업"
+        );
+        // Falls back to English.
+        assert_eq!(
+            manga.display_title(&[LanguageCode::French]),
+            "Solo Leveling"
+        );
+        assert!(manga
+            .description(&[LanguageCode::French])
+            .unwrap()
+            .starts_with("10 years ago"));
         // 2019-08-25T10:51:55+00:00
         assert_eq!(
             manga.attributes.created_at,
-            Utc.ymd(2019, 8, 25).and_hms(10, 51, 55)
+            Utc.with_ymd_and_hms(2019, 8, 25, 10, 51, 55).unwrap()
         );
     }
 
     #[tokio::test]
     async fn random_manga() {
-        let client = Client::default();
+        let client = test_client("manga/random_manga");
         let manga_result = RandomManga.send(&client).await.unwrap();
         let manga = manga_result.data;
         assert_eq!(manga.r#type, ResourceType::Manga);
@@ -364,12 +463,14 @@ mod tests {
 
     #[tokio::test]
     async fn tag_list() {
-        let client = Client::default();
+        let client = test_client("manga/tag_list");
         let tag_results = ListTags.send(&client).await.unwrap();
 
         for result in &tag_results {
             let tag = &result.as_ref().unwrap().data;
             assert_eq!(tag.r#type, ResourceType::Tag);
+            // Empty descriptions are sent as `[]`.
+            assert!(tag.attributes.description.is_empty());
         }
     }
 }
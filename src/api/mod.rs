@@ -1,4 +1,71 @@
 //! API request wrappers
+//!
+//! Every request type owns its data, so requests are `Send + Sync + 'static`, and can be cloned,
+//! moved into spawned tasks, or stored next to their data. Requests also implement
+//! `Serialize` and `Deserialize`, so pending requests can be persisted and restored.
+//!
+//! A request serializes to the query or body it sends. The requests also sending ids in their
+//! path (such as the id in [`manga::UpdateManga`]) leave them out, and implement [`PathIds`]:
+//! [`Persisted`] serializes them with their ids, the form `Deserialize` reads.
+//!
+//! ```rust
+//! use mangadex::api::manga::UpdateMangaStatus;
+//! use mangadex::api::Persisted;
+//! use mangadex::schema::manga::MangaReadingStatus;
+//!
+//! let request = UpdateMangaStatus {
+//!     id: "32d76d19-8a05-4db0-9fc2-e0b0648fe9d0".parse().unwrap(),
+//!     status: MangaReadingStatus::Reading,
+//! };
+//! let stored = serde_json::to_string(&Persisted(&request)).unwrap();
+//! let restored: UpdateMangaStatus = serde_json::from_str(&stored).unwrap();
+//! assert_eq!(restored.id, request.id);
+//! ```
+
+use std::collections::BTreeMap;
+
+use serde::{Serialize, Serializer};
+
+/// A request sending ids in its path along with a query or body, which its `Serialize`
+/// implementation leaves out.
+pub trait PathIds {
+    /// Get the ids, by the name they are deserialized from.
+    fn path_ids(&self) -> Vec<(&'static str, String)>;
+}
+
+/// A request serialized with its path ids, to be restored with `Deserialize`.
+#[derive(Debug, Clone, Copy)]
+pub struct Persisted<'a, T>(pub &'a T);
+
+impl<T: Serialize + PathIds> Serialize for Persisted<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Form<'a, T> {
+            #[serde(flatten)]
+            ids: BTreeMap<&'static str, String>,
+            #[serde(flatten)]
+            request: &'a T,
+        }
+
+        Form {
+            ids: self.0.path_ids().into_iter().collect(),
+            request: self.0,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Implement [`PathIds`] for requests, given their path ids and the names they are deserialized
+/// from.
+macro_rules! path_ids {
+    ($($typ:ident { $($field:ident: $name:literal),+ }),+ $(,)?) => {$(
+        impl $crate::api::PathIds for $typ {
+            fn path_ids(&self) -> Vec<(&'static str, String)> {
+                vec![$(($name, self.$field.to_string())),+]
+            }
+        }
+    )+};
+}
 
 pub mod account;
 pub mod at_home;
@@ -14,4 +81,255 @@ pub mod legacy;
 pub mod list;
 pub mod manga;
 pub mod report;
+pub mod upload;
 pub mod user;
+
+#[cfg(test)]
+mod conformance;
+
+#[cfg(test)]
+mod tests {
+    use serde::{de::DeserializeOwned, Serialize};
+
+    use super::*;
+    use crate::Endpoint;
+
+    fn assert_owned<T: Send + Sync + Clone + Serialize + DeserializeOwned + 'static>() {}
+
+    #[test]
+    fn requests_are_owned() {
+        assert_owned::<account::CreateAccount>();
+        assert_owned::<account::ActivateAccount>();
+        assert_owned::<account::ResendActivationCode>();
+        assert_owned::<account::RecoverAccount>();
+        assert_owned::<account::CompleteAccountRecover>();
+        assert_owned::<at_home::GetAtHomeServer>();
+        assert_owned::<auth::Login>();
+        assert_owned::<auth::CheckToken>();
+        assert_owned::<auth::Logout>();
+        assert_owned::<auth::RefreshToken>();
+        assert_owned::<author::ListAuthors>();
+        assert_owned::<author::CreateAuthor>();
+        assert_owned::<author::GetAuthor>();
+        assert_owned::<author::UpdateAuthor>();
+        assert_owned::<author::DeleteAuthor>();
+        assert_owned::<captcha::SolveCaptcha>();
+        assert_owned::<chapter::ListChapters>();
+        assert_owned::<chapter::GetChapter>();
+        assert_owned::<chapter::UpdateChapter>();
+        assert_owned::<chapter::DeleteChapter>();
+        assert_owned::<chapter::MarkChapterRead>();
+        assert_owned::<chapter::MarkChapterUnread>();
+        assert_owned::<cover::ListCovers>();
+        assert_owned::<cover::GetCover>();
+        assert_owned::<cover::EditCover>();
+        assert_owned::<cover::DeleteCover>();
+        assert_owned::<cover::UploadCover>();
+        assert_owned::<feed::GetMangaFeed>();
+        assert_owned::<feed::FollowedMangaFeed>();
+        assert_owned::<feed::CustomListMangaFeed>();
+        assert_owned::<group::ListGroups>();
+        assert_owned::<group::CreateGroup>();
+        assert_owned::<group::ViewGroup>();
+        assert_owned::<group::UpdateGroup>();
+        assert_owned::<group::DeleteGroup>();
+        assert_owned::<group::FollowGroup>();
+        assert_owned::<group::UnfollowGroup>();
+        assert_owned::<infrastructure::Ping>();
+        assert_owned::<legacy::LegacyMapping>();
+        assert_owned::<list::CreateCustomList>();
+        assert_owned::<list::GetCustomList>();
+        assert_owned::<list::UpdateCustomList>();
+        assert_owned::<list::DeleteCustomList>();
+        assert_owned::<list::AddMangaToCustomList>();
+        assert_owned::<list::RemoveMangaFromCustomList>();
+        assert_owned::<list::GetLoggedUserCustomLists>();
+        assert_owned::<list::GetUserCustomLists>();
+        assert_owned::<manga::ListManga>();
+        assert_owned::<manga::CreateManga>();
+        assert_owned::<manga::GetMangaAggregate>();
+        assert_owned::<manga::GetManga>();
+        assert_owned::<manga::UpdateManga>();
+        assert_owned::<manga::DeleteManga>();
+        assert_owned::<manga::UnfollowManga>();
+        assert_owned::<manga::FollowManga>();
+        assert_owned::<manga::GetMangaReadMarkers>();
+        assert_owned::<manga::GetBatchMangaReadMarkers>();
+        assert_owned::<manga::RandomManga>();
+        assert_owned::<manga::ListTags>();
+        assert_owned::<manga::AllMangaStatus>();
+        assert_owned::<manga::GetMangaStatus>();
+        assert_owned::<manga::UpdateMangaStatus>();
+        assert_owned::<report::ListReports>();
+        assert_owned::<report::CreateReport>();
+        assert_owned::<upload::GetUploadSession>();
+        assert_owned::<upload::BeginUploadSession>();
+        assert_owned::<upload::PutUploadSessionFile>();
+        assert_owned::<upload::AbandonUploadSession>();
+        assert_owned::<upload::CommitUploadSession>();
+        assert_owned::<upload::DeleteUploadedSessionFile>();
+        assert_owned::<upload::DeleteUploadedSessionFiles>();
+        assert_owned::<user::ListUsers>();
+        assert_owned::<user::GetUser>();
+        assert_owned::<user::DeleteUser>();
+        assert_owned::<user::ApproveUserDeletion>();
+        assert_owned::<user::UpdatePassword>();
+        assert_owned::<user::UpdateEmail>();
+        assert_owned::<user::GetLoggedUser>();
+        assert_owned::<user::ListFollowedGroups>();
+        assert_owned::<user::ListFollowedUsers>();
+        assert_owned::<user::ListFollowedManga>();
+    }
+
+    #[tokio::test]
+    async fn delete_uploaded_files() -> anyhow::Result<()> {
+        use crate::schema::auth::AuthTokens;
+        use crate::Client;
+        use httpmock::{Method::DELETE, MockServer};
+        use serde_json::json;
+        use uuid::Uuid;
+
+        let session = Uuid::parse_str("2f7fd8ec-92d0-4de9-8b8b-4a32cc0d4ad1")?;
+        let file = Uuid::parse_str("8f3e1818-a015-491d-bd81-3addc4d7d56a")?;
+
+        let server = MockServer::start_async().await;
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(DELETE)
+                    .path(format!("/upload/{}/batch", session))
+                    .header("Authorization", "Bearer sessiontoken")
+                    .json_body(json!([file]));
+                then.status(200)
+                    .header("Content-Type", "application/json")
+                    .json_body(json!({ "result": "ok" }));
+            })
+            .await;
+
+        let mut client = Client::new(&server.base_url())?;
+        client.set_tokens(Some(AuthTokens {
+            session: "sessiontoken".to_string(),
+            refresh: "refreshtoken".to_string(),
+        }));
+
+        upload::DeleteUploadedSessionFiles {
+            upload_session_id: session,
+            ids: vec![file],
+        }
+        .send(&client)
+        .await?;
+
+        mock.assert_async().await;
+        Ok(())
+    }
+
+    /// Persist a request and restore it, checking that its path id survives the round trip but
+    /// isn't sent to the api.
+    fn round_trip<T>(sample: serde_json::Value, field: &str)
+    where
+        T: crate::Endpoint + PathIds + Serialize + DeserializeOwned,
+    {
+        let request: T = serde_json::from_value(sample).unwrap();
+        let persisted = serde_json::to_value(Persisted(&request)).unwrap();
+        assert_eq!(persisted[field], ID, "{} not persisted", field);
+
+        let restored: T = serde_json::from_value(persisted.clone()).unwrap();
+        assert_eq!(
+            serde_json::to_value(Persisted(&restored)).unwrap(),
+            persisted
+        );
+
+        let query = request.query().map(serde_json::to_value);
+        let body = request.body().map(serde_json::to_value);
+        for wire in query.into_iter().chain(body) {
+            assert!(
+                wire.unwrap().get(field).is_none(),
+                "{} sent to the api",
+                field
+            );
+        }
+    }
+
+    const ID: &str = "32d76d19-8a05-4db0-9fc2-e0b0648fe9d0";
+
+    #[test]
+    fn path_ids_round_trip() {
+        use serde_json::json;
+
+        round_trip::<account::CompleteAccountRecover>(
+            json!({ "code": ID, "newPassword": "hunter2" }),
+            "code",
+        );
+        round_trip::<at_home::GetAtHomeServer>(
+            json!({ "chapterId": ID, "forcePort443": false }),
+            "chapterId",
+        );
+        round_trip::<author::UpdateAuthor>(json!({ "id": ID, "name": "name", "version": 1 }), "id");
+        round_trip::<chapter::UpdateChapter>(
+            json!({
+                "chapterId": ID,
+                "title": "title",
+                "translatedLanguage": "en",
+                "groups": [],
+                "version": 1,
+            }),
+            "chapterId",
+        );
+        round_trip::<cover::EditCover>(
+            json!({ "cover_id": ID, "volume": null, "version": 1 }),
+            "cover_id",
+        );
+        round_trip::<feed::GetMangaFeed>(
+            json!({ "mangaId": ID, "translatedLanguage": [] }),
+            "mangaId",
+        );
+        round_trip::<feed::CustomListMangaFeed>(
+            json!({ "listId": ID, "translatedLanguage": [] }),
+            "listId",
+        );
+        round_trip::<group::UpdateGroup>(
+            json!({ "id": ID, "name": "name", "leader": ID, "members": [], "version": 1 }),
+            "id",
+        );
+        round_trip::<list::UpdateCustomList>(
+            json!({ "id": ID, "name": "name", "visibility": "private", "manga": [], "version": 1 }),
+            "id",
+        );
+        round_trip::<list::GetUserCustomLists>(json!({ "user_id": ID }), "user_id");
+        round_trip::<manga::GetMangaAggregate>(json!({ "mangaId": ID }), "mangaId");
+        round_trip::<manga::UpdateManga>(
+            json!({ "id": ID, "title": { "en": "Title" }, "version": 1 }),
+            "id",
+        );
+        round_trip::<upload::CommitUploadSession>(
+            json!({
+                "uploadSessionId": ID,
+                "chapterDraft": { "translatedLanguage": "en" },
+                "pageOrder": [],
+            }),
+            "uploadSessionId",
+        );
+        round_trip::<manga::UpdateMangaStatus>(json!({ "id": ID, "status": "reading" }), "id");
+
+        // The body is the list of files, the session id stays in the serialized request.
+        let request: upload::DeleteUploadedSessionFiles =
+            serde_json::from_value(json!({ "uploadSessionId": ID, "ids": [] })).unwrap();
+        assert_eq!(
+            serde_json::to_value(&request).unwrap()["uploadSessionId"],
+            ID
+        );
+        assert_eq!(serde_json::to_value(request.body()).unwrap(), json!([]));
+    }
+
+    #[tokio::test]
+    async fn spawn_request() {
+        let request = manga::GetManga {
+            id: "32d76d19-8a05-4db0-9fc2-e0b0648fe9d0".parse().unwrap(),
+        };
+
+        let persisted = serde_json::to_string(&request).unwrap();
+        let restored: manga::GetManga = serde_json::from_str(&persisted).unwrap();
+
+        let handle = tokio::spawn(async move { restored.id });
+        assert_eq!(handle.await.unwrap(), request.id);
+    }
+}
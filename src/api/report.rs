@@ -2,13 +2,13 @@
 
 use crate::schema::report::*;
 use derive_builder::Builder;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 /// List reports.
 ///
-/// Call to `GET /reports/reasons/{category}`
-#[derive(Debug, Serialize, Clone, Builder)]
+/// Call to `GET /report/reasons/{category}`
+#[derive(Debug, Serialize, Deserialize, Clone, Builder)]
 #[serde(rename_all = "camelCase")]
 #[builder(setter(strip_option))]
 pub struct ListReports {
@@ -16,28 +16,29 @@ pub struct ListReports {
 }
 
 impl_endpoint! {
-    GET ("/reports/reasons/{}", category),
-    #[query] ListReports,
+    GET ("/report/reasons/{}", category),
+    #[no_data auth] ListReports,
     ReportList
 }
 
 /// Create a new report (requires authentication)
 ///
 /// Call to `POST /report`
-#[derive(Debug, Serialize, Clone, Builder)]
-#[builder(setter(strip_option))]
-pub struct CreateReport<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone, Builder)]
+#[builder(setter(into, strip_option))]
+#[serde(rename_all = "camelCase")]
+pub struct CreateReport {
     pub category: ReportCategory,
     /// The reason.
-    pub reason: &'a str,
+    pub reason: String,
     /// The object id.
-    pub object_id: &'a Uuid,
+    pub object_id: Uuid,
     /// The details.
-    pub details: &'a str,
+    pub details: String,
 }
 
 impl_endpoint! {
     POST "/report",
-    #[body auth] CreateReport<'_>,
+    #[body auth] CreateReport,
     #[flatten_result] ReportResponse
 }
@@ -0,0 +1,192 @@
+// This file is generated from `api.yaml` by `cargo xtask codegen`, do not edit it by hand.
+
+//! Upload sessions
+
+use std::borrow::Cow;
+
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::common::Endpoint;
+use crate::common::Multipart;
+use crate::schema::{chapter::ChapterResponse, upload::*, GroupId, MangaId, NoData};
+use crate::Client;
+use crate::Result;
+
+/// Get the current User upload session (requires authentication)
+///
+/// Call to `GET /upload`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GetUploadSession;
+
+impl_endpoint! {
+    GET "/upload",
+    #[no_data auth] GetUploadSession,
+    #[flatten_result] UploadSessionResponse
+}
+
+/// Start an upload session (requires authentication)
+///
+/// Call to `POST /upload/begin`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BeginUploadSession {
+    pub groups: Vec<GroupId>,
+
+    pub manga: MangaId,
+}
+
+impl_endpoint! {
+    POST "/upload/begin",
+    #[body auth] BeginUploadSession,
+    #[flatten_result] UploadSessionResponse
+}
+
+/// Upload images to the upload session (requires authentication)
+///
+/// Call to `POST /upload/{uploadSessionId}`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PutUploadSessionFile {
+    pub upload_session_id: Uuid,
+
+    pub file: Cow<'static, [u8]>,
+}
+
+impl Endpoint for PutUploadSessionFile {
+    type Query = ();
+    type Body = ();
+    type Response = PutUploadSessionFileResponse;
+
+    fn path(&self) -> Cow<'_, str> {
+        Cow::Owned(format!("/upload/{:x}", self.upload_session_id))
+    }
+    fn method(&self) -> Method {
+        Method::POST
+    }
+
+    fn require_auth(&self) -> bool {
+        true
+    }
+
+    fn multipart(&self) -> Option<Multipart> {
+        let form = Multipart::new().bytes("file", self.file.clone());
+        Some(form)
+    }
+}
+
+impl PutUploadSessionFile {
+    /// Send the request
+    pub async fn send(&self, client: &Client) -> PutUploadSessionFileResponse {
+        client.send(self).await?
+    }
+
+    /// Send the request with a blocking client
+    #[cfg(feature = "blocking")]
+    pub fn send_blocking(&self, client: &crate::blocking::Client) -> PutUploadSessionFileResponse {
+        client.send(self)?
+    }
+}
+
+/// Abandon upload session (requires authentication)
+///
+/// Call to `DELETE /upload/{uploadSessionId}`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AbandonUploadSession {
+    pub upload_session_id: Uuid,
+}
+
+impl_endpoint! {
+    DELETE ("/upload/{:x}", upload_session_id),
+    #[no_data auth] AbandonUploadSession,
+    #[discard_result] Result<NoData>
+}
+
+/// Commit the upload session and specify chapter data (requires authentication)
+///
+/// Call to `POST /upload/{uploadSessionId}/commit`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitUploadSession {
+    #[serde(skip_serializing)]
+    pub upload_session_id: Uuid,
+
+    pub chapter_draft: ChapterDraft,
+
+    /// ordered list of Upload Session File ids
+    pub page_order: Vec<Uuid>,
+}
+
+impl_endpoint! {
+    POST ("/upload/{:x}/commit", upload_session_id),
+    #[body auth] CommitUploadSession,
+    #[flatten_result] ChapterResponse
+}
+
+path_ids! { CommitUploadSession { upload_session_id: "uploadSessionId" } }
+
+/// Delete an uploaded image from the Upload Session (requires authentication)
+///
+/// Call to `DELETE /upload/{uploadSessionId}/{uploadSessionFileId}`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteUploadedSessionFile {
+    pub upload_session_id: Uuid,
+
+    pub upload_session_file_id: Uuid,
+}
+
+impl_endpoint! {
+    DELETE ("/upload/{:x}/{:x}", upload_session_id, upload_session_file_id),
+    #[no_data auth] DeleteUploadedSessionFile,
+    #[discard_result] Result<NoData>
+}
+
+/// Delete a set of uploaded images from the Upload Session (requires authentication)
+///
+/// Call to `DELETE /upload/{uploadSessionId}/batch`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteUploadedSessionFiles {
+    pub upload_session_id: Uuid,
+
+    pub ids: Vec<Uuid>,
+}
+
+impl Endpoint for DeleteUploadedSessionFiles {
+    type Query = ();
+    type Body = Vec<Uuid>;
+    type Response = Result<NoData>;
+
+    fn path(&self) -> Cow<'_, str> {
+        Cow::Owned(format!("/upload/{:x}/batch", self.upload_session_id))
+    }
+    fn method(&self) -> Method {
+        Method::DELETE
+    }
+
+    fn require_auth(&self) -> bool {
+        true
+    }
+
+    fn body(&self) -> Option<&Self::Body> {
+        Some(&self.ids)
+    }
+}
+
+impl DeleteUploadedSessionFiles {
+    /// Send the request
+    pub async fn send(&self, client: &Client) -> Result<()> {
+        client.send(self).await??;
+        Ok(())
+    }
+
+    /// Send the request with a blocking client
+    #[cfg(feature = "blocking")]
+    pub fn send_blocking(&self, client: &crate::blocking::Client) -> Result<()> {
+        client.send(self)??;
+        Ok(())
+    }
+}
@@ -1,18 +1,18 @@
 //! User data
 
 use derive_builder::Builder;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::schema::{group::ScanlationGroupList, manga::MangaList, user::*, NoData};
+use crate::schema::{group::ScanlationGroupList, manga::MangaList, user::*, NoData, UserId};
 use crate::Result;
 
 /// List users (requires authentication)
 ///
 /// Call to `GET /user`
-#[derive(Debug, Serialize, Clone, Builder, Default)]
-#[builder(setter(strip_option), default)]
-pub struct ListUsers<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone, Builder, Default)]
+#[builder(setter(into, strip_option), default)]
+pub struct ListUsers {
     /// Page size
     pub limit: Option<i32>,
 
@@ -22,10 +22,10 @@ pub struct ListUsers<'a> {
     /// User ids
     #[builder(setter(each = "add_user"))]
     #[serde(rename = "ids")]
-    pub user_ids: Vec<&'a Uuid>,
+    pub user_ids: Vec<UserId>,
 
     /// Username
-    pub username: Option<&'a str>,
+    pub username: Option<String>,
 
     /// Sort order
     pub order: Option<UserOrder>,
@@ -33,87 +33,88 @@ pub struct ListUsers<'a> {
 
 impl_endpoint! {
     GET "/user",
-    #[query auth] ListUsers<'_>,
+    #[query auth] ListUsers,
     UserList
 }
 
 /// Get user
 ///
 /// Call to `GET /user/{user_id}`
-#[derive(Debug, Clone)]
-pub struct GetUser<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GetUser {
     /// The user id.
-    pub user_id: &'a Uuid,
+    pub user_id: UserId,
 }
 
 impl_endpoint! {
     GET ("/user/{:x}", user_id),
-    #[no_data] GetUser<'_>,
+    #[no_data] GetUser,
     #[flatten_result] UserResponse
 }
 
 /// Delete user
 ///
 /// Call to `DELETE /user/{user_id}`
-#[derive(Debug, Clone)]
-pub struct DeleteUser<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DeleteUser {
     /// The user id.
-    pub user_id: &'a Uuid,
+    pub user_id: UserId,
 }
 
 impl_endpoint! {
     DELETE ("/user/{:x}", user_id),
-    #[no_data auth] DeleteUser<'_>,
+    #[no_data auth] DeleteUser,
     #[discard_result] Result<NoData>
 }
 
 /// Approve user deletion
 ///
 /// Call to `POST /user/delete/{code}`
-#[derive(Debug, Clone)]
-pub struct ApproveUserDeletion<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ApproveUserDeletion {
     /// The code.
-    pub code: &'a Uuid,
+    pub code: Uuid,
 }
 
 impl_endpoint! {
     POST ("/user/delete/{:x}", code),
-    #[no_data auth] ApproveUserDeletion<'_>,
+    #[no_data] ApproveUserDeletion,
     #[discard_result] Result<NoData>
 }
 
 /// Update user password
 ///
 /// Call to `POST /user/password`
-#[derive(Debug, Serialize, Clone)]
-pub struct UpdatePassword<'a> {
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdatePassword {
     /// The old password.
-    pub old_password: &'a str,
+    pub old_password: String,
     /// The new password.
-    pub new_password: &'a str,
+    pub new_password: String,
 }
 
 impl_endpoint! {
     POST "/user/password",
-    #[body auth] UpdatePassword<'_>,
+    #[body auth] UpdatePassword,
     #[discard_result] Result<NoData>
 }
 
 /// Update user email
 ///
 /// Call to `POST /user/email`
-#[derive(Debug, Serialize, Clone)]
-pub struct UpdateEmail<'a> {
-    pub email: &'a str,
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UpdateEmail {
+    pub email: String,
 }
 
 impl_endpoint! {
     POST "/user/email",
-    #[body auth] UpdateEmail<'_>,
+    #[body auth] UpdateEmail,
     #[discard_result] Result<NoData>
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GetLoggedUser;
 
 impl_endpoint! {
@@ -125,7 +126,7 @@ impl_endpoint! {
 /// Get logged user's followed groups (requires authentication)
 ///
 /// Call to `GET /user/follows/group`
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ListFollowedGroups {
     /// Page size
     pub limit: Option<i32>,
@@ -143,7 +144,7 @@ impl_endpoint! {
 /// Get logged user's followed users (requires authentication)
 ///
 /// Call to `GET /user/follows/user`
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ListFollowedUsers {
     /// Page size
     pub limit: Option<i32>,
@@ -161,7 +162,7 @@ impl_endpoint! {
 /// Get logged user followed manga list (requires authentication)
 ///
 /// Call to `GET /usr/follows/manga`
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ListFollowedManga {
     /// Page size
     pub limit: Option<i32>,
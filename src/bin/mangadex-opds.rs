@@ -0,0 +1,52 @@
+//! Serve an OPDS catalog of MangaDex to the reader apps of the local network.
+//!
+//! Usage: `mangadex-opds [ADDRESS]`, listening on `0.0.0.0:8080` by default.
+//!
+//! Environment variables:
+//!
+//! * `MANGADEX_USERNAME` and `MANGADEX_PASSWORD`: an account, to browse its followed manga and
+//!   custom lists,
+//! * `MANGADEX_LANGUAGES`: the preferred languages of titles and chapters, e.g. `en,ja-ro`
+//!   (English by default),
+//! * `MANGADEX_DATA_SAVER`: set to `1` to serve compressed pages.
+
+use std::env;
+use std::error::Error;
+use std::net::SocketAddr;
+
+use mangadex::opds::OpdsServer;
+use mangadex::schema::LanguageCode;
+use mangadex::Client;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let addr: SocketAddr = env::args()
+        .nth(1)
+        .as_deref()
+        .unwrap_or("0.0.0.0:8080")
+        .parse()?;
+
+    let mut builder = Client::builder();
+    if let Ok(codes) = env::var("MANGADEX_LANGUAGES") {
+        let mut languages = Vec::new();
+        for code in codes.split(',').filter(|code| !code.is_empty()) {
+            languages.push(code.trim().parse::<LanguageCode>()?);
+        }
+        builder = builder.preferred_languages(languages);
+    }
+
+    let mut client = builder.build()?;
+    if let (Ok(username), Ok(password)) =
+        (env::var("MANGADEX_USERNAME"), env::var("MANGADEX_PASSWORD"))
+    {
+        client.login(&username, &password).await?;
+    }
+
+    let data_saver = env::var("MANGADEX_DATA_SAVER").is_ok_and(|value| value == "1");
+    eprintln!("Serving the catalog at http://{}/opds", addr);
+    OpdsServer::new(client)
+        .data_saver(data_saver)
+        .serve(addr)
+        .await?;
+    Ok(())
+}
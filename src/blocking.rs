@@ -0,0 +1,328 @@
+//! A blocking client, for programs that don't run an async runtime.
+//!
+//! Every request type in [`api`][crate::api] provides a `send_blocking()` method, mirroring its
+//! `send()` method, which takes a [`blocking::Client`][Client] instead.
+//!
+//! ```rust,no_run
+//! use mangadex::api::manga::ListManga;
+//! use mangadex::blocking::Client;
+//!
+//! # fn main() -> mangadex::Result<()> {
+//! let client = Client::default();
+//! let mangas = ListManga::default().send_blocking(&client)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! This module is only available with the `blocking` feature.
+
+use std::sync::Arc;
+
+use reqwest::blocking::{RequestBuilder, Response};
+use reqwest::{Method, Url};
+
+use crate::{
+    api::auth::{Login, Logout, RefreshToken},
+    client::API_URL,
+    errors::{Errors, Result},
+    policy::{ContentPolicy, RateLimiter, RetryPolicy},
+    schema::{
+        auth::{AuthTokens, RefreshTokenResponse},
+        LanguageCode,
+    },
+    Endpoint, FromResponse, UrlSerdeQS,
+};
+
+/// The blocking client used to talk to the api.
+///
+/// It is configured with the same [`ClientBuilder`][crate::ClientBuilder] as the async client,
+/// using [`ClientBuilder::build_blocking()`][crate::ClientBuilder::build_blocking()].
+#[derive(Debug, Clone)]
+pub struct Client {
+    pub(crate) http: reqwest::blocking::Client,
+    pub(crate) base_url: Url,
+    pub(crate) uploads_url: Url,
+    pub(crate) at_home_url: Url,
+    pub(crate) tokens: Option<AuthTokens>,
+    pub(crate) rate_limiter: Option<Arc<RateLimiter>>,
+    pub(crate) retry_policy: RetryPolicy,
+    pub(crate) preferred_languages: Vec<LanguageCode>,
+    pub(crate) content_policy: Option<Arc<ContentPolicy>>,
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new(API_URL).expect("Error creating default API client")
+    }
+}
+
+impl Client {
+    /// Create a new client.
+    pub fn new(base_url: &str) -> Result<Self> {
+        crate::Client::builder().base_url(base_url).build_blocking()
+    }
+
+    /// Get the base url of the api.
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+
+    /// Get the base url of the uploads server.
+    pub fn uploads_url(&self) -> &Url {
+        &self.uploads_url
+    }
+
+    /// Get the base url of the MangaDex@Home network api.
+    pub fn at_home_url(&self) -> &Url {
+        &self.at_home_url
+    }
+
+    /// Get the underlying http client.
+    pub fn http_client(&self) -> &reqwest::blocking::Client {
+        &self.http
+    }
+
+    /// Get the languages to display localized strings in, by order of preference.
+    pub fn preferred_languages(&self) -> &[LanguageCode] {
+        &self.preferred_languages
+    }
+
+    /// Get the content policy, if one is configured.
+    pub fn content_policy(&self) -> Option<&ContentPolicy> {
+        self.content_policy.as_deref()
+    }
+
+    fn build_request<E>(&self, endpoint: &E) -> Result<RequestBuilder>
+    where
+        E: Endpoint,
+    {
+        let mut endpoint_url = self.base_url.join(&endpoint.path())?;
+        if let Some(query) = endpoint.query() {
+            endpoint_url = endpoint_url.query_qs(query);
+        }
+        if let Some(policy) = &self.content_policy {
+            if endpoint.method() == Method::GET {
+                policy.apply_query(&endpoint.path(), &mut endpoint_url);
+            }
+        }
+
+        let mut req = self.http.request(endpoint.method(), endpoint_url);
+        if let Some(body) = endpoint.body() {
+            req = req.json(body);
+        }
+
+        if let Some(multipart) = endpoint.multipart() {
+            req = req.multipart(multipart.blocking_form());
+        }
+
+        if let Some(tokens) = self.get_tokens() {
+            req = req.bearer_auth(&tokens.session);
+        } else if endpoint.require_auth() {
+            return Err(Errors::MissingTokens);
+        }
+
+        Ok(req)
+    }
+
+    /// Wait until the rate limit allows sending another request.
+    fn throttle(&self) {
+        if let Some(limiter) = &self.rate_limiter {
+            let delay = limiter.reserve();
+            if !delay.is_zero() {
+                std::thread::sleep(delay);
+            }
+        }
+    }
+
+    /// Send a request, retrying it according to the retry policy.
+    fn execute(
+        &self,
+        method: &Method,
+        mut build: impl FnMut() -> Result<RequestBuilder>,
+    ) -> Result<Response> {
+        let mut attempt = 0;
+        loop {
+            let req = build()?;
+            self.throttle();
+
+            let res = req.send();
+            let retry = attempt < self.retry_policy.max_retries
+                && RetryPolicy::is_retryable_method(method)
+                && match &res {
+                    Ok(res) => RetryPolicy::is_retryable_status(res.status()),
+                    Err(err) => RetryPolicy::is_retryable_error(err),
+                };
+
+            if !retry {
+                return Ok(res?);
+            }
+
+            let headers = res.as_ref().ok().map(|res| res.headers());
+            std::thread::sleep(self.retry_policy.delay(attempt, headers));
+            attempt += 1;
+        }
+    }
+
+    /// Send a request to an endpoint.
+    ///
+    /// This is the blocking equivalent of [`Client::send()`][crate::Client::send()].
+    /// Middlewares are not supported by the blocking client.
+    pub fn send<E>(&self, endpoint: &E) -> Result<E::Response>
+    where
+        E: Endpoint,
+    {
+        let res = self.execute(&endpoint.method(), || self.build_request(endpoint))?;
+        let res = match &self.content_policy {
+            Some(policy)
+                if endpoint.method() == Method::GET && policy.filters(&endpoint.path()) =>
+            {
+                let mut body = res.json::<serde_json::Value>()?;
+                policy.filter_body(&mut body);
+                serde_json::from_value(body)?
+            }
+            _ => res.json::<<E::Response as FromResponse>::Response>()?,
+        };
+
+        Ok(FromResponse::from_response(res))
+    }
+
+    /// Login
+    ///
+    /// * `username` - Should be between [1, 64] characters.
+    /// * `password` - Should be between [8, 1024] characters.
+    pub fn login(&mut self, username: &str, password: &str) -> Result<&AuthTokens> {
+        let tokens = Login {
+            username: username.to_string(),
+            password: password.to_string(),
+        }
+        .send_blocking(self)?
+        .tokens;
+
+        self.set_tokens(Some(tokens));
+        Ok(self.get_tokens().unwrap())
+    }
+
+    /// Get the tokens used for authentication
+    pub fn get_tokens(&self) -> Option<&AuthTokens> {
+        self.tokens.as_ref()
+    }
+
+    /// Set the tokens used for authentication.
+    pub fn set_tokens(&mut self, tokens: Option<AuthTokens>) {
+        self.tokens = tokens;
+    }
+
+    /// Logout
+    pub fn logout(&mut self) -> Result<()> {
+        Logout.send_blocking(self)?;
+
+        self.set_tokens(None);
+        Ok(())
+    }
+
+    /// Refresh token endpoint
+    pub fn refresh_tokens(&mut self) -> Result<RefreshTokenResponse> {
+        let refresh_token = self
+            .get_tokens()
+            .ok_or(Errors::MissingTokens)?
+            .refresh
+            .clone();
+        let res = RefreshToken { refresh_token }.send_blocking(self)?;
+
+        self.set_tokens(Some(res.tokens.clone()));
+        Ok(res)
+    }
+
+    /// Ping the api server
+    pub fn ping(&self) -> Result<()> {
+        let endpoint = self.base_url.join("/ping")?;
+
+        let res = self.execute(&Method::GET, || Ok(self.http.get(endpoint.clone())))?;
+        if res.text()? == "pong" {
+            Ok(())
+        } else {
+            Err(Errors::PingError)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::auth::CheckToken;
+    use crate::api::legacy::LegacyMapping;
+    use crate::schema::legacy::MappingType;
+    use httpmock::Method::{GET, POST};
+    use httpmock::MockServer;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn login_and_send() -> anyhow::Result<()> {
+        let server = MockServer::start();
+
+        let login = server.mock(|when, then| {
+            when.method(POST)
+                .path("/auth/login")
+                .json_body(json!({"username": "test", "password": "hunter1"}));
+            then.status(200)
+                .header("Content-Type", "application/json")
+                .json_body(json!({
+                    "result": "ok",
+                    "token": {
+                        "session": "sessiontoken",
+                        "refresh": "refreshtoken",
+                    }
+                }));
+        });
+
+        let check = server.mock(|when, then| {
+            when.method(GET)
+                .path("/auth/check")
+                .header("Authorization", "Bearer sessiontoken");
+            then.status(200)
+                .header("Content-Type", "application/json")
+                .json_body(json!({
+                    "result": "ok",
+                    "isAuthenticated": true,
+                    "roles": ["ROLE_MEMBER"],
+                    "permissions": [],
+                }));
+        });
+
+        let mut client = Client::new(&server.base_url())?;
+        client.login("test", "hunter1")?;
+        let info = CheckToken.send_blocking(&client)?;
+
+        login.assert();
+        check.assert();
+        assert!(info.is_authenticated);
+        assert_eq!(info.roles, vec!["ROLE_MEMBER".to_string()]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn send_list() -> anyhow::Result<()> {
+        let server = MockServer::start();
+
+        let mock = server.mock(|when, then| {
+            when.method(POST).path("/legacy/mapping");
+            then.status(200)
+                .header("Content-Type", "application/json")
+                .json_body(json!([]));
+        });
+
+        let client = Client::new(&server.base_url())?;
+        let mappings = LegacyMapping {
+            r#type: MappingType::Manga,
+            ids: vec![1],
+        }
+        .send_blocking(&client)?;
+
+        mock.assert();
+        assert!(mappings.is_empty());
+
+        Ok(())
+    }
+}
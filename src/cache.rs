@@ -0,0 +1,801 @@
+//! Response caching for read endpoints.
+//!
+//! A [`ResponseCache`] is configured on the [`ClientBuilder`][crate::ClientBuilder] and stores the
+//! successful responses of the public read endpoints of the api: manga, chapters, covers, authors,
+//! groups and tags. User data (`/user`, reading statuses, read markers) is never cached unless
+//! opted in with [`ResponseCache::allow()`]. Entries are keyed by the full request url and the
+//! identity of the authenticated user, so responses are never shared between sessions.
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use mangadex::cache::{MemoryCache, ResponseCache};
+//! use mangadex::Client;
+//!
+//! # fn main() -> mangadex::Result<()> {
+//! let cache = ResponseCache::new(MemoryCache::new(1024))
+//!     .default_ttl(Duration::from_secs(60))
+//!     .ttl("/manga/tag", Duration::from_secs(24 * 60 * 60));
+//!
+//! let client = Client::builder().cache(cache).build()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The lifetime of an entry is taken from the `Cache-Control: max-age` header of the response if
+//! present, and from the configured ttl otherwise. Responses with `Cache-Control: no-store` are
+//! never cached. Expired entries that carry an `ETag` are revalidated with `If-None-Match`, and
+//! reused if the server answers `304 Not Modified`.
+//!
+//! When a mutating call (`POST`, `PUT`, `DELETE`) succeeds, every entry whose url contains one of
+//! the ids in the path of the call is dropped, along with the listings that can contain them: the
+//! listing of the resource for a call on an object (`PUT /manga/{id}` drops the `/manga`
+//! listings), and the listing of the action for a call on a part of an object
+//! (`POST /manga/{id}/status` drops `/manga/status`, following a manga drops
+//! `/user/follows/manga`). Chapter edits and uploads also drop the feeds and aggregates.
+//!
+//! The cache is best-effort: storage failures are ignored and the request goes to the network.
+//! It is only used by the async [`Client`][crate::Client].
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use reqwest::header::{HeaderName, CACHE_CONTROL, CONTENT_TYPE, ETAG};
+use reqwest::{Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use url::Url;
+use uuid::Uuid;
+
+use crate::schema::auth::AuthTokens;
+
+/// A cached response.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheEntry {
+    /// Status code of the response.
+    pub status: u16,
+    /// Content type of the response.
+    pub content_type: Option<String>,
+    /// Entity tag of the response, used to revalidate the entry.
+    pub etag: Option<String>,
+    /// The response body.
+    pub body: String,
+    /// When the entry must be revalidated.
+    pub expires_at: SystemTime,
+}
+
+impl CacheEntry {
+    /// Whether the entry can be used without asking the server.
+    pub fn is_fresh(&self) -> bool {
+        SystemTime::now() < self.expires_at
+    }
+
+    /// Build a response from the entry.
+    pub(crate) fn to_response(&self) -> Response {
+        let mut res = http::Response::builder().status(self.status);
+        if let Some(content_type) = &self.content_type {
+            res = res.header(CONTENT_TYPE, content_type);
+        }
+        if let Some(etag) = &self.etag {
+            res = res.header(ETAG, etag);
+        }
+
+        res.body(self.body.clone())
+            .expect("cached response is valid")
+            .into()
+    }
+}
+
+/// A storage backend for a [`ResponseCache`].
+pub trait CacheStore: Send + Sync + 'static {
+    /// Get an entry, fresh or not.
+    fn get(&self, key: &str) -> Option<CacheEntry>;
+
+    /// Insert or replace an entry.
+    fn put(&self, key: &str, entry: CacheEntry);
+
+    /// Remove every entry whose key `matches`.
+    fn remove_where(&self, matches: &dyn Fn(&str) -> bool);
+
+    /// Remove every entry whose key contains `pattern`.
+    fn remove_matching(&self, pattern: &str) {
+        self.remove_where(&|key| key.contains(pattern));
+    }
+
+    /// Remove every entry.
+    fn clear(&self);
+}
+
+/// An in-memory store, evicting the least recently used entries once full.
+#[derive(Debug)]
+pub struct MemoryCache {
+    capacity: usize,
+    inner: Mutex<MemoryCacheInner>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryCacheInner {
+    tick: u64,
+    entries: HashMap<String, (CacheEntry, u64)>,
+    /// Keys by last access.
+    recency: BTreeMap<u64, String>,
+}
+
+impl MemoryCacheInner {
+    fn touch(&mut self, key: &str) {
+        self.tick += 1;
+        let tick = self.tick;
+        if let Some((_, last)) = self.entries.get_mut(key) {
+            self.recency.remove(last);
+            *last = tick;
+            self.recency.insert(tick, key.to_string());
+        }
+    }
+}
+
+impl MemoryCache {
+    /// Create a store holding at most `capacity` entries.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Mutex::new(MemoryCacheInner::default()),
+        }
+    }
+
+    /// Number of entries in the store.
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Whether the store is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MemoryCacheInner> {
+        self.inner.lock().expect("cache lock poisoned")
+    }
+}
+
+impl CacheStore for MemoryCache {
+    fn get(&self, key: &str) -> Option<CacheEntry> {
+        let mut inner = self.lock();
+        inner.touch(key);
+        inner.entries.get(key).map(|(entry, _)| entry.clone())
+    }
+
+    fn put(&self, key: &str, entry: CacheEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut inner = self.lock();
+        if let Some((old, _)) = inner.entries.get_mut(key) {
+            *old = entry;
+            inner.touch(key);
+            return;
+        }
+
+        while inner.entries.len() >= self.capacity {
+            let oldest = match inner.recency.keys().next() {
+                Some(&tick) => tick,
+                None => break,
+            };
+            if let Some(key) = inner.recency.remove(&oldest) {
+                inner.entries.remove(&key);
+            }
+        }
+
+        inner.entries.insert(key.to_string(), (entry, 0));
+        inner.touch(key);
+    }
+
+    fn remove_where(&self, matches: &dyn Fn(&str) -> bool) {
+        let mut inner = self.lock();
+        let MemoryCacheInner {
+            entries, recency, ..
+        } = &mut *inner;
+        entries.retain(|key, (_, tick)| {
+            let keep = !matches(key);
+            if !keep {
+                recency.remove(tick);
+            }
+            keep
+        });
+    }
+
+    fn clear(&self) {
+        let mut inner = self.lock();
+        inner.entries.clear();
+        inner.recency.clear();
+    }
+}
+
+/// A store keeping one json file per entry in a directory.
+///
+/// The directory is created when the first entry is stored. Entries survive restarts, so the
+/// store can be shared between runs of a program.
+#[derive(Debug, Clone)]
+pub struct FileCache {
+    dir: PathBuf,
+}
+
+#[derive(Serialize, Deserialize)]
+struct FileEntry {
+    key: String,
+    entry: CacheEntry,
+}
+
+impl FileCache {
+    /// Create a store in the given directory.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir
+            .join(format!("{:016x}.json", fnv1a(key.as_bytes())))
+    }
+
+    fn read(path: &std::path::Path) -> Option<FileEntry> {
+        let data = fs::read(path).ok()?;
+        serde_json::from_slice(&data).ok()
+    }
+
+    fn files(&self) -> impl Iterator<Item = PathBuf> {
+        fs::read_dir(&self.dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+    }
+}
+
+impl CacheStore for FileCache {
+    fn get(&self, key: &str) -> Option<CacheEntry> {
+        Self::read(&self.path(key))
+            .filter(|file| file.key == key)
+            .map(|file| file.entry)
+    }
+
+    fn put(&self, key: &str, entry: CacheEntry) {
+        let file = FileEntry {
+            key: key.to_string(),
+            entry,
+        };
+        if let Ok(data) = serde_json::to_vec(&file) {
+            let _ = fs::create_dir_all(&self.dir).and_then(|_| fs::write(self.path(key), data));
+        }
+    }
+
+    fn remove_where(&self, matches: &dyn Fn(&str) -> bool) {
+        for path in self.files() {
+            if Self::read(&path).is_some_and(|file| matches(&file.key)) {
+                let _ = fs::remove_file(path);
+            }
+        }
+    }
+
+    fn clear(&self) {
+        for path in self.files() {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// The endpoints cached by default, see [`ResponseCache::allow()`] for the syntax.
+const PUBLIC_ENDPOINTS: &[&str] = &[
+    "/author",
+    "/author/{id}",
+    "/chapter",
+    "/chapter/{id}",
+    "/cover",
+    "/cover/{id}",
+    "/group",
+    "/group/{id}",
+    "/manga",
+    "/manga/{id}",
+    "/manga/{id}/aggregate",
+    "/manga/{id}/feed",
+    "/manga/tag",
+];
+
+/// Caching configuration of a [`Client`][crate::Client].
+///
+/// By default the public read endpoints are cached (see the [module docs][self]), and entries
+/// live for 5 minutes.
+#[derive(Clone)]
+pub struct ResponseCache {
+    store: Arc<dyn CacheStore>,
+    default_ttl: Duration,
+    endpoints: Vec<String>,
+    ttls: Vec<(String, Duration)>,
+}
+
+impl ResponseCache {
+    /// Create a cache using the given store.
+    pub fn new(store: impl CacheStore) -> Self {
+        Self {
+            store: Arc::new(store),
+            default_ttl: Duration::from_secs(5 * 60),
+            endpoints: PUBLIC_ENDPOINTS.iter().map(|e| e.to_string()).collect(),
+            ttls: Vec::new(),
+        }
+    }
+
+    /// Also cache the endpoints matching `pattern`, a path where `{id}` stands for any id.
+    ///
+    /// For example `/user/follows/manga` caches the followed manga of the logged in user, and
+    /// `/list/{id}` caches custom lists.
+    pub fn allow(mut self, pattern: &str) -> Self {
+        self.endpoints.push(pattern.to_string());
+        self
+    }
+
+    /// Set the lifetime of entries without a more specific ttl.
+    pub fn default_ttl(mut self, ttl: Duration) -> Self {
+        self.default_ttl = ttl;
+        self
+    }
+
+    /// Set the lifetime of the entries of cached endpoints whose path starts with `prefix`.
+    ///
+    /// The longest matching prefix wins. A ttl of zero disables caching for those endpoints.
+    pub fn ttl(mut self, prefix: &str, ttl: Duration) -> Self {
+        self.ttls.retain(|(p, _)| p != prefix);
+        self.ttls.push((prefix.to_string(), ttl));
+        self
+    }
+
+    /// Get the store backing the cache.
+    pub fn store(&self) -> &dyn CacheStore {
+        &*self.store
+    }
+
+    /// Drop every entry related to a resource.
+    pub fn invalidate(&self, id: &Uuid) {
+        self.store
+            .remove_matching(&id.to_hyphenated_ref().to_string());
+    }
+
+    /// Drop every entry.
+    pub fn clear(&self) {
+        self.store.clear();
+    }
+
+    /// The ttl of an endpoint, or `None` if it isn't cached.
+    pub(crate) fn ttl_for(&self, path: &str) -> Option<Duration> {
+        if !self.endpoints.iter().any(|pattern| matches(pattern, path)) {
+            return None;
+        }
+
+        let ttl = self
+            .ttls
+            .iter()
+            .filter(|(prefix, _)| path.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.default_ttl, |(_, ttl)| *ttl);
+
+        Some(ttl).filter(|ttl| !ttl.is_zero())
+    }
+
+    /// The key of a request.
+    pub(crate) fn key(url: &str, tokens: Option<&AuthTokens>) -> String {
+        match tokens {
+            Some(tokens) => format!("{:016x} {}", fnv1a(tokens.session.as_bytes()), url),
+            None => format!("- {}", url),
+        }
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<CacheEntry> {
+        self.store.get(key)
+    }
+
+    /// Store a successful response, returning an equivalent response to hand to the caller.
+    pub(crate) async fn put(
+        &self,
+        key: &str,
+        res: Response,
+        ttl: Duration,
+    ) -> reqwest::Result<Response> {
+        if !res.status().is_success() {
+            return Ok(res);
+        }
+
+        let header = |name: HeaderName| {
+            res.headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+        };
+        let ttl = match cache_control(header(CACHE_CONTROL).as_deref()) {
+            CacheControl::NoStore => return Ok(res),
+            CacheControl::MaxAge(max_age) => max_age,
+            CacheControl::Default => ttl,
+        };
+
+        let entry = CacheEntry {
+            status: res.status().as_u16(),
+            content_type: header(CONTENT_TYPE),
+            etag: header(ETAG),
+            body: res.text().await?,
+            expires_at: SystemTime::now() + ttl,
+        };
+        if ttl > Duration::ZERO || entry.etag.is_some() {
+            self.store.put(key, entry.clone());
+        }
+
+        Ok(entry.to_response())
+    }
+
+    /// Mark a revalidated entry as fresh again, returning its response.
+    pub(crate) fn refresh(&self, key: &str, mut entry: CacheEntry, ttl: Duration) -> Response {
+        entry.expires_at = SystemTime::now() + ttl;
+        let res = entry.to_response();
+        self.store.put(key, entry);
+        res
+    }
+
+    /// Drop the entries of the ids in the path of a successful mutating call, and the listings
+    /// it can change.
+    pub(crate) fn invalidate_path(&self, path: &str, status: StatusCode) {
+        if !status.is_success() {
+            return;
+        }
+
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        let ids: Vec<String> = segments
+            .iter()
+            .filter_map(|s| Uuid::parse_str(s).ok())
+            .map(|id| id.to_hyphenated_ref().to_string())
+            .collect();
+        let listings = listings(&segments);
+        // New or edited chapters change the feeds and aggregates of their manga and lists.
+        let chapters = matches!(
+            segments.as_slice(),
+            ["chapter"] | ["chapter", _] | ["upload", ..]
+        );
+
+        self.store.remove_where(&|key| {
+            if ids.iter().any(|id| key.contains(id.as_str())) {
+                return true;
+            }
+            let path = key_path(key);
+            listings.contains(&path)
+                || (chapters && (path.ends_with("/feed") || path.ends_with("/aggregate")))
+        });
+    }
+}
+
+/// The listings a mutating call can change: the listing of the resource for a call on an object
+/// (`PUT /manga/{id}` changes `/manga`), and the listing of the action for a call on a part of an
+/// object (`POST /manga/{id}/status` changes `/manga/status`).
+fn listings(segments: &[&str]) -> Vec<String> {
+    match segments {
+        [] => Vec::new(),
+        ["upload", ..] => vec!["/chapter".to_string()],
+        [resource] | [resource, _] => vec![format!("/{}", resource)],
+        [resource, _, "follow"] => vec![
+            format!("/user/follows/{}", resource),
+            format!("/user/follows/{}/feed", resource),
+        ],
+        [resource, _, action, ..] => vec![format!("/{}/{}", resource, action)],
+    }
+}
+
+/// The path of the url in a cache key.
+fn key_path(key: &str) -> String {
+    key.rsplit(' ')
+        .next()
+        .and_then(|url| Url::parse(url).ok())
+        .map(|url| url.path().to_string())
+        .unwrap_or_default()
+}
+
+impl fmt::Debug for ResponseCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseCache")
+            .field("default_ttl", &self.default_ttl)
+            .field("endpoints", &self.endpoints)
+            .field("ttls", &self.ttls)
+            .finish()
+    }
+}
+
+/// Whether a path matches an endpoint pattern, `{id}` matching a single uuid segment.
+fn matches(pattern: &str, path: &str) -> bool {
+    let mut pattern = pattern.split('/');
+    let mut path = path.split('/');
+    loop {
+        match (pattern.next(), path.next()) {
+            (None, None) => return true,
+            (Some("{id}"), Some(segment)) if Uuid::parse_str(segment).is_ok() => {}
+            (Some(expected), Some(segment)) if expected == segment => {}
+            _ => return false,
+        }
+    }
+}
+
+enum CacheControl {
+    Default,
+    NoStore,
+    MaxAge(Duration),
+}
+
+fn cache_control(header: Option<&str>) -> CacheControl {
+    let directives = header.into_iter().flat_map(|h| h.split(',')).map(str::trim);
+
+    let mut control = CacheControl::Default;
+    for directive in directives {
+        let directive = directive.to_ascii_lowercase();
+        if directive == "no-store" {
+            return CacheControl::NoStore;
+        } else if directive == "no-cache" {
+            control = CacheControl::MaxAge(Duration::ZERO);
+        } else if let Some(secs) = directive.strip_prefix("max-age=") {
+            if let Ok(secs) = secs.trim_matches('"').parse() {
+                control = CacheControl::MaxAge(Duration::from_secs(secs));
+            }
+        }
+    }
+    control
+}
+
+/// 64 bit FNV-1a, a hash that is stable between builds (unlike `DefaultHasher`).
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::manga::{AllMangaStatus, GetMangaReadMarkers, UpdateMangaStatus};
+    use crate::schema::manga::MangaReadingStatus;
+    use crate::Client;
+    use httpmock::Method::{GET, POST};
+    use httpmock::MockServer;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    const MANGA_ID: &str = "32d76d19-8a05-4db0-9fc2-e0b0648fe9d0";
+
+    fn client(server: &MockServer) -> crate::Result<Client> {
+        let mut client = Client::builder()
+            .base_url(&server.base_url())
+            .cache(
+                ResponseCache::new(MemoryCache::new(16))
+                    .allow("/manga/{id}/read")
+                    .allow("/manga/status"),
+            )
+            .build()?;
+        client.set_tokens(Some(AuthTokens {
+            session: "sessiontoken".to_string(),
+            refresh: "refreshtoken".to_string(),
+        }));
+        Ok(client)
+    }
+
+    fn read_markers() -> GetMangaReadMarkers {
+        GetMangaReadMarkers {
+            manga_id: MANGA_ID.parse().unwrap(),
+        }
+    }
+
+    fn entry(body: &str) -> CacheEntry {
+        CacheEntry {
+            status: 200,
+            content_type: Some("application/json".to_string()),
+            etag: None,
+            body: body.to_string(),
+            expires_at: SystemTime::now() + Duration::from_secs(60),
+        }
+    }
+
+    #[test]
+    fn memory_cache_evicts_least_recently_used() {
+        let cache = MemoryCache::new(2);
+        cache.put("a", entry("a"));
+        cache.put("b", entry("b"));
+        assert!(cache.get("a").is_some());
+
+        cache.put("c", entry("c"));
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("b").is_none());
+        assert_eq!(cache.get("a").map(|e| e.body), Some("a".to_string()));
+        assert!(cache.get("c").is_some());
+    }
+
+    #[test]
+    fn file_cache_round_trip() {
+        let dir = std::env::temp_dir().join(format!("mangadex-cache-{}", std::process::id()));
+        let cache = FileCache::new(&dir);
+        let first = entry("first");
+
+        cache.put("- https://api.mangadex.org/manga/1", first.clone());
+        cache.put("- https://api.mangadex.org/manga/2", entry("second"));
+        assert_eq!(cache.get("- https://api.mangadex.org/manga/1"), Some(first));
+
+        cache.remove_matching("/manga/1");
+        assert_eq!(cache.get("- https://api.mangadex.org/manga/1"), None);
+        assert!(cache.get("- https://api.mangadex.org/manga/2").is_some());
+
+        cache.clear();
+        assert_eq!(cache.get("- https://api.mangadex.org/manga/2"), None);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn cache_and_invalidate() -> anyhow::Result<()> {
+        let server = MockServer::start_async().await;
+
+        let read = server
+            .mock_async(|when, then| {
+                when.method(GET).path(format!("/manga/{}/read", MANGA_ID));
+                then.status(200)
+                    .header("Content-Type", "application/json")
+                    .json_body(json!({ "result": "ok", "data": [] }));
+            })
+            .await;
+        let statuses = server
+            .mock_async(|when, then| {
+                when.method(GET).path("/manga/status");
+                then.status(200)
+                    .header("Content-Type", "application/json")
+                    .json_body(json!({ "result": "ok", "statuses": {} }));
+            })
+            .await;
+        let update = server
+            .mock_async(|when, then| {
+                when.method(POST)
+                    .path(format!("/manga/{}/status", MANGA_ID));
+                then.status(200)
+                    .header("Content-Type", "application/json")
+                    .json_body(json!({ "result": "ok" }));
+            })
+            .await;
+
+        let client = client(&server)?;
+        let all_statuses = AllMangaStatus { status: None };
+        read_markers().send(&client).await?;
+        read_markers().send(&client).await?;
+        all_statuses.send(&client).await?;
+        all_statuses.send(&client).await?;
+        assert_eq!(read.hits_async().await, 1);
+        assert_eq!(statuses.hits_async().await, 1);
+
+        UpdateMangaStatus {
+            id: MANGA_ID.parse()?,
+            status: MangaReadingStatus::Reading,
+        }
+        .send(&client)
+        .await?;
+        read_markers().send(&client).await?;
+        all_statuses.send(&client).await?;
+
+        update.assert_async().await;
+        assert_eq!(read.hits_async().await, 2);
+        assert_eq!(statuses.hits_async().await, 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalidate_only_the_mutated_manga_and_its_listings() {
+        const OTHER_ID: &str = "f9c33607-9180-4ba6-b85c-e4b5faee7192";
+        let cache = ResponseCache::new(MemoryCache::new(16));
+        let key = |path: String| format!("- https://api.mangadex.org{}", path);
+        let keys = [
+            key(format!("/manga/{}", MANGA_ID)),
+            key(format!("/manga/{}/feed", MANGA_ID)),
+            key("/manga?title=Yotsuba".to_string()),
+            key(format!("/manga/{}", OTHER_ID)),
+            key(format!("/manga/{}/feed", OTHER_ID)),
+            key("/manga/tag".to_string()),
+            key("/chapter?limit=10".to_string()),
+        ];
+        for key in &keys {
+            cache.store.put(key, entry(key));
+        }
+
+        cache.invalidate_path(&format!("/manga/{}", MANGA_ID), StatusCode::OK);
+
+        let cached: Vec<bool> = keys
+            .iter()
+            .map(|key| cache.store.get(key).is_some())
+            .collect();
+        assert_eq!(cached, [false, false, false, true, true, true, true]);
+    }
+
+    #[tokio::test]
+    async fn revalidate_with_etag() -> anyhow::Result<()> {
+        let server = MockServer::start_async().await;
+        let path = format!("/manga/{}/read", MANGA_ID);
+
+        let first = server
+            .mock_async(|when, then| {
+                when.method(GET).path(&path);
+                then.status(200)
+                    .header("Content-Type", "application/json")
+                    .header("Cache-Control", "no-cache")
+                    .header("ETag", "\"v1\"")
+                    .json_body(json!({ "result": "ok", "data": [MANGA_ID] }));
+            })
+            .await;
+
+        let client = client(&server)?;
+        read_markers().send(&client).await?;
+        first.assert_async().await;
+        first.delete_async().await;
+
+        let revalidate = server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path(&path)
+                    .header("If-None-Match", "\"v1\"");
+                then.status(304);
+            })
+            .await;
+
+        let markers = read_markers().send(&client).await?;
+
+        revalidate.assert_async().await;
+        assert_eq!(markers.data, vec![MANGA_ID.parse()?]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn ttl_for_longest_prefix() {
+        let cache = ResponseCache::new(MemoryCache::new(1))
+            .default_ttl(Duration::from_secs(1))
+            .ttl("/manga", Duration::from_secs(2))
+            .ttl("/manga/tag", Duration::from_secs(3));
+
+        let author = format!("/author/{}", MANGA_ID);
+        let manga = format!("/manga/{}", MANGA_ID);
+        assert_eq!(cache.ttl_for(&author), Some(Duration::from_secs(1)));
+        assert_eq!(cache.ttl_for(&manga), Some(Duration::from_secs(2)));
+        assert_eq!(cache.ttl_for("/manga/tag"), Some(Duration::from_secs(3)));
+        assert_eq!(cache.ttl_for("/manga/random"), None);
+        assert_eq!(cache.ttl_for("/auth/check"), None);
+    }
+
+    #[test]
+    fn only_public_endpoints_by_default() {
+        let cache = ResponseCache::new(MemoryCache::new(1));
+
+        for path in [
+            "/manga",
+            "/manga/{}",
+            "/manga/{}/feed",
+            "/cover/{}",
+            "/group",
+        ] {
+            let path = path.replace("{}", MANGA_ID);
+            assert!(cache.ttl_for(&path).is_some(), "{} not cached", path);
+        }
+        for path in [
+            "/manga/{}/status",
+            "/manga/{}/read",
+            "/manga/status",
+            "/manga/read",
+            "/manga/1",
+            "/user/me",
+            "/user/follows/manga",
+            "/list/{}",
+            "/at-home/server/{}",
+        ] {
+            let path = path.replace("{}", MANGA_ID);
+            assert!(cache.ttl_for(&path).is_none(), "{} cached", path);
+        }
+
+        let cache = cache.allow("/user/follows/manga");
+        assert!(cache.ttl_for("/user/follows/manga").is_some());
+    }
+}
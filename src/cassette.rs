@@ -0,0 +1,485 @@
+//! Record and replay HTTP traffic
+//!
+//! A [`Cassette`] is a [`Middleware`] that records the requests sent by a [`Client`] and the
+//! responses of the api to a json file, and serves them back later without touching the network.
+//! This makes it possible to test code using this crate against real MangaDex traffic, offline.
+//!
+//! ```rust,no_run
+//! use mangadex::api::manga::RandomManga;
+//! use mangadex::cassette::Cassette;
+//! use mangadex::Client;
+//!
+//! # async fn run() -> mangadex::Result<()> {
+//! // Talks to the api and writes the traffic to `random.json`.
+//! let client = Client::builder()
+//!     .middleware(Cassette::record("tests/cassettes/random.json"))
+//!     .build()?;
+//! RandomManga.send(&client).await?;
+//!
+//! // Serves the recorded response, without network access.
+//! let client = Client::builder()
+//!     .middleware(Cassette::replay("tests/cassettes/random.json")?)
+//!     .build()?;
+//! RandomManga.send(&client).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Requests are matched by method, path, query and body, regardless of the base url of the
+//! client. Identical requests are answered with the matching interactions in the order they were
+//! recorded, the last one being repeated once they are exhausted.
+//!
+//! Request headers are not recorded, and the secret fields of json bodies (passwords and the
+//! session and refresh tokens of the `/auth` endpoints) are replaced by `[redacted]` in both
+//! directions, so credentials never end up in a cassette. Replaying a login hands the client
+//! redacted tokens, which is enough for the replayed requests.
+//!
+//! The cassette should be the last middleware of the client, so it sees the requests as they are
+//! sent over the network.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use reqwest::header::{HeaderName, HeaderValue, CONTENT_TYPE};
+use reqwest::{Request, Response};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::middleware::{async_trait, Middleware, Next, RequestContext};
+use crate::{Errors, Result};
+
+/// The environment variable read by [`Cassette::from_env()`].
+pub const CASSETTE_MODE_VAR: &str = "MANGADEX_CASSETTE";
+
+/// The value replacing secrets in recorded bodies.
+const REDACTED: &str = "[redacted]";
+
+/// The json fields whose values are secret, anywhere in a request or response body.
+const SECRET_FIELDS: &[&str] = &[
+    "password",
+    "newPassword",
+    "oldPassword",
+    "token",
+    "session",
+    "refresh",
+];
+
+/// A recorded request and its response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interaction {
+    /// The request.
+    pub request: RecordedRequest,
+    /// The response.
+    pub response: RecordedResponse,
+}
+
+/// A recorded request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    /// The HTTP method.
+    pub method: String,
+    /// The path of the url.
+    pub path: String,
+    /// The query string of the url, if not empty.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    /// The body, as json if possible.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Value>,
+}
+
+/// A recorded response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    /// The status code.
+    pub status: u16,
+    /// The headers.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// The body. It is stored as json for json responses, and as a string otherwise.
+    pub body: Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Record,
+    Replay,
+}
+
+/// A middleware recording or replaying HTTP traffic, see the [module docs][self].
+#[derive(Debug)]
+pub struct Cassette {
+    path: PathBuf,
+    mode: Mode,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    interactions: Vec<Interaction>,
+    used: Vec<bool>,
+}
+
+impl Cassette {
+    /// Record the traffic to the given file, replacing its content.
+    ///
+    /// The file is written after every request.
+    pub fn record(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            mode: Mode::Record,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Replay the traffic recorded in the given file.
+    pub fn replay(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let interactions: Vec<Interaction> = serde_json::from_slice(&fs::read(&path)?)?;
+
+        Ok(Self {
+            path,
+            mode: Mode::Replay,
+            state: Mutex::new(State {
+                used: vec![false; interactions.len()],
+                interactions,
+            }),
+        })
+    }
+
+    /// Record if the `MANGADEX_CASSETTE` environment variable is set to `record`, and replay
+    /// otherwise.
+    pub fn from_env(path: impl Into<PathBuf>) -> Result<Self> {
+        match std::env::var(CASSETTE_MODE_VAR) {
+            Ok(mode) if mode == "record" => Ok(Self::record(path)),
+            _ => Self::replay(path),
+        }
+    }
+
+    /// The file backing the cassette.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The interactions recorded or loaded so far.
+    pub fn interactions(&self) -> Vec<Interaction> {
+        self.lock().interactions.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().expect("cassette lock poisoned")
+    }
+
+    fn find(&self, req: &RecordedRequest) -> Option<RecordedResponse> {
+        let mut state = self.lock();
+        let State { interactions, used } = &mut *state;
+
+        let matching: Vec<usize> = interactions
+            .iter()
+            .enumerate()
+            .filter(|(_, i)| request_matches(&i.request, req))
+            .map(|(index, _)| index)
+            .collect();
+
+        let index = matching
+            .iter()
+            .copied()
+            .find(|&index| !used[index])
+            .or_else(|| matching.last().copied())?;
+        used[index] = true;
+        Some(interactions[index].response.clone())
+    }
+
+    async fn record_response(&self, request: RecordedRequest, res: Response) -> Result<Response> {
+        let status = res.status();
+        let headers: BTreeMap<String, String> = res
+            .headers()
+            .iter()
+            .filter(|(name, _)| *name != reqwest::header::SET_COOKIE)
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        let bytes = res.bytes().await?;
+
+        let response = RecordedResponse {
+            status: status.as_u16(),
+            body: parse_body(
+                &bytes,
+                headers.get(CONTENT_TYPE.as_str()).map(String::as_str),
+            ),
+            headers,
+        };
+        let mut redacted = response.clone();
+        redact(&mut redacted.body);
+
+        let interactions = {
+            let mut state = self.lock();
+            state.interactions.push(Interaction {
+                request,
+                response: redacted,
+            });
+            state.used.push(true);
+            serde_json::to_vec_pretty(&state.interactions)?
+        };
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, interactions)?;
+
+        Ok(to_response(&response))
+    }
+}
+
+#[async_trait]
+impl Middleware for Cassette {
+    async fn handle(&self, req: Request, ctx: &RequestContext, next: Next<'_>) -> Result<Response> {
+        let mut recorded = RecordedRequest {
+            method: req.method().to_string(),
+            path: req.url().path().to_string(),
+            query: req
+                .url()
+                .query()
+                .filter(|q| !q.is_empty())
+                .map(str::to_string),
+            body: req
+                .body()
+                .and_then(|body| body.as_bytes())
+                .map(|body| parse_body(body, None)),
+        };
+        // Redacted when replaying too, so the requests match their recordings.
+        if let Some(body) = &mut recorded.body {
+            redact(body);
+        }
+
+        match self.mode {
+            Mode::Replay => match self.find(&recorded) {
+                Some(res) => Ok(to_response(&res)),
+                None => Err(Errors::MissingRecording(format!(
+                    "{} {}{}",
+                    recorded.method,
+                    recorded.path,
+                    recorded
+                        .query
+                        .map(|q| format!("?{}", q))
+                        .unwrap_or_default()
+                ))),
+            },
+            Mode::Record => {
+                let res = next.run(req, ctx).await?;
+                self.record_response(recorded, res).await
+            }
+        }
+    }
+}
+
+fn request_matches(recorded: &RecordedRequest, req: &RecordedRequest) -> bool {
+    recorded.method.eq_ignore_ascii_case(&req.method)
+        && recorded.path == req.path
+        && recorded.query == req.query
+        && (recorded.body.is_none() || recorded.body == req.body)
+}
+
+/// Replace the values of the secret fields of a json body.
+fn redact(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            for (name, value) in fields {
+                if SECRET_FIELDS.contains(&name.as_str()) {
+                    redact_all(value);
+                } else {
+                    redact(value);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+/// Replace every string of a secret json value, keeping its shape so it still deserializes.
+fn redact_all(value: &mut Value) {
+    match value {
+        Value::String(text) => *text = REDACTED.to_string(),
+        Value::Object(fields) => fields.values_mut().for_each(redact_all),
+        Value::Array(values) => values.iter_mut().for_each(redact_all),
+        _ => {}
+    }
+}
+
+/// Parse a body as json, if it looks like json.
+fn parse_body(body: &[u8], content_type: Option<&str>) -> Value {
+    let is_json = match content_type {
+        Some(content_type) => content_type.contains("json"),
+        None => true,
+    };
+    is_json
+        .then(|| serde_json::from_slice(body).ok())
+        .flatten()
+        .unwrap_or_else(|| Value::String(String::from_utf8_lossy(body).into_owned()))
+}
+
+fn to_response(recorded: &RecordedResponse) -> Response {
+    let body = match &recorded.body {
+        Value::String(text) if !is_json(recorded) => text.clone(),
+        body => body.to_string(),
+    };
+
+    let mut res = http::Response::new(body);
+    *res.status_mut() = http::StatusCode::from_u16(recorded.status)
+        .unwrap_or(http::StatusCode::INTERNAL_SERVER_ERROR);
+    for (name, value) in &recorded.headers {
+        // The body is re-encoded, so the original framing headers don't apply anymore.
+        if name.eq_ignore_ascii_case("content-length")
+            || name.eq_ignore_ascii_case("content-encoding")
+            || name.eq_ignore_ascii_case("transfer-encoding")
+        {
+            continue;
+        }
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            res.headers_mut().append(name, value);
+        }
+    }
+    res.into()
+}
+
+fn is_json(recorded: &RecordedResponse) -> bool {
+    recorded
+        .headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(CONTENT_TYPE.as_str()))
+        .is_some_and(|(_, value)| value.contains("json"))
+}
+
+/// A client replaying (or recording, see [`Cassette::from_env()`]) the cassette with the given
+/// name, from `tests/cassettes`.
+#[cfg(test)]
+pub(crate) fn test_client(name: &str) -> crate::Client {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/cassettes")
+        .join(format!("{}.json", name));
+    let cassette = Cassette::from_env(&path)
+        .unwrap_or_else(|e| panic!("failed to load cassette {}: {:?}", path.display(), e));
+
+    crate::Client::builder()
+        .middleware(cassette)
+        .build()
+        .expect("valid client")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::auth::Login;
+    use crate::api::legacy::LegacyMapping;
+    use crate::schema::legacy::MappingType;
+    use crate::Client;
+    use assert_matches::assert_matches;
+    use httpmock::Method::POST;
+    use httpmock::MockServer;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn legacy_mapping(ids: Vec<u32>) -> LegacyMapping {
+        LegacyMapping {
+            r#type: MappingType::Manga,
+            ids,
+        }
+    }
+
+    #[tokio::test]
+    async fn record_then_replay() -> anyhow::Result<()> {
+        let server = MockServer::start_async().await;
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(POST).path("/legacy/mapping");
+                then.status(200)
+                    .header("Content-Type", "application/json")
+                    .json_body(json!([]));
+            })
+            .await;
+
+        let path =
+            std::env::temp_dir().join(format!("mangadex-cassette-{}.json", std::process::id()));
+
+        let client = Client::builder()
+            .base_url(&server.base_url())
+            .middleware(Cassette::record(&path))
+            .build()?;
+        legacy_mapping(vec![1]).send(&client).await?;
+        mock.assert_async().await;
+
+        let cassette = Cassette::replay(&path)?;
+        assert_eq!(cassette.interactions().len(), 1);
+        assert_eq!(
+            cassette.interactions()[0].request.body,
+            Some(json!({ "type": "manga", "ids": [1] }))
+        );
+
+        let client = Client::builder().middleware(cassette).build()?;
+        let mappings = legacy_mapping(vec![1]).send(&client).await?;
+        assert!(mappings.is_empty());
+        assert_eq!(mock.hits_async().await, 1);
+
+        let missing = legacy_mapping(vec![2]).send(&client).await;
+        assert_matches!(missing, Err(Errors::MissingRecording(_)));
+
+        fs::remove_file(path)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn redact_credentials() -> anyhow::Result<()> {
+        let server = MockServer::start_async().await;
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(POST).path("/auth/login");
+                then.status(200)
+                    .header("Content-Type", "application/json")
+                    .json_body(json!({
+                        "result": "ok",
+                        "token": {
+                            "session": "sessiontoken",
+                            "refresh": "refreshtoken",
+                        },
+                    }));
+            })
+            .await;
+
+        let path = std::env::temp_dir().join(format!(
+            "mangadex-cassette-login-{}.json",
+            std::process::id()
+        ));
+        let login = Login {
+            username: "user".to_string(),
+            password: "hunter2hunter2".to_string(),
+        };
+
+        let client = Client::builder()
+            .base_url(&server.base_url())
+            .middleware(Cassette::record(&path))
+            .build()?;
+        let res = login.send(&client).await?;
+        assert_eq!(res.tokens.session, "sessiontoken");
+        mock.assert_async().await;
+
+        let recorded = fs::read_to_string(&path)?;
+        assert!(recorded.contains("\"user\""));
+        assert!(!recorded.contains("hunter2hunter2"));
+        assert!(!recorded.contains("sessiontoken"));
+        assert!(!recorded.contains("refreshtoken"));
+
+        let client = Client::builder()
+            .middleware(Cassette::replay(&path)?)
+            .build()?;
+        let res = login.send(&client).await?;
+        assert_eq!(res.tokens.session, REDACTED);
+        assert_eq!(mock.hits_async().await, 1);
+
+        fs::remove_file(path)?;
+        Ok(())
+    }
+}
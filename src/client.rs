@@ -1,60 +1,136 @@
+use std::sync::Arc;
+use std::time::Duration;
+
 use crate::{
     api::auth::{Login, Logout, RefreshToken},
+    cache::ResponseCache,
+    coalesce::{self, Call, Captured, InFlight},
     errors::{Errors, Result},
-    schema::auth::{AuthTokens, RefreshTokenResponse},
+    middleware::{Middleware, MiddlewareStack, Next, RequestContext},
+    policy::{ContentPolicy, RateLimit, RateLimiter, RetryPolicy},
+    schema::{
+        auth::{AuthTokens, RefreshTokenResponse},
+        LanguageCode,
+    },
+    tags::TagCatalog,
     Endpoint, FromResponse, UrlSerdeQS,
 };
-use reqwest::Url;
-use serde::de::DeserializeOwned;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, IF_NONE_MATCH};
+use reqwest::{Method, Proxy, StatusCode, Url};
+use tokio::sync::OnceCell;
 
 #[cfg(not(target_arch = "wasm32"))]
-static APP_USER_AGENT: &str = concat!(
+pub(crate) static APP_USER_AGENT: &str = concat!(
     env!("CARGO_PKG_NAME"),
     "-rs",
     "/",
     env!("CARGO_PKG_VERSION"),
 );
 
+/// The default base url of the api.
+pub const API_URL: &str = "https://api.mangadex.org/";
+
+/// The default base url of the uploads server, used for cover art.
+pub const UPLOADS_URL: &str = "https://uploads.mangadex.org/";
+
+/// The default base url of the MangaDex@Home network api.
+pub const AT_HOME_URL: &str = "https://api.mangadex.network/";
+
 /// The client used to talk to the api.
 #[derive(Debug, Clone)]
 pub struct Client {
     http: reqwest::Client,
     base_url: Url,
+    uploads_url: Url,
+    at_home_url: Url,
     tokens: Option<AuthTokens>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    retry_policy: RetryPolicy,
+    middlewares: MiddlewareStack,
+    cache: Option<ResponseCache>,
+    inflight: Option<Arc<InFlight>>,
+    preferred_languages: Vec<LanguageCode>,
+    content_policy: Option<Arc<ContentPolicy>>,
+    tag_catalog: Arc<OnceCell<Arc<TagCatalog>>>,
 }
 
 impl Default for Client {
     fn default() -> Self {
-        Self::new("https://api.mangadex.org/").expect("Error creating default API client")
+        Self::new(API_URL).expect("Error creating default API client")
     }
 }
 
 impl Client {
     /// Create a new client.
     pub fn new(base_url: &str) -> Result<Self> {
-        let client = reqwest::Client::builder();
+        Self::builder().base_url(base_url).build()
+    }
 
-        #[cfg(not(target_arch = "wasm32"))]
-        let client = client.user_agent(APP_USER_AGENT);
+    /// Create a builder to configure a new client.
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::default()
+    }
 
-        let client = client.build()?;
+    /// Get the base url of the api.
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
 
-        Ok(Self {
-            http: client,
-            base_url: Url::parse(base_url)?,
-            tokens: None,
-        })
+    /// Get the base url of the uploads server.
+    pub fn uploads_url(&self) -> &Url {
+        &self.uploads_url
+    }
+
+    /// Get the base url of the MangaDex@Home network api.
+    pub fn at_home_url(&self) -> &Url {
+        &self.at_home_url
+    }
+
+    /// Get the underlying http client.
+    pub fn http_client(&self) -> &reqwest::Client {
+        &self.http
+    }
+
+    /// Get the response cache, if one is configured.
+    pub fn cache(&self) -> Option<&ResponseCache> {
+        self.cache.as_ref()
+    }
+
+    /// Get the languages to display localized strings in, by order of preference.
+    ///
+    /// See [`Manga::display_title()`][crate::schema::manga::Manga::display_title()].
+    pub fn preferred_languages(&self) -> &[LanguageCode] {
+        &self.preferred_languages
     }
 
-    pub(crate) async fn send_request<E>(&self, endpoint: &E) -> Result<E::Response>
+    /// Get the content policy, if one is configured.
+    pub fn content_policy(&self) -> Option<&ContentPolicy> {
+        self.content_policy.as_deref()
+    }
+
+    /// Get the catalog of every tag, to look them up by name.
+    ///
+    /// The tags are fetched on the first call, and shared by all the clones of the client.
+    pub async fn tag_catalog(&self) -> Result<Arc<TagCatalog>> {
+        self.tag_catalog
+            .get_or_try_init(|| async { TagCatalog::load(self).await.map(Arc::new) })
+            .await
+            .cloned()
+    }
+
+    fn build_request<E>(&self, endpoint: &E) -> Result<reqwest::RequestBuilder>
     where
         E: Endpoint,
-        <<E as Endpoint>::Response as FromResponse>::Response: DeserializeOwned,
     {
         let mut endpoint_url = self.base_url.join(&endpoint.path())?;
         if let Some(query) = endpoint.query() {
             endpoint_url = endpoint_url.query_qs(query);
         }
+        if let Some(policy) = &self.content_policy {
+            if endpoint.method() == Method::GET {
+                policy.apply_query(&endpoint.path(), &mut endpoint_url);
+            }
+        }
 
         let mut req = self.http.request(endpoint.method(), endpoint_url);
         if let Some(body) = endpoint.body() {
@@ -62,7 +138,7 @@ impl Client {
         }
 
         if let Some(multipart) = endpoint.multipart() {
-            req = req.multipart(multipart);
+            req = req.multipart(multipart.form());
         }
 
         if let Some(tokens) = self.get_tokens() {
@@ -71,10 +147,179 @@ impl Client {
             return Err(Errors::MissingTokens);
         }
 
-        let res = req.send().await?;
-        let res = res
-            .json::<<E::Response as FromResponse>::Response>()
-            .await?;
+        Ok(req)
+    }
+
+    /// Wait until the rate limit allows sending another request.
+    async fn throttle(&self) {
+        if let Some(limiter) = &self.rate_limiter {
+            let delay = limiter.reserve();
+            if !delay.is_zero() {
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+
+    /// Send a request over the network, once the middleware chain is done with it.
+    pub(crate) async fn execute(&self, req: reqwest::Request) -> Result<reqwest::Response> {
+        self.throttle().await;
+        Ok(self.http.execute(req).await?)
+    }
+
+    /// Run a request through the middleware chain.
+    async fn dispatch(
+        &self,
+        req: reqwest::Request,
+        ctx: &RequestContext,
+    ) -> Result<reqwest::Response> {
+        Next::new(self, &self.middlewares.0).run(req, ctx).await
+    }
+
+    /// Send a request, coalescing it with identical in-flight requests when possible.
+    async fn fetch<E>(
+        &self,
+        endpoint: &E,
+        ctx: &RequestContext,
+        etag: Option<&HeaderValue>,
+    ) -> Result<reqwest::Response>
+    where
+        E: Endpoint,
+    {
+        let inflight = match &self.inflight {
+            Some(inflight) if ctx.method == Method::GET => inflight,
+            _ => return self.fetch_direct(endpoint, ctx, etag).await,
+        };
+
+        let url = self.build_request(endpoint)?.build()?.url().to_string();
+        let mut key = ResponseCache::key(&url, self.get_tokens());
+        if let Some(etag) = etag.and_then(|etag| etag.to_str().ok()) {
+            key = format!("{} {}", etag, key);
+        }
+
+        match inflight.join(key) {
+            Call::Leader(leader) => {
+                let res = self.fetch_direct(endpoint, ctx, etag).await?;
+                let res = Captured::capture(res).await?;
+                leader.finish(&res);
+                Ok(res.to_response())
+            }
+            Call::Follower(slot) => match coalesce::wait(slot).await {
+                Some(res) => Ok(res.to_response()),
+                None => self.fetch_direct(endpoint, ctx, etag).await,
+            },
+        }
+    }
+
+    /// Send a request, retrying it according to the retry policy.
+    async fn fetch_direct<E>(
+        &self,
+        endpoint: &E,
+        ctx: &RequestContext,
+        etag: Option<&HeaderValue>,
+    ) -> Result<reqwest::Response>
+    where
+        E: Endpoint,
+    {
+        let mut attempt = 0;
+        loop {
+            let mut req = self.build_request(endpoint)?.build()?;
+            if let Some(etag) = etag {
+                req.headers_mut().insert(IF_NONE_MATCH, etag.clone());
+            }
+
+            let res = self.dispatch(req, ctx).await;
+            let retry = attempt < self.retry_policy.max_retries
+                && RetryPolicy::is_retryable_method(&ctx.method)
+                && match &res {
+                    Ok(res) => RetryPolicy::is_retryable_status(res.status()),
+                    Err(Errors::Http(err)) => RetryPolicy::is_retryable_error(err),
+                    Err(_) => false,
+                };
+
+            if !retry {
+                return res;
+            }
+
+            let headers = res.as_ref().ok().map(|res| res.headers());
+            tokio::time::sleep(self.retry_policy.delay(attempt, headers)).await;
+            attempt += 1;
+        }
+    }
+
+    /// Send a request through the response cache.
+    async fn fetch_cached<E>(
+        &self,
+        cache: &ResponseCache,
+        endpoint: &E,
+        ctx: &RequestContext,
+    ) -> Result<reqwest::Response>
+    where
+        E: Endpoint,
+    {
+        let ttl = match cache.ttl_for(&ctx.path) {
+            Some(ttl) if ctx.method == Method::GET => ttl,
+            _ => {
+                let res = self.fetch(endpoint, ctx, None).await?;
+                if ctx.method != Method::GET {
+                    cache.invalidate_path(&ctx.path, res.status());
+                }
+                return Ok(res);
+            }
+        };
+
+        let url = self.build_request(endpoint)?.build()?.url().to_string();
+        let key = ResponseCache::key(&url, self.get_tokens());
+        let cached = cache.get(&key);
+
+        let etag = match &cached {
+            Some(entry) if entry.is_fresh() => return Ok(entry.to_response()),
+            Some(entry) => entry
+                .etag
+                .as_deref()
+                .and_then(|etag| HeaderValue::from_str(etag).ok()),
+            None => None,
+        };
+
+        let res = self.fetch(endpoint, ctx, etag.as_ref()).await?;
+        match cached {
+            Some(entry) if res.status() == StatusCode::NOT_MODIFIED => {
+                Ok(cache.refresh(&key, entry, ttl))
+            }
+            _ => Ok(cache.put(&key, res, ttl).await?),
+        }
+    }
+
+    /// Send a request to an endpoint.
+    ///
+    /// The request types in [`api`][crate::api] provide a `send()` method that calls this
+    /// function. It can also be used to send requests to endpoints defined outside of this crate,
+    /// see [`Endpoint`].
+    pub async fn send<E>(&self, endpoint: &E) -> Result<E::Response>
+    where
+        E: Endpoint,
+    {
+        let ctx = RequestContext {
+            method: endpoint.method(),
+            path: endpoint.path().into_owned(),
+            require_auth: endpoint.require_auth(),
+        };
+
+        let res = match &self.cache {
+            Some(cache) => self.fetch_cached(cache, endpoint, &ctx).await?,
+            None => self.fetch(endpoint, &ctx, None).await?,
+        };
+
+        let res = match &self.content_policy {
+            Some(policy) if ctx.method == Method::GET && policy.filters(&ctx.path) => {
+                let mut body = res.json::<serde_json::Value>().await?;
+                policy.filter_body(&mut body);
+                serde_json::from_value(body)?
+            }
+            _ => {
+                res.json::<<E::Response as FromResponse>::Response>()
+                    .await?
+            }
+        };
 
         Ok(FromResponse::from_response(res))
     }
@@ -84,7 +329,13 @@ impl Client {
     /// * `username` - Should be between [1, 64] characters.
     /// * `password` - Should be between [8, 1024] characters.
     pub async fn login(&mut self, username: &str, password: &str) -> Result<&AuthTokens> {
-        let tokens = Login { username, password }.send(self).await?.tokens;
+        let tokens = Login {
+            username: username.to_string(),
+            password: password.to_string(),
+        }
+        .send(self)
+        .await?
+        .tokens;
 
         self.set_tokens(Some(tokens));
         Ok(self.get_tokens().unwrap())
@@ -110,7 +361,11 @@ impl Client {
 
     /// Refresh token endpoint
     pub async fn refresh_tokens(&mut self) -> Result<RefreshTokenResponse> {
-        let refresh_token = &self.get_tokens().ok_or(Errors::MissingTokens)?.refresh;
+        let refresh_token = self
+            .get_tokens()
+            .ok_or(Errors::MissingTokens)?
+            .refresh
+            .clone();
         let res = RefreshToken { refresh_token }.send(self).await?;
 
         self.set_tokens(Some(res.tokens.clone()));
@@ -120,8 +375,14 @@ impl Client {
     /// Ping the api server
     pub async fn ping(&self) -> Result<()> {
         let endpoint = self.base_url.join("/ping")?;
+        let ctx = RequestContext {
+            method: reqwest::Method::GET,
+            path: "/ping".to_string(),
+            require_auth: false,
+        };
 
-        let res = self.http.get(endpoint).send().await?;
+        let req = self.http.get(endpoint).build()?;
+        let res = self.dispatch(req, &ctx).await?;
         if res.text().await? == "pong" {
             Ok(())
         } else {
@@ -130,7 +391,303 @@ impl Client {
     }
 }
 
-/// Helper macro to quickly implement the `Endpoint` trait,
+/// Apply the http settings of a [`ClientBuilder`] to a `reqwest` client builder.
+///
+/// This is a macro because the async and blocking `reqwest` builders don't share a trait.
+macro_rules! configure_http {
+    ($config:expr, $builder:expr) => {{
+        let config = $config;
+        let mut client = $builder
+            .gzip(config.gzip)
+            .brotli(config.brotli)
+            .default_headers(config.default_headers);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            client = match config.user_agent_suffix {
+                Some(suffix) => client.user_agent(format!("{} {}", APP_USER_AGENT, suffix)),
+                None => client.user_agent(APP_USER_AGENT),
+            };
+        }
+
+        if let Some(timeout) = config.connect_timeout {
+            client = client.connect_timeout(timeout);
+        }
+
+        if let Some(timeout) = config.timeout {
+            client = client.timeout(timeout);
+        }
+
+        for proxy in config.proxies {
+            client = client.proxy(proxy);
+        }
+
+        client
+    }};
+}
+
+/// A builder to configure a [`Client`].
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use mangadex::{Client, RateLimit, RetryPolicy};
+///
+/// # fn main() -> mangadex::Result<()> {
+/// let client = Client::builder()
+///     .timeout(Duration::from_secs(30))
+///     .user_agent_suffix("my-app/1.0")
+///     .rate_limit(RateLimit::default())
+///     .retry_policy(RetryPolicy::default())
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ClientBuilder {
+    base_url: String,
+    uploads_url: String,
+    at_home_url: String,
+    http: Option<reqwest::Client>,
+    connect_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    proxies: Vec<Proxy>,
+    default_headers: HeaderMap,
+    user_agent_suffix: Option<String>,
+    gzip: bool,
+    brotli: bool,
+    rate_limit: Option<RateLimit>,
+    retry_policy: RetryPolicy,
+    middlewares: MiddlewareStack,
+    cache: Option<ResponseCache>,
+    coalesce_requests: bool,
+    preferred_languages: Vec<LanguageCode>,
+    content_policy: Option<ContentPolicy>,
+}
+
+impl Default for ClientBuilder {
+    fn default() -> Self {
+        Self {
+            base_url: API_URL.to_string(),
+            uploads_url: UPLOADS_URL.to_string(),
+            at_home_url: AT_HOME_URL.to_string(),
+            http: None,
+            connect_timeout: None,
+            timeout: None,
+            proxies: Vec::new(),
+            default_headers: HeaderMap::new(),
+            user_agent_suffix: None,
+            gzip: true,
+            brotli: true,
+            rate_limit: None,
+            retry_policy: RetryPolicy::none(),
+            middlewares: MiddlewareStack::default(),
+            cache: None,
+            coalesce_requests: true,
+            preferred_languages: vec![LanguageCode::English],
+            content_policy: None,
+        }
+    }
+}
+
+impl ClientBuilder {
+    /// Set the base url of the api.
+    pub fn base_url(mut self, url: &str) -> Self {
+        self.base_url = url.to_string();
+        self
+    }
+
+    /// Set the base url of the uploads server.
+    pub fn uploads_url(mut self, url: &str) -> Self {
+        self.uploads_url = url.to_string();
+        self
+    }
+
+    /// Set the base url of the MangaDex@Home network api.
+    pub fn at_home_url(mut self, url: &str) -> Self {
+        self.at_home_url = url.to_string();
+        self
+    }
+
+    /// Use a pre-built http client, for example to share a connection pool.
+    ///
+    /// The timeouts, proxies, default headers, user agent and compression settings of this
+    /// builder are ignored, since they are a property of the http client.
+    pub fn http_client(mut self, client: reqwest::Client) -> Self {
+        self.http = Some(client);
+        self
+    }
+
+    /// Set the timeout for connecting to the server.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the timeout of a whole request, from connecting until the response body is read.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Add a HTTP, HTTPS or SOCKS5 proxy.
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.proxies.push(proxy);
+        self
+    }
+
+    /// Add a header sent with every request.
+    pub fn default_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.default_headers.insert(name, value);
+        self
+    }
+
+    /// Append a suffix to the user agent of the client, to identify your application.
+    pub fn user_agent_suffix(mut self, suffix: &str) -> Self {
+        self.user_agent_suffix = Some(suffix.to_string());
+        self
+    }
+
+    /// Enable or disable gzip response decompression (enabled by default).
+    pub fn gzip(mut self, enable: bool) -> Self {
+        self.gzip = enable;
+        self
+    }
+
+    /// Enable or disable brotli response decompression (enabled by default).
+    pub fn brotli(mut self, enable: bool) -> Self {
+        self.brotli = enable;
+        self
+    }
+
+    /// Limit the rate of requests sent by the client (no limit by default).
+    ///
+    /// The limit is shared between all clones of the built client.
+    pub fn rate_limit(mut self, limit: RateLimit) -> Self {
+        self.rate_limit = Some(limit);
+        self
+    }
+
+    /// Set the policy used to retry failed requests (no retries by default).
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Add a middleware wrapping every request sent by the client.
+    ///
+    /// Middlewares are run in the order they are added: the first one sees the request first,
+    /// and the response last.
+    pub fn middleware<M: Middleware>(mut self, middleware: M) -> Self {
+        self.middlewares.0.push(Arc::new(middleware));
+        self
+    }
+
+    /// Cache the responses of read endpoints (disabled by default).
+    ///
+    /// The cache is shared between all clones of the built client. See [`cache`][crate::cache].
+    pub fn cache(mut self, cache: ResponseCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Enable or disable request coalescing (enabled by default).
+    ///
+    /// When enabled, identical `GET` requests (same path, query and session) sent concurrently
+    /// result in a single call to the api, whose response is shared between the callers.
+    pub fn coalesce_requests(mut self, enable: bool) -> Self {
+        self.coalesce_requests = enable;
+        self
+    }
+
+    /// Set the languages to display localized strings in, by order of preference (English by
+    /// default).
+    pub fn preferred_languages<I: IntoIterator<Item = LanguageCode>>(
+        mut self,
+        languages: I,
+    ) -> Self {
+        self.preferred_languages = languages.into_iter().collect();
+        self
+    }
+
+    /// Filter every listing sent by the client (no filters by default).
+    ///
+    /// See [`ContentPolicy`].
+    pub fn content_policy(mut self, policy: ContentPolicy) -> Self {
+        self.content_policy = Some(policy);
+        self
+    }
+
+    fn build_http(self) -> Result<reqwest::Client> {
+        let client = configure_http!(self, reqwest::Client::builder());
+        Ok(client.build()?)
+    }
+
+    /// Build the client.
+    pub fn build(mut self) -> Result<Client> {
+        let base_url = Url::parse(&self.base_url)?;
+        let uploads_url = Url::parse(&self.uploads_url)?;
+        let at_home_url = Url::parse(&self.at_home_url)?;
+        let rate_limiter = self.rate_limit.map(|l| Arc::new(RateLimiter::new(l)));
+        let retry_policy = self.retry_policy;
+        let middlewares = std::mem::take(&mut self.middlewares);
+        let cache = self.cache.take();
+        let inflight = self.coalesce_requests.then(Arc::default);
+        let preferred_languages = std::mem::take(&mut self.preferred_languages);
+        let content_policy = self.content_policy.take().map(Arc::new);
+
+        let http = match self.http.take() {
+            Some(http) => http,
+            None => self.build_http()?,
+        };
+
+        Ok(Client {
+            http,
+            base_url,
+            uploads_url,
+            at_home_url,
+            tokens: None,
+            rate_limiter,
+            retry_policy,
+            middlewares,
+            cache,
+            inflight,
+            preferred_languages,
+            content_policy,
+            tag_catalog: Arc::default(),
+        })
+    }
+
+    /// Build a [`blocking::Client`][crate::blocking::Client].
+    ///
+    /// Middlewares, response caches, request coalescing and pre-built http clients are not
+    /// supported by the blocking client, and are ignored.
+    #[cfg(feature = "blocking")]
+    pub fn build_blocking(self) -> Result<crate::blocking::Client> {
+        let base_url = Url::parse(&self.base_url)?;
+        let uploads_url = Url::parse(&self.uploads_url)?;
+        let at_home_url = Url::parse(&self.at_home_url)?;
+        let rate_limiter = self.rate_limit.map(|l| Arc::new(RateLimiter::new(l)));
+        let retry_policy = self.retry_policy;
+        let preferred_languages = self.preferred_languages.clone();
+        let content_policy = self.content_policy.clone().map(Arc::new);
+
+        let http = configure_http!(self, reqwest::blocking::Client::builder()).build()?;
+
+        Ok(crate::blocking::Client {
+            http,
+            base_url,
+            uploads_url,
+            at_home_url,
+            tokens: None,
+            rate_limiter,
+            retry_policy,
+            preferred_languages,
+            content_policy,
+        })
+    }
+}
+
+/// Helper macro to quickly implement the [`Endpoint`] trait,
 /// and optionally a `send()` method for the input struct.
 ///
 /// The first argument is the endpoint, the second the input data, and the third the response.
@@ -138,7 +695,7 @@ impl Client {
 ///
 /// The endpoint is specified by the HTTP method, followed by the path. To get a dymanic path
 /// based on the input structure, surround the path with parenthesis:
-/// ```
+/// ```text
 /// POST ("/account/activate/{}", id)
 /// ```
 /// The format is the same as the `format!()` macro, except `id` will be substituted by `self.id`,
@@ -149,15 +706,15 @@ impl Client {
 /// serialized as the query parameter, in the second as a json body, and in the third no data will
 /// be sent with the request. If an `auth` tag is also included, the request will not be made if
 /// the user is not authenticated. Some examples of valid tags are:
-/// ```
+/// ```text
 /// #[query] QueryReq
 /// #[body] BodyReq
 /// #[query auth] QueryReq
 /// #[no_data] QueryStruct
 /// ```
 /// The input structure itself should implement `serde::Serialize` if it is used as a body or query.
-/// By strategically using `#[serde(skip)]` and `#[serde(flatten)]`, this is powerfull enough for
-/// our use case.
+/// By strategically using `#[serde(skip_serializing)]` and `#[serde(flatten)]`, this is powerfull
+/// enough for our use case.
 ///
 /// The final argument is the output type, tagged similarly to the input, to modify the behaviour
 /// of the generated `send()` method. Specifically:
@@ -166,14 +723,30 @@ impl Client {
 /// - `discard_result`: If `Output = Result<T>`, discard `T`, and return `Result<()>`
 /// - `no_send`: Do not implement a `send()` function
 ///
+/// The output type must implement [`FromResponse`], see its documentation for the envelopes
+/// supported out of the box.
+///
 /// Example:
-/// ```
+/// ```rust,no_run
+/// use mangadex::impl_endpoint;
+/// use mangadex::schema::manga::MangaResponse;
+/// use serde::Serialize;
+/// use uuid::Uuid;
+///
+/// #[derive(Serialize)]
+/// pub struct GetMangaWithIncludes {
+///     #[serde(skip_serializing)]
+///     pub id: Uuid,
+///     pub includes: Vec<String>,
+/// }
+///
 /// impl_endpoint! {
-///     GET "/path/to/endpoint",
-///     #[query] StructWithData<'_>,
-///     #[flatten_result] Result<ResponseType>
+///     GET ("/manga/{:x}", id),
+///     #[query] GetMangaWithIncludes,
+///     #[flatten_result] MangaResponse
 /// }
 /// ```
+#[macro_export]
 macro_rules! impl_endpoint {
     {
         $method:ident $path:tt,
@@ -181,31 +754,31 @@ macro_rules! impl_endpoint {
         $(#[$out_res:ident])? $out:ty
     } => {
 
-        impl $crate::common::Endpoint for $typ {
+        impl $crate::Endpoint for $typ {
             /// The response type.
             type Response = $out;
 
             /// Get the method of the request.
-            fn method(&self) -> reqwest::Method {
-                reqwest::Method::$method
+            fn method(&self) -> $crate::reqwest::Method {
+                $crate::reqwest::Method::$method
             }
 
-            impl_endpoint! { @path $path }
-            impl_endpoint! { @payload $payload }
-            $(impl_endpoint! { @$auth })?
+            $crate::impl_endpoint! { @path $path }
+            $crate::impl_endpoint! { @payload $payload }
+            $($crate::impl_endpoint! { @$auth })?
         }
 
-        impl_endpoint! { @send $(:$out_res)?, $typ, $out }
+        $crate::impl_endpoint! { @send $(:$out_res)?, $typ, $out }
     };
     { @path ($path:expr, $($arg:ident),+) } => {
         /// Get the path of the request.
-        fn path(&self) -> std::borrow::Cow<str> {
+        fn path(&self) -> std::borrow::Cow<'_, str> {
             std::borrow::Cow::Owned(format!($path, $(self.$arg),+))
         }
     };
     { @path $path:expr } => {
         /// Get the path of the request.
-        fn path(&self) -> std::borrow::Cow<str> {
+        fn path(&self) -> std::borrow::Cow<'_, str> {
             std::borrow::Cow::Borrowed($path)
         }
     };
@@ -239,23 +812,44 @@ macro_rules! impl_endpoint {
         impl $typ {
             /// Send the request
             pub async fn send(&self, client: &$crate::Client) -> $crate::Result<$out> {
-                client.send_request(self).await
+                client.send(self).await
             }
         }
+
+        $crate::__impl_send_blocking! {
+            $typ,
+            $crate::Result<$out>,
+            |client, req| client.send(req)
+        }
     };
     { @send:flatten_result, $typ:ty, $out:ty } => {
         impl $typ {
             /// Send the request
             pub async fn send(&self, client: &$crate::Client) -> $out {
-                client.send_request(self).await?
+                client.send(self).await?
             }
         }
+
+        $crate::__impl_send_blocking! {
+            $typ,
+            $out,
+            |client, req| client.send(req)?
+        }
     };
     { @send:discard_result, $typ:ty, $out:ty } => {
         impl $typ {
             /// Send the request
             pub async fn send(&self, client: &$crate::Client) -> $crate::Result<()> {
-                client.send_request(self).await??;
+                client.send(self).await??;
+                Ok(())
+            }
+        }
+
+        $crate::__impl_send_blocking! {
+            $typ,
+            $crate::Result<()>,
+            |client, req| {
+                client.send(req)??;
                 Ok(())
             }
         }
@@ -263,9 +857,41 @@ macro_rules! impl_endpoint {
     { @send:no_send, $typ:ty, $out:ty } => { };
 }
 
+/// Implement `send_blocking()` for a request type, when the `blocking` feature is enabled.
+///
+/// This has to be a separate macro, because a `#[cfg(feature = "blocking")]` inside of
+/// [`impl_endpoint!`] would be evaluated against the features of the crate using the macro.
+#[cfg(feature = "blocking")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_send_blocking {
+    ($typ:ty, $out:ty, |$client:ident, $req:ident| $body:expr) => {
+        impl $typ {
+            /// Send the request with a blocking client
+            pub fn send_blocking(&self, $client: &$crate::blocking::Client) -> $out {
+                let $req = self;
+                $body
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "blocking"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_send_blocking {
+    ($($tt:tt)*) => {};
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::api::auth::{CheckToken, Logout};
+    use assert_matches::assert_matches;
+    use httpmock::Method::{GET, POST};
+    use httpmock::MockServer;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
 
     #[test]
     fn client_new() {
@@ -274,7 +900,368 @@ mod tests {
 
     #[tokio::test]
     async fn ping_server() {
-        let client = Client::default();
+        let client = crate::cassette::test_client("client/ping_server");
         client.ping().await.unwrap();
     }
+
+    #[tokio::test]
+    async fn user_agent_suffix() -> anyhow::Result<()> {
+        let server = MockServer::start_async().await;
+        let user_agent = format!("{} my-app/1.0", APP_USER_AGENT);
+
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/ping")
+                    .header("User-Agent", &user_agent);
+                then.status(200).body("pong");
+            })
+            .await;
+
+        let client = Client::builder()
+            .base_url(&server.base_url())
+            .user_agent_suffix("my-app/1.0")
+            .build()?;
+        client.ping().await?;
+
+        mock.assert_async().await;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn retry_on_503() -> anyhow::Result<()> {
+        let server = MockServer::start_async().await;
+
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(GET).path("/auth/check");
+                then.status(503)
+                    .header("Content-Type", "application/json")
+                    .json_body(json!({
+                        "result": "error",
+                        "errors": [],
+                    }));
+            })
+            .await;
+
+        let mut client = Client::builder()
+            .base_url(&server.base_url())
+            .retry_policy(RetryPolicy {
+                max_retries: 2,
+                initial_backoff: Duration::from_millis(1),
+                max_backoff: Duration::from_millis(10),
+            })
+            .build()?;
+        client.set_tokens(Some(AuthTokens {
+            session: "sessiontoken".to_string(),
+            refresh: "refreshtoken".to_string(),
+        }));
+
+        let errors = CheckToken.send(&client).await.expect_err("expected error");
+
+        mock.assert_hits_async(3).await;
+        assert_matches!(errors, Errors::Api(_));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn no_retry_on_post() -> anyhow::Result<()> {
+        let server = MockServer::start_async().await;
+
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(POST).path("/auth/logout");
+                then.status(503)
+                    .header("Content-Type", "application/json")
+                    .json_body(json!({
+                        "result": "error",
+                        "errors": [],
+                    }));
+            })
+            .await;
+
+        let mut client = Client::builder()
+            .base_url(&server.base_url())
+            .retry_policy(RetryPolicy {
+                max_retries: 2,
+                initial_backoff: Duration::from_millis(1),
+                max_backoff: Duration::from_millis(10),
+            })
+            .build()?;
+        client.set_tokens(Some(AuthTokens {
+            session: "sessiontoken".to_string(),
+            refresh: "refreshtoken".to_string(),
+        }));
+
+        let errors = Logout.send(&client).await.expect_err("expected error");
+
+        mock.assert_hits_async(1).await;
+        assert_matches!(errors, Errors::Api(_));
+
+        Ok(())
+    }
+
+    #[derive(serde::Serialize)]
+    struct CustomEndpoint {
+        #[serde(skip)]
+        id: u32,
+        name: &'static str,
+    }
+
+    impl Endpoint for CustomEndpoint {
+        type Query = Self;
+        type Body = ();
+        type Response = crate::Result<crate::schema::NoData>;
+
+        fn path(&self) -> std::borrow::Cow<'_, str> {
+            std::borrow::Cow::Owned(format!("/custom/{}", self.id))
+        }
+
+        fn query(&self) -> Option<&Self::Query> {
+            Some(self)
+        }
+    }
+
+    #[tokio::test]
+    async fn send_custom_endpoint() -> anyhow::Result<()> {
+        let server = MockServer::start_async().await;
+
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/custom/42")
+                    .query_param("name", "test");
+                then.status(200)
+                    .header("Content-Type", "application/json")
+                    .json_body(json!({ "result": "ok" }));
+            })
+            .await;
+
+        let client = Client::new(&server.base_url())?;
+        client
+            .send(&CustomEndpoint {
+                id: 42,
+                name: "test",
+            })
+            .await??;
+
+        mock.assert_async().await;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn coalesce_identical_requests() -> anyhow::Result<()> {
+        let server = MockServer::start_async().await;
+
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(GET).path("/custom/42");
+                then.status(200)
+                    .header("Content-Type", "application/json")
+                    .delay(Duration::from_millis(200))
+                    .json_body(json!({ "result": "ok" }));
+            })
+            .await;
+
+        let client = Client::new(&server.base_url())?;
+        let same = CustomEndpoint {
+            id: 42,
+            name: "test",
+        };
+        let other = CustomEndpoint {
+            id: 42,
+            name: "other",
+        };
+        let (a, b, c, other) = tokio::join!(
+            client.send(&same),
+            client.send(&same),
+            client.send(&same),
+            client.send(&other),
+        );
+        a??;
+        b??;
+        c??;
+        other??;
+
+        assert_eq!(mock.hits_async().await, 2);
+
+        Ok(())
+    }
+
+    /// A listing of copies of the manga of the `manga/view_manga` cassette.
+    fn manga_list(manga: Vec<(&str, &str, &str)>) -> serde_json::Value {
+        let cassette: serde_json::Value =
+            serde_json::from_str(include_str!("../tests/cassettes/manga/view_manga.json")).unwrap();
+        let template = &cassette[0]["response"]["body"];
+        let results: Vec<_> = manga
+            .into_iter()
+            .map(|(id, rating, tag)| {
+                let mut entry = template.clone();
+                entry["data"]["id"] = json!(id);
+                entry["data"]["attributes"]["contentRating"] = json!(rating);
+                entry["data"]["attributes"]["tags"][0]["id"] = json!(tag);
+                entry
+            })
+            .collect();
+        json!({ "results": results, "limit": 10, "offset": 0, "total": 3 })
+    }
+
+    #[tokio::test]
+    async fn content_policy() -> anyhow::Result<()> {
+        use crate::api::manga::ListMangaBuilder;
+        use crate::schema::manga::ContentRating;
+
+        const SAFE: &str = "32d76d19-8a05-4db0-9fc2-e0b0648fe9d0";
+        const GORE: &str = "b29d6a3d-1569-4e7a-8caf-7557bc92cd5d";
+        const ACTION: &str = "391b0423-d847-456f-aff0-8b0cfc03066b";
+
+        let server = MockServer::start_async().await;
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/manga")
+                    .query_param("contentRating[0]", "safe")
+                    .query_param("excludedTags[0]", ACTION)
+                    .query_param("excludedTags[1]", GORE);
+                then.status(200)
+                    .header("Content-Type", "application/json")
+                    .json_body(manga_list(vec![
+                        (SAFE, "safe", "a3c67850-4684-404e-9b7f-c69850ee5da6"),
+                        ("3d2f2c1c-4f1c-4a4e-9a43-6a7cd5d4d8c8", "erotica", ACTION),
+                        ("c0ee660b-f9f2-45c3-8068-5123ff53f84a", "safe", GORE),
+                    ]));
+            })
+            .await;
+
+        let client = Client::builder()
+            .base_url(&server.base_url())
+            .content_policy(
+                ContentPolicy::new()
+                    .allow_ratings(vec![ContentRating::Safe, ContentRating::Suggestive])
+                    .exclude_tags(vec![GORE.parse()?]),
+            )
+            .build()?;
+
+        // The request narrows down the allowed ratings, and adds its own excluded tags.
+        let list = ListMangaBuilder::default()
+            .add_content_rating(ContentRating::Safe)
+            .add_content_rating(ContentRating::Erotica)
+            .exclude_tag(ACTION.parse::<crate::schema::TagId>()?)
+            .build()?
+            .send(&client)
+            .await?;
+
+        mock.assert_async().await;
+        let ids: Vec<String> = list
+            .results
+            .into_iter()
+            .map(|res| res.map(|res| res.data.id.to_string()))
+            .collect::<Result<_>>()?;
+        assert_eq!(ids, vec![SAFE]);
+        Ok(())
+    }
+
+    #[test]
+    fn content_policy_listings() {
+        use crate::schema::manga::ContentRating;
+
+        let tag = "b29d6a3d-1569-4e7a-8caf-7557bc92cd5d";
+        let group = "e11e461b-8c3a-4b5c-8b07-8892c2dcc449";
+        let policy = ContentPolicy::new()
+            .allow_ratings(vec![ContentRating::Safe])
+            .exclude_tags(vec![tag.parse().unwrap()])
+            .allow_languages(vec![LanguageCode::English])
+            .exclude_groups(vec![group.parse().unwrap()]);
+        let manga = format!(
+            "contentRating[0]=safe&excludedTags[0]={}&availableTranslatedLanguage[0]=en",
+            tag
+        );
+        let chapters = format!("translatedLanguage[0]=en&excludedGroups[0]={}", group);
+        let id = "32d76d19-8a05-4db0-9fc2-e0b0648fe9d0";
+
+        let cases = vec![
+            ("/manga".to_string(), manga.clone()),
+            ("/user/follows/manga".to_string(), manga),
+            (
+                "/manga/random".to_string(),
+                format!("contentRating[0]=safe&excludedTags[0]={}", tag),
+            ),
+            ("/chapter".to_string(), chapters.clone()),
+            (format!("/manga/{}/feed", id), chapters.clone()),
+            (format!("/list/{}/feed", id), chapters.clone()),
+            ("/user/follows/manga/feed".to_string(), chapters),
+            (format!("/manga/{}", id), String::new()),
+        ];
+        for (path, expected) in cases {
+            let mut url = Url::parse("https://api.mangadex.org")
+                .unwrap()
+                .join(&path)
+                .unwrap();
+            policy.apply_query(&path, &mut url);
+            let query = url
+                .query_pairs()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>()
+                .join("&");
+            assert_eq!(query, expected, "{}", path);
+        }
+    }
+
+    #[test]
+    fn content_policy_chapters() {
+        let group = "e11e461b-8c3a-4b5c-8b07-8892c2dcc449";
+        let policy = ContentPolicy::new()
+            .allow_languages(vec![LanguageCode::English, LanguageCode::French])
+            .exclude_groups(vec![group.parse().unwrap()]);
+
+        let mut url = Url::parse("https://api.mangadex.org/chapter?limit=10").unwrap();
+        policy.apply_query("/chapter", &mut url);
+        assert_eq!(
+            url.query(),
+            Some(
+                "limit=10&translatedLanguage%5B0%5D=en&translatedLanguage%5B1%5D=fr\
+                 &excludedGroups%5B0%5D=e11e461b-8c3a-4b5c-8b07-8892c2dcc449"
+            )
+        );
+
+        let mut url =
+            Url::parse("https://api.mangadex.org/manga/1/feed?translatedLanguage[0]=fr").unwrap();
+        policy.apply_query("/manga/1/feed", &mut url);
+        assert_eq!(
+            url.query(),
+            Some(
+                "translatedLanguage%5B0%5D=fr\
+                 &excludedGroups%5B0%5D=e11e461b-8c3a-4b5c-8b07-8892c2dcc449"
+            )
+        );
+
+        let chapter = |language: &str, group: &str| {
+            json!({
+                "result": "ok",
+                "data": { "type": "chapter", "attributes": { "translatedLanguage": language } },
+                "relationships": [{ "id": group, "type": "scanlation_group" }],
+            })
+        };
+        let other = "0b3ad6e1-9c1f-4e2e-9f59-3e6f5d1d8a01";
+        let mut body = json!({
+            "results": [chapter("en", other), chapter("ja", other), chapter("fr", group)],
+        });
+        assert!(policy.filters("/user/follows/manga/feed"));
+        policy.filter_body(&mut body);
+        assert_eq!(body["results"], json!([chapter("en", other)]));
+    }
+
+    #[test]
+    fn rate_limiter() {
+        let limiter = RateLimiter::new(RateLimit::new(2, Duration::from_secs(60)));
+
+        assert_eq!(limiter.reserve(), Duration::ZERO);
+        assert_eq!(limiter.reserve(), Duration::ZERO);
+
+        let delay = limiter.reserve();
+        assert!(delay > Duration::from_secs(59) && delay <= Duration::from_secs(60));
+    }
 }
@@ -0,0 +1,119 @@
+//! Coalescing of identical in-flight requests.
+//!
+//! When several tasks send the same `GET` request at once, only the first one (the leader) goes
+//! to the network. The others wait for its response and decode their own copy of it. If the
+//! leader fails without a response, or is dropped before finishing, the waiters send their own
+//! request instead.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use reqwest::header::HeaderMap;
+use reqwest::{Response, StatusCode};
+use tokio::sync::watch;
+
+/// A response read into memory, so it can be handed to several callers.
+#[derive(Debug, Clone)]
+pub(crate) struct Captured {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Vec<u8>,
+}
+
+impl Captured {
+    pub(crate) async fn capture(res: Response) -> reqwest::Result<Self> {
+        Ok(Self {
+            status: res.status(),
+            headers: res.headers().clone(),
+            body: res.bytes().await?.to_vec(),
+        })
+    }
+
+    pub(crate) fn to_response(&self) -> Response {
+        let mut res = http::Response::new(self.body.clone());
+        *res.status_mut() = self.status;
+        *res.headers_mut() = self.headers.clone();
+        res.into()
+    }
+}
+
+type Slot = watch::Receiver<Option<Captured>>;
+
+/// The requests currently in flight, by key.
+#[derive(Debug, Default)]
+pub(crate) struct InFlight {
+    calls: Mutex<Calls>,
+}
+
+#[derive(Debug, Default)]
+struct Calls {
+    next_id: u64,
+    slots: HashMap<String, (u64, Slot)>,
+}
+
+/// The role of a caller in a coalesced request.
+pub(crate) enum Call {
+    /// The caller sends the request, and must publish the result with [`Leader::finish()`].
+    Leader(Leader),
+    /// The same request is already in flight.
+    Follower(Slot),
+}
+
+impl InFlight {
+    /// Join the request with the given key, becoming its leader if it isn't in flight yet.
+    pub(crate) fn join(self: &Arc<Self>, key: String) -> Call {
+        let mut calls = self.calls.lock().expect("in-flight lock poisoned");
+
+        if let Some((_, slot)) = calls.slots.get(&key) {
+            return Call::Follower(slot.clone());
+        }
+
+        calls.next_id += 1;
+        let id = calls.next_id;
+        let (tx, rx) = watch::channel(None);
+        calls.slots.insert(key.clone(), (id, rx));
+
+        Call::Leader(Leader {
+            inflight: self.clone(),
+            key,
+            id,
+            tx,
+        })
+    }
+
+    fn remove(&self, key: &str, id: u64) {
+        let mut calls = self.calls.lock().expect("in-flight lock poisoned");
+        if calls.slots.get(key).map(|(slot_id, _)| *slot_id) == Some(id) {
+            calls.slots.remove(key);
+        }
+    }
+}
+
+/// The caller responsible for sending a coalesced request.
+pub(crate) struct Leader {
+    inflight: Arc<InFlight>,
+    key: String,
+    id: u64,
+    tx: watch::Sender<Option<Captured>>,
+}
+
+impl Leader {
+    /// Hand the response to the waiting callers.
+    pub(crate) fn finish(self, res: &Captured) {
+        self.inflight.remove(&self.key, self.id);
+        let _ = self.tx.send(Some(res.clone()));
+    }
+}
+
+impl Drop for Leader {
+    fn drop(&mut self) {
+        self.inflight.remove(&self.key, self.id);
+    }
+}
+
+/// Wait for the leader of a request, returning `None` if it failed.
+pub(crate) async fn wait(mut slot: Slot) -> Option<Captured> {
+    let _ = slot.changed().await;
+    let res = slot.borrow().clone();
+    res
+}
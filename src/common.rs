@@ -1,5 +1,4 @@
-use reqwest::multipart;
-use serde::{Deserialize, Deserializer, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
 use std::borrow::Cow;
 
 pub(crate) trait UrlSerdeQS {
@@ -24,38 +23,213 @@ where
     Ok(opt.unwrap_or_default())
 }
 
-/// So this trait isn't ideal, but it's the only practical way I could figure out on stable Rust,
-// without specialization. With that in mind, please don't expose it to the public API.
-pub(crate) trait FromResponse: Sized {
-    type Response;
+/// (De)serialize a list of sort orders, each a single key map such as `{"title": "asc"}`, as one
+/// map (`order[title]=asc&order[year]=desc`) keeping their priority.
+pub(crate) mod order_map {
+    use std::fmt;
+    use std::marker::PhantomData;
 
+    use serde::de::{DeserializeOwned, Error as _, MapAccess, Visitor};
+    use serde::ser::{Error as _, SerializeMap};
+    use serde::{Deserializer, Serialize, Serializer};
+    use serde_json::{Map, Value};
+
+    pub(crate) fn serialize<S, T>(orders: &[T], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: Serialize,
+    {
+        let mut map = serializer.serialize_map(None)?;
+        for order in orders {
+            match serde_json::to_value(order).map_err(S::Error::custom)? {
+                Value::Object(entry) => {
+                    for (key, direction) in entry {
+                        map.serialize_entry(&key, &direction)?;
+                    }
+                }
+                _ => return Err(S::Error::custom("a sort order must be a map")),
+            }
+        }
+        map.end()
+    }
+
+    pub(crate) fn deserialize<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: DeserializeOwned,
+    {
+        struct OrdersVisitor<T>(PhantomData<T>);
+
+        impl<'de, T: DeserializeOwned> Visitor<'de> for OrdersVisitor<T> {
+            type Value = Vec<T>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a map of sort orders")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut orders = Vec::new();
+                while let Some((key, direction)) = map.next_entry::<String, Value>()? {
+                    let mut entry = Map::new();
+                    entry.insert(key, direction);
+                    orders.push(
+                        serde_json::from_value(Value::Object(entry)).map_err(A::Error::custom)?,
+                    );
+                }
+                Ok(orders)
+            }
+
+            fn visit_unit<E>(self) -> Result<Self::Value, E> {
+                Ok(Vec::new())
+            }
+        }
+
+        deserializer.deserialize_any(OrdersVisitor(PhantomData))
+    }
+}
+
+/// Decoding of the response envelope of an endpoint.
+///
+/// The api wraps responses in a few different envelopes. `Self` is the type returned to the
+/// caller, while `Self::Response` is the type the json body is deserialized into. The crate
+/// provides implementations for the common envelopes:
+///
+/// - `Result<T>`: a single object, tagged with `"result": "ok"` or `"result": "error"`.
+/// - `Results<Result<T>>`: a paginated list of objects, as returned by the listing endpoints.
+/// - `Vec<Result<T>>`: a raw list of objects.
+///
+/// Types that are returned as is can implement this trait with `type Response = Self`.
+pub trait FromResponse: Sized {
+    /// The type the json body is deserialized into.
+    type Response: DeserializeOwned;
+
+    /// Convert the deserialized body into the returned type.
     fn from_response(res: Self::Response) -> Self;
 }
 
-pub(crate) trait Endpoint {
+/// An api endpoint.
+///
+/// Implementing this trait is enough to send a request with [`Client::send()`], which takes care
+/// of authentication, rate limiting, middlewares and error decoding:
+///
+/// ```rust,no_run
+/// use std::borrow::Cow;
+///
+/// use mangadex::schema::manga::MangaResponse;
+/// use mangadex::{Client, Endpoint};
+/// use serde::Serialize;
+/// use uuid::Uuid;
+///
+/// #[derive(Serialize)]
+/// struct GetMangaWithIncludes {
+///     #[serde(skip_serializing)]
+///     id: Uuid,
+///     includes: Vec<String>,
+/// }
+///
+/// impl Endpoint for GetMangaWithIncludes {
+///     type Query = Self;
+///     type Body = ();
+///     type Response = MangaResponse;
+///
+///     fn path(&self) -> Cow<'_, str> {
+///         Cow::Owned(format!("/manga/{:x}", self.id))
+///     }
+///
+///     fn query(&self) -> Option<&Self::Query> {
+///         Some(self)
+///     }
+/// }
+///
+/// # async fn run(client: &Client) -> mangadex::Result<()> {
+/// let request = GetMangaWithIncludes {
+///     id: Uuid::parse_str("32d76d19-8a05-4db0-9fc2-e0b0648fe9d0").unwrap(),
+///     includes: vec!["author".to_string()],
+/// };
+/// let manga = client.send(&request).await??;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Client::send()`]: crate::Client::send
+pub trait Endpoint {
+    /// The query string of the request, serialized with `serde_qs`.
     type Query: Serialize;
+    /// The json body of the request.
     type Body: Serialize;
+    /// The response envelope of the request.
     type Response: FromResponse;
 
-    fn path(&self) -> Cow<str>;
+    /// Get the path of the request, relative to the base url of the api.
+    fn path(&self) -> Cow<'_, str>;
 
+    /// Get the method of the request.
     fn method(&self) -> reqwest::Method {
         reqwest::Method::GET
     }
 
+    /// Get whether auth is required for this request.
     fn require_auth(&self) -> bool {
         false
     }
 
+    /// Get the query of the request.
     fn query(&self) -> Option<&Self::Query> {
         None
     }
 
+    /// Get the body of the request.
     fn body(&self) -> Option<&Self::Body> {
         None
     }
 
-    fn multipart(&self) -> Option<multipart::Form> {
+    /// Get the multipart form of the request.
+    fn multipart(&self) -> Option<Multipart> {
         None
     }
 }
+
+/// A multipart form, the body of the requests uploading files.
+///
+/// It is independent of the client sending it, so an [`Endpoint`] describes its form once for
+/// both the async and the blocking clients.
+#[derive(Debug, Clone, Default)]
+pub struct Multipart {
+    parts: Vec<(Cow<'static, str>, Cow<'static, [u8]>)>,
+}
+
+impl Multipart {
+    /// Create an empty form.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a part with the given name and content.
+    pub fn bytes(
+        mut self,
+        name: impl Into<Cow<'static, str>>,
+        bytes: impl Into<Cow<'static, [u8]>>,
+    ) -> Self {
+        self.parts.push((name.into(), bytes.into()));
+        self
+    }
+
+    /// The form sent by the async client.
+    pub(crate) fn form(&self) -> reqwest::multipart::Form {
+        use reqwest::multipart::{Form, Part};
+
+        self.parts.iter().fold(Form::new(), |form, (name, bytes)| {
+            form.part(name.clone(), Part::bytes(bytes.clone()))
+        })
+    }
+
+    /// The form sent by the blocking client.
+    #[cfg(feature = "blocking")]
+    pub(crate) fn blocking_form(&self) -> reqwest::blocking::multipart::Form {
+        use reqwest::blocking::multipart::{Form, Part};
+
+        self.parts.iter().fold(Form::new(), |form, (name, bytes)| {
+            form.part(name.clone(), Part::bytes(bytes.clone()))
+        })
+    }
+}
@@ -25,6 +25,18 @@ pub enum Errors {
     /// Received an unexpected response from /ping
     #[error("invalid ping response")]
     PingError,
+
+    /// Error when reading or writing a file
+    #[error("io error")]
+    Io(#[from] std::io::Error),
+
+    /// Error when parsing or serializing json outside of a response
+    #[error("json error")]
+    Json(#[from] serde_json::Error),
+
+    /// A replayed cassette has no response for a request
+    #[error("no recorded response for {0}")]
+    MissingRecording(String),
 }
 
 /// Helper Result type.
@@ -0,0 +1,442 @@
+//! An in-process fake MangaDex api, for integration tests
+//!
+//! [`FakeServer`] listens on a local port and implements the api on top of an in-memory store:
+//! manga, chapters, authors, groups, covers, tags, custom lists, users and follows. Accounts log
+//! in with working session and refresh tokens, lists are paginated with `limit`/`offset`/`total`,
+//! updates are checked against the `version` of the resource, and chapter pages are served by a
+//! fake MangaDex@Home server.
+//!
+//! ```rust,no_run
+//! use mangadex::api::manga::GetManga;
+//! use mangadex::fake::FakeServer;
+//! use serde_json::json;
+//!
+//! # async fn run() -> mangadex::Result<()> {
+//! let server = FakeServer::start().await?;
+//! let id = server.add_manga(json!({ "title": { "en": "Test manga" } }));
+//!
+//! let client = server.client()?;
+//! let manga = GetManga { id }.send(&client).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Anything not listed above (uploads, reports, account creation, captchas) answers `404`.
+//!
+//! This module is only available with the `fake-server` feature.
+
+mod routes;
+mod store;
+
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response};
+use serde_json::{json, Map, Value};
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+use crate::schema::{
+    legacy::MappingType, manga::TagGroup, AuthorId, ChapterId, CoverId, GroupId, MangaId,
+    ResourceType, TagId, UserId,
+};
+use crate::{Client, Result};
+use routes::{FakeRequest, Query};
+use store::{Account, Store};
+
+/// A fake MangaDex api running in the background, see the [module docs][self].
+///
+/// The server stops when this handle is dropped.
+#[derive(Debug)]
+pub struct FakeServer {
+    store: Arc<Mutex<Store>>,
+    base_url: String,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl FakeServer {
+    /// Start a server on a random local port.
+    ///
+    /// Must be called from within a tokio runtime.
+    pub async fn start() -> Result<Self> {
+        let listener = std::net::TcpListener::bind(("127.0.0.1", 0))?;
+        listener.set_nonblocking(true)?;
+        let base_url = format!("http://{}", listener.local_addr()?);
+        let store = Arc::new(Mutex::new(Store::default()));
+
+        let make_service = {
+            let store = store.clone();
+            let base_url = base_url.clone();
+            make_service_fn(move |_| {
+                let store = store.clone();
+                let base_url = base_url.clone();
+                async move {
+                    Ok::<_, Infallible>(service_fn(move |req| {
+                        serve(store.clone(), base_url.clone(), req)
+                    }))
+                }
+            })
+        };
+
+        let (shutdown, stopped) = oneshot::channel::<()>();
+        let server = hyper::Server::from_tcp(listener)
+            .map_err(std::io::Error::other)?
+            .serve(make_service)
+            .with_graceful_shutdown(async {
+                stopped.await.ok();
+            });
+        tokio::spawn(server);
+
+        Ok(Self {
+            store,
+            base_url,
+            shutdown: Some(shutdown),
+        })
+    }
+
+    /// The base url of the server, e.g. `http://127.0.0.1:41233/`.
+    pub fn base_url(&self) -> String {
+        format!("{}/", self.base_url)
+    }
+
+    /// A client using the server as its api, uploads and MangaDex@Home server.
+    pub fn client(&self) -> Result<Client> {
+        let base_url = self.base_url();
+        Client::builder()
+            .base_url(&base_url)
+            .uploads_url(&base_url)
+            .at_home_url(&base_url)
+            .build()
+    }
+
+    fn store(&self) -> std::sync::MutexGuard<'_, Store> {
+        self.store.lock().expect("fake server lock poisoned")
+    }
+
+    fn insert(&self, kind: ResourceType, attributes: Value) -> Uuid {
+        self.insert_related(kind, attributes, Vec::new())
+    }
+
+    fn insert_related(
+        &self,
+        kind: ResourceType,
+        attributes: Value,
+        relationships: Vec<(ResourceType, Uuid)>,
+    ) -> Uuid {
+        let attributes = match attributes {
+            Value::Object(attributes) => attributes,
+            _ => Map::new(),
+        };
+        self.store().insert(kind, attributes, relationships)
+    }
+
+    /// Add a user that can log in with the given credentials.
+    pub fn add_user(&self, username: &str, password: &str) -> UserId {
+        let id = self.insert(ResourceType::User, json!({ "username": username }));
+        self.store().accounts.insert(
+            username.to_string(),
+            Account {
+                password: password.to_string(),
+                user_id: id,
+            },
+        );
+        id.into()
+    }
+
+    /// Add an author.
+    pub fn add_author(&self, name: &str) -> AuthorId {
+        self.insert(ResourceType::Author, json!({ "name": name }))
+            .into()
+    }
+
+    /// Add a tag, in the given group.
+    pub fn add_tag(&self, name: &str, group: TagGroup) -> TagId {
+        self.insert(
+            ResourceType::Tag,
+            json!({ "name": { "en": name }, "group": group.as_str() }),
+        )
+        .into()
+    }
+
+    /// Add a manga with the given attributes, in the format of the api.
+    ///
+    /// Missing attributes get a default value. `tags` may be given as a list of tag ids.
+    pub fn add_manga(&self, mut attributes: Value) -> MangaId {
+        if let Some(Value::Array(tags)) = attributes.get_mut("tags") {
+            let store = self.store();
+            for tag in tags.iter_mut() {
+                let id = tag.as_str().and_then(|id| Uuid::parse_str(id).ok());
+                if let Some(data) = id.and_then(|id| store.get(ResourceType::Tag, id)) {
+                    *tag = data.data();
+                }
+            }
+        }
+        self.insert(ResourceType::Manga, attributes).into()
+    }
+
+    /// Add a chapter of a manga with the given attributes, in the format of the api.
+    pub fn add_chapter(&self, manga: MangaId, attributes: Value) -> ChapterId {
+        self.insert_related(
+            ResourceType::Chapter,
+            attributes,
+            vec![(ResourceType::Manga, manga.into())],
+        )
+        .into()
+    }
+
+    /// Add a scanlation group.
+    pub fn add_group(&self, name: &str, leader: UserId) -> GroupId {
+        let leader_object = self.store().user_object(leader.into());
+        self.insert_related(
+            ResourceType::ScanlationGroup,
+            json!({ "name": name, "leader": leader_object }),
+            vec![(ResourceType::User, leader.into())],
+        )
+        .into()
+    }
+
+    /// Credit a chapter to a scanlation group.
+    pub fn add_chapter_group(&self, chapter: ChapterId, group: GroupId) {
+        if let Some(chapter) = self.store().get_mut(ResourceType::Chapter, chapter.into()) {
+            chapter
+                .relationships
+                .push((ResourceType::ScanlationGroup, group.into()));
+        }
+    }
+
+    /// Add a cover of a manga, served by the uploads server at `covers/{manga}/{file_name}`.
+    ///
+    /// The first cover of a manga is its main cover, in the relationships of the manga.
+    pub fn add_cover(&self, manga: MangaId, file_name: &str, image: Vec<u8>) -> CoverId {
+        self.store()
+            .files
+            .insert(format!("covers/{}/{}", manga, file_name), image);
+        let cover = self.insert_related(
+            ResourceType::CoverArt,
+            json!({ "fileName": file_name }),
+            vec![(ResourceType::Manga, manga.into())],
+        );
+        if let Some(manga) = self.store().get_mut(ResourceType::Manga, manga.into()) {
+            if manga.related(ResourceType::CoverArt).next().is_none() {
+                manga.relationships.push((ResourceType::CoverArt, cover));
+            }
+        }
+        cover.into()
+    }
+
+    /// Add a page to a chapter, served by the MangaDex@Home server in both qualities.
+    pub fn add_page(&self, chapter: ChapterId, file_name: &str, image: Vec<u8>) {
+        let mut store = self.store();
+        let hash = store.new_id().to_simple().to_string();
+        let chapter = match store.get_mut(ResourceType::Chapter, chapter.into()) {
+            Some(chapter) => chapter,
+            None => return,
+        };
+
+        let attributes = &mut chapter.attributes;
+        let hash = match attributes.get("hash").and_then(Value::as_str) {
+            Some(current) if !current.is_empty() => current.to_string(),
+            _ => {
+                attributes.insert("hash".to_string(), json!(hash));
+                hash
+            }
+        };
+        for key in &["data", "dataSaver"] {
+            if let Some(Value::Array(files)) = attributes.get_mut(*key) {
+                files.push(json!(file_name));
+            }
+        }
+
+        for quality in &["data", "data-saver"] {
+            let path = format!("at-home/{}/{}/{}", quality, hash, file_name);
+            store.files.insert(path, image.clone());
+        }
+    }
+
+    /// Map a legacy numeric id to a new id.
+    pub fn add_legacy_mapping(&self, kind: MappingType, legacy_id: u32, new_id: Uuid) {
+        let kind = serde_json::to_value(kind).expect("mapping type serializes");
+        let kind = kind.as_str().unwrap_or_default().to_string();
+        self.store().legacy.push((kind, legacy_id, new_id));
+    }
+
+    /// Make a user follow a manga, group or user.
+    pub fn follow(&self, user: UserId, id: impl Into<Uuid>) {
+        let id = id.into();
+        let mut store = self.store();
+        let follows = store.follows.entry(user.into()).or_default();
+        if !follows.contains(&id) {
+            follows.push(id);
+        }
+    }
+
+    /// Get a resource, in the single entity envelope of the api.
+    pub fn resource(&self, id: impl Into<Uuid>) -> Option<Value> {
+        let id = id.into();
+        self.store()
+            .resources
+            .iter()
+            .find(|r| r.id == id)
+            .map(|r| r.entity())
+    }
+}
+
+impl Drop for FakeServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+async fn serve(
+    store: Arc<Mutex<Store>>,
+    base_url: String,
+    req: Request<Body>,
+) -> std::result::Result<Response<Body>, Infallible> {
+    let (parts, body) = req.into_parts();
+    let body = hyper::body::to_bytes(body).await.unwrap_or_default();
+
+    let req = FakeRequest {
+        method: parts.method,
+        path: parts.uri.path().to_string(),
+        query: Query::parse(parts.uri.query()),
+        body: serde_json::from_slice(&body).ok(),
+        token: parts
+            .headers
+            .get(hyper::header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .map(str::to_string),
+        base_url,
+    };
+
+    let res = {
+        let mut store = store.lock().expect("fake server lock poisoned");
+        routes::handle(&mut store, &req)
+    };
+
+    let res = Response::builder()
+        .status(res.status)
+        .header(hyper::header::CONTENT_TYPE, res.content_type)
+        .body(Body::from(res.body))
+        .expect("valid response");
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::manga::{FollowManga, GetManga, ListManga, ListMangaBuilder, UpdateManga};
+    use crate::api::{at_home::GetAtHomeServer, feed::FollowedMangaFeed};
+    use crate::schema::manga::MangaRequestBuilder;
+    use crate::Errors;
+    use assert_matches::assert_matches;
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn login_and_refresh() -> anyhow::Result<()> {
+        let server = FakeServer::start().await?;
+        server.add_user("test", "hunter12");
+
+        let mut client = server.client()?;
+        assert_matches!(client.login("test", "wrong").await, Err(Errors::Api(_)));
+
+        let session = client.login("test", "hunter12").await?.session.clone();
+        let refreshed = client.refresh_tokens().await?;
+        assert_ne!(refreshed.tokens.session, session);
+
+        client.logout().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn pagination_and_versions() -> anyhow::Result<()> {
+        let server = FakeServer::start().await?;
+        server.add_user("test", "hunter12");
+        let ids: Vec<MangaId> = (0..15)
+            .map(|i| server.add_manga(json!({ "title": { "en": format!("Manga {}", i) } })))
+            .collect();
+
+        let client = server.client()?;
+        let page = ListMangaBuilder::default()
+            .limit(10)
+            .offset(10)
+            .build()?
+            .send(&client)
+            .await?;
+        assert_eq!((page.limit, page.offset, page.total), (10, 10, 15));
+        assert_eq!(page.results.len(), 5);
+
+        let all = ListManga::default().send(&client).await?;
+        assert_eq!(all.results.len(), 10);
+
+        let mut client = client;
+        client.login("test", "hunter12").await?;
+        let update = |version| UpdateManga {
+            id: ids[0],
+            request: MangaRequestBuilder::default()
+                .title(crate::schema::LocalizedString::new())
+                .year(2021)
+                .version(version)
+                .build()
+                .unwrap(),
+        };
+
+        let manga = update(1).send(&client).await?.data;
+        assert_eq!(manga.attributes.version, 2);
+        assert_eq!(manga.attributes.year, Some(2021));
+        assert_matches!(update(1).send(&client).await, Err(Errors::Api(_)));
+
+        let manga = GetManga { id: ids[0] }.send(&client).await?.data;
+        assert_eq!(manga.attributes.version, 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn followed_feed_and_pages() -> anyhow::Result<()> {
+        let server = FakeServer::start().await?;
+        server.add_user("test", "hunter12");
+        let followed = server.add_manga(json!({ "title": { "en": "Followed" } }));
+        let other = server.add_manga(json!({ "title": { "en": "Other" } }));
+        let chapter = server.add_chapter(followed, json!({ "chapter": "1" }));
+        server.add_chapter(other, json!({ "chapter": "1" }));
+        server.add_page(chapter, "1.png", b"page one".to_vec());
+
+        let mut client = server.client()?;
+        client.login("test", "hunter12").await?;
+        FollowManga { id: followed }.send(&client).await?;
+
+        let feed = FollowedMangaFeed {
+            limit: None,
+            offset: None,
+            translated_language: Vec::new(),
+            created_at_since: None,
+            updated_at_since: None,
+            publish_at_since: None,
+            order: None,
+        }
+        .send(&client)
+        .await?;
+        assert_eq!(feed.total, 1);
+        let data = &feed.results[0].as_ref().unwrap().data;
+        assert_eq!(data.id, chapter);
+
+        let at_home = GetAtHomeServer {
+            chapter_id: chapter,
+            force_port443: false,
+        }
+        .send(&client)
+        .await?;
+        let page = format!(
+            "{}/data/{}/{}",
+            at_home, data.attributes.hash, data.attributes.data[0]
+        );
+        let bytes = reqwest::get(&page).await?.bytes().await?;
+        assert_eq!(&bytes[..], b"page one");
+
+        Ok(())
+    }
+}
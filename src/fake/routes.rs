@@ -0,0 +1,948 @@
+//! Request handling of the fake server.
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashSet};
+
+use chrono::Utc;
+use reqwest::Method;
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
+
+use super::store::{parse_time, timestamp, Resource, Store};
+use crate::schema::ResourceType;
+
+/// A decoded request.
+#[derive(Debug)]
+pub(crate) struct FakeRequest {
+    pub(crate) method: Method,
+    pub(crate) path: String,
+    pub(crate) query: Query,
+    pub(crate) body: Option<Value>,
+    pub(crate) token: Option<String>,
+    /// The base url of the server, without the trailing slash.
+    pub(crate) base_url: String,
+}
+
+/// A response to encode.
+#[derive(Debug)]
+pub(crate) struct FakeResponse {
+    pub(crate) status: u16,
+    pub(crate) content_type: &'static str,
+    pub(crate) body: Vec<u8>,
+}
+
+/// Handlers return the error response in `Err`, so `?` can be used.
+type Reply = Result<FakeResponse, FakeResponse>;
+
+/// The query parameters of a request.
+///
+/// Array keys are normalized, so `ids[]=a&ids[1]=b` are both read as `ids`.
+#[derive(Debug, Default)]
+pub(crate) struct Query(Vec<(String, String)>);
+
+impl Query {
+    pub(crate) fn parse(query: Option<&str>) -> Self {
+        let pairs = url::form_urlencoded::parse(query.unwrap_or_default().as_bytes())
+            .map(|(key, value)| {
+                let index = key
+                    .strip_suffix(']')
+                    .and_then(|k| k.split_once('['))
+                    .filter(|(_, index)| index.chars().all(|c| c.is_ascii_digit()));
+                let key = match index {
+                    Some((name, _)) => name.to_string(),
+                    None => key.into_owned(),
+                };
+                (key, value.into_owned())
+            })
+            .collect();
+        Self(pairs)
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn all(&self, key: &str) -> Vec<&str> {
+        self.0
+            .iter()
+            .filter(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+            .collect()
+    }
+}
+
+/// Handle a request.
+pub(crate) fn handle(store: &mut Store, req: &FakeRequest) -> FakeResponse {
+    route(store, req).unwrap_or_else(|err| err)
+}
+
+fn route(store: &mut Store, req: &FakeRequest) -> Reply {
+    let path = req.path.trim_matches('/');
+    if req.method == Method::GET {
+        if let Some(file) = store.files.get(path) {
+            return Ok(file_response(path, file.clone()));
+        }
+    }
+
+    let segments: Vec<&str> = path.split('/').collect();
+    match (&req.method, segments.as_slice()) {
+        (&Method::GET, ["ping"]) => Ok(FakeResponse {
+            status: 200,
+            content_type: "text/plain; charset=UTF-8",
+            body: b"pong".to_vec(),
+        }),
+
+        (&Method::POST, ["auth", "login"]) => login(store, req),
+        (&Method::GET, ["auth", "check"]) => {
+            let authenticated = authenticate(store, req).is_ok();
+            let role = if authenticated {
+                "ROLE_MEMBER"
+            } else {
+                "ROLE_GUEST"
+            };
+            ok(json!({
+                "result": "ok",
+                "isAuthenticated": authenticated,
+                "roles": [role],
+                "permissions": [],
+            }))
+        }
+        (&Method::POST, ["auth", "logout"]) => {
+            authenticate(store, req)?;
+            if let Some(token) = &req.token {
+                store.sessions.remove(token);
+            }
+            ok(json!({ "result": "ok" }))
+        }
+        (&Method::POST, ["auth", "refresh"]) => refresh(store, req),
+
+        (&Method::POST, ["legacy", "mapping"]) => legacy_mapping(store, req),
+
+        (&Method::GET, ["at-home", "server", id]) => {
+            let id = parse_id(id)?;
+            find(store, ResourceType::Chapter, id)?;
+            ok(json!({ "baseUrl": format!("{}/at-home", req.base_url) }))
+        }
+
+        (&Method::GET, ["user", "me"]) => {
+            let user = authenticate(store, req)?;
+            ok(find(store, ResourceType::User, user)?.entity())
+        }
+        (&Method::GET, ["user", "follows", "manga", "feed"]) => {
+            let user = authenticate(store, req)?;
+            let followed: HashSet<Uuid> = store.follows(user).iter().copied().collect();
+            feed(store, req, |chapter| {
+                chapter
+                    .related(ResourceType::Manga)
+                    .any(|manga| followed.contains(&manga))
+            })
+        }
+        (&Method::GET, ["user", "follows", kind]) => {
+            let kind = match *kind {
+                "manga" => ResourceType::Manga,
+                "group" => ResourceType::ScanlationGroup,
+                "user" => ResourceType::User,
+                _ => return Err(not_found("route")),
+            };
+            let user = authenticate(store, req)?;
+            let followed = store.follows(user);
+            let resources = followed
+                .iter()
+                .filter_map(|id| store.get(kind.clone(), *id))
+                .collect();
+            paginate(resources, &req.query)
+        }
+        (&Method::GET, ["user", "list"]) => {
+            let user = authenticate(store, req)?;
+            user_lists(store, req, user, true)
+        }
+        (&Method::GET, ["user", id, "list"]) => {
+            let user = parse_id(id)?;
+            let own = authenticate(store, req).ok() == Some(user);
+            user_lists(store, req, user, own)
+        }
+
+        (&Method::GET, ["manga", "tag"]) => {
+            let tags: Vec<Value> = store.all(ResourceType::Tag).map(Resource::entity).collect();
+            ok(Value::Array(tags))
+        }
+        (&Method::GET, ["manga", "random"]) => {
+            let manga: Vec<&Resource> = store.all(ResourceType::Manga).collect();
+            if manga.is_empty() {
+                return Err(not_found("manga"));
+            }
+            let index = Utc::now().timestamp_subsec_nanos() as usize % manga.len();
+            ok(manga[index].entity())
+        }
+        (&Method::GET, ["manga", "read"]) => {
+            let user = authenticate(store, req)?;
+            let ids = req
+                .query
+                .all("ids")
+                .into_iter()
+                .map(parse_id)
+                .collect::<Result<Vec<_>, _>>()?;
+            ok(json!({ "result": "ok", "data": read_markers(store, user, &ids) }))
+        }
+        (&Method::GET, ["manga", "status"]) => {
+            let user = authenticate(store, req)?;
+            let filter = req.query.get("status");
+            let statuses: BTreeMap<String, &String> = store
+                .statuses
+                .iter()
+                .filter(|((u, _), status)| {
+                    *u == user && (filter.is_none() || filter == Some(*status))
+                })
+                .map(|((_, manga), status)| (manga.to_string(), status))
+                .collect();
+            ok(json!({ "result": "ok", "statuses": statuses }))
+        }
+        (&Method::GET, ["manga", id, "feed"]) => {
+            let id = parse_id(id)?;
+            find(store, ResourceType::Manga, id)?;
+            feed(store, req, |chapter| {
+                chapter.related(ResourceType::Manga).any(|m| m == id)
+            })
+        }
+        (&Method::GET, ["manga", id, "aggregate"]) => {
+            let id = parse_id(id)?;
+            find(store, ResourceType::Manga, id)?;
+            aggregate(store, req, id)
+        }
+        (&Method::GET, ["manga", id, "read"]) => {
+            let user = authenticate(store, req)?;
+            let id = parse_id(id)?;
+            ok(json!({ "result": "ok", "data": read_markers(store, user, &[id]) }))
+        }
+        (&Method::GET, ["manga", id, "status"]) => {
+            let user = authenticate(store, req)?;
+            let id = parse_id(id)?;
+            find(store, ResourceType::Manga, id)?;
+            let status = store.statuses.get(&(user, id));
+            ok(json!({ "result": "ok", "status": status }))
+        }
+        (&Method::POST, ["manga", id, "status"]) => {
+            let user = authenticate(store, req)?;
+            let id = parse_id(id)?;
+            find(store, ResourceType::Manga, id)?;
+            match body(req)?.get("status").and_then(Value::as_str) {
+                Some(status) => store.statuses.insert((user, id), status.to_string()),
+                None => store.statuses.remove(&(user, id)),
+            };
+            ok(json!({ "result": "ok" }))
+        }
+        (method, ["manga" | "group" | "user", id, "follow"])
+            if *method == Method::POST || *method == Method::DELETE =>
+        {
+            let user = authenticate(store, req)?;
+            let kind = collection(segments[0]).expect("known collection");
+            let id = parse_id(id)?;
+            find(store, kind, id)?;
+            let follows = store.follows.entry(user).or_default();
+            follows.retain(|followed| *followed != id);
+            if *method == Method::POST {
+                follows.push(id);
+            }
+            ok(json!({ "result": "ok" }))
+        }
+        (method, ["manga", manga, "list", list])
+            if *method == Method::POST || *method == Method::DELETE =>
+        {
+            let user = authenticate(store, req)?;
+            let manga = parse_id(manga)?;
+            let list = parse_id(list)?;
+            find(store, ResourceType::Manga, manga)?;
+            owned_list(store, list, user)?;
+            let list = store
+                .get_mut(ResourceType::CustomList, list)
+                .expect("list exists");
+            list.relationships.retain(|(_, id)| *id != manga);
+            if *method == Method::POST {
+                list.relationships.push((ResourceType::Manga, manga));
+            }
+            ok(json!({ "result": "ok" }))
+        }
+        (method, ["chapter", id, "read"])
+            if *method == Method::POST || *method == Method::DELETE =>
+        {
+            let user = authenticate(store, req)?;
+            let id = parse_id(id)?;
+            find(store, ResourceType::Chapter, id)?;
+            let read = store.read.entry(user).or_default();
+            if *method == Method::POST {
+                read.insert(id);
+            } else {
+                read.remove(&id);
+            }
+            ok(json!({ "result": "ok" }))
+        }
+        (&Method::GET, ["list", id, "feed"]) => {
+            let id = parse_id(id)?;
+            let list = visible_list(store, req, id)?;
+            let manga: HashSet<Uuid> = list.related(ResourceType::Manga).collect();
+            feed(store, req, |chapter| {
+                chapter
+                    .related(ResourceType::Manga)
+                    .any(|m| manga.contains(&m))
+            })
+        }
+        (&Method::GET, ["list", id]) => {
+            let id = parse_id(id)?;
+            ok(visible_list(store, req, id)?.entity())
+        }
+
+        (&Method::GET, [name]) => {
+            let kind = collection(name).ok_or_else(|| not_found("route"))?;
+            let mut resources = filter(store.all(kind.clone()).collect(), &req.query)?;
+            // Like the api, pornographic manga are only listed when asked for.
+            if kind == ResourceType::Manga && req.query.all("contentRating").is_empty() {
+                resources
+                    .retain(|r| r.attributes.get("contentRating") != Some(&json!("pornographic")));
+            }
+            paginate(resources, &req.query)
+        }
+        (&Method::POST, [name @ ("manga" | "author" | "group" | "list")]) => {
+            let user = authenticate(store, req)?;
+            let kind = collection(name).expect("known collection");
+            create(store, req, kind, user)
+        }
+        (&Method::GET, [name, id]) => {
+            let kind = collection(name).ok_or_else(|| not_found("route"))?;
+            ok(find(store, kind, parse_id(id)?)?.entity())
+        }
+        (&Method::PUT, [name, id]) => {
+            let user = authenticate(store, req)?;
+            let kind = collection(name).ok_or_else(|| not_found("route"))?;
+            update(store, req, kind, parse_id(id)?, user)
+        }
+        (&Method::DELETE, [name, id]) => {
+            let user = authenticate(store, req)?;
+            let kind = collection(name).ok_or_else(|| not_found("route"))?;
+            let id = parse_id(id)?;
+            if kind == ResourceType::CustomList {
+                owned_list(store, id, user)?;
+            }
+            if !store.remove(kind, id) {
+                return Err(not_found(name));
+            }
+            ok(json!({ "result": "ok" }))
+        }
+
+        _ => Err(error(
+            404,
+            "not_found_http_exception",
+            &format!("No route found for \"{} /{}\"", req.method, path),
+        )),
+    }
+}
+
+fn collection(name: &str) -> Option<ResourceType> {
+    Some(match name {
+        "manga" => ResourceType::Manga,
+        "chapter" => ResourceType::Chapter,
+        "author" => ResourceType::Author,
+        "group" => ResourceType::ScanlationGroup,
+        "cover" => ResourceType::CoverArt,
+        "list" => ResourceType::CustomList,
+        "user" => ResourceType::User,
+        _ => return None,
+    })
+}
+
+fn ok(body: Value) -> Reply {
+    Ok(json_response(200, &body))
+}
+
+fn json_response(status: u16, body: &Value) -> FakeResponse {
+    FakeResponse {
+        status,
+        content_type: "application/json",
+        body: body.to_string().into_bytes(),
+    }
+}
+
+fn file_response(path: &str, body: Vec<u8>) -> FakeResponse {
+    let content_type = match path.rsplit('.').next() {
+        Some("png") => "image/png",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        _ => "application/octet-stream",
+    };
+    FakeResponse {
+        status: 200,
+        content_type,
+        body,
+    }
+}
+
+/// An error response, in the envelope used by the api.
+pub(crate) fn error(status: u16, title: &str, detail: &str) -> FakeResponse {
+    json_response(
+        status,
+        &json!({
+            "result": "error",
+            "errors": [{
+                "id": Uuid::nil(),
+                "status": status,
+                "title": title,
+                "detail": detail,
+            }],
+        }),
+    )
+}
+
+fn not_found(what: &str) -> FakeResponse {
+    error(
+        404,
+        "not_found_http_exception",
+        &format!("{} not found", what),
+    )
+}
+
+fn bad_request(detail: &str) -> FakeResponse {
+    error(400, "validation_exception", detail)
+}
+
+fn parse_id(id: &str) -> Result<Uuid, FakeResponse> {
+    Uuid::parse_str(id).map_err(|_| bad_request(&format!("\"{}\" is not a valid uuid", id)))
+}
+
+fn find(store: &Store, kind: ResourceType, id: Uuid) -> Result<&Resource, FakeResponse> {
+    store
+        .get(kind.clone(), id)
+        .ok_or_else(|| not_found(kind.as_str()))
+}
+
+fn body(req: &FakeRequest) -> Result<Map<String, Value>, FakeResponse> {
+    match &req.body {
+        Some(Value::Object(body)) => Ok(body.clone()),
+        _ => Err(bad_request("expected a json object body")),
+    }
+}
+
+fn authenticate(store: &Store, req: &FakeRequest) -> Result<Uuid, FakeResponse> {
+    req.token
+        .as_ref()
+        .and_then(|token| store.sessions.get(token))
+        .copied()
+        .ok_or_else(|| {
+            error(
+                401,
+                "unauthorized_http_exception",
+                "Missing or expired session token",
+            )
+        })
+}
+
+fn login(store: &mut Store, req: &FakeRequest) -> Reply {
+    let body = body(req)?;
+    let username = body.get("username").and_then(Value::as_str);
+    let password = body.get("password").and_then(Value::as_str);
+
+    let user = match (username, password) {
+        (Some(username), Some(password)) => store
+            .accounts
+            .get(username)
+            .filter(|account| account.password == password)
+            .map(|account| account.user_id),
+        _ => None,
+    }
+    .ok_or_else(|| {
+        error(
+            401,
+            "unauthorized_http_exception",
+            "User / Password does not match",
+        )
+    })?;
+
+    let (session, refresh) = issue_tokens(store, user);
+    ok(json!({
+        "result": "ok",
+        "token": { "session": session, "refresh": refresh },
+    }))
+}
+
+fn refresh(store: &mut Store, req: &FakeRequest) -> Reply {
+    let body = body(req)?;
+    let token = body
+        .get("token")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let user = store
+        .refresh_tokens
+        .remove(token)
+        .ok_or_else(|| error(401, "unauthorized_http_exception", "Invalid refresh token"))?;
+
+    let (session, refresh) = issue_tokens(store, user);
+    ok(json!({
+        "result": "ok",
+        "token": { "session": session, "refresh": refresh },
+        "message": "Token refreshed",
+    }))
+}
+
+fn issue_tokens(store: &mut Store, user: Uuid) -> (String, String) {
+    let session = store.new_token("session");
+    let refresh = store.new_token("refresh");
+    store.sessions.insert(session.clone(), user);
+    store.refresh_tokens.insert(refresh.clone(), user);
+    (session, refresh)
+}
+
+fn legacy_mapping(store: &Store, req: &FakeRequest) -> Reply {
+    let body = body(req)?;
+    let kind = body.get("type").and_then(Value::as_str).unwrap_or_default();
+    let ids: Vec<u64> = body
+        .get("ids")
+        .and_then(Value::as_array)
+        .map(|ids| ids.iter().filter_map(Value::as_u64).collect())
+        .unwrap_or_default();
+
+    let mappings: Vec<Value> = store
+        .legacy
+        .iter()
+        .filter(|(t, legacy_id, _)| t == kind && ids.contains(&u64::from(*legacy_id)))
+        .map(|(t, legacy_id, new_id)| {
+            json!({
+                "result": "ok",
+                "data": {
+                    "id": Uuid::from_u128(u128::from(*legacy_id)),
+                    "type": "mapping_id",
+                    "attributes": { "type": t, "legacyId": legacy_id, "newId": new_id },
+                },
+                "relationships": [],
+            })
+        })
+        .collect();
+    ok(Value::Array(mappings))
+}
+
+fn read_markers(store: &Store, user: Uuid, manga: &[Uuid]) -> Vec<Uuid> {
+    let read = match store.read.get(&user) {
+        Some(read) => read,
+        None => return Vec::new(),
+    };
+    store
+        .all(ResourceType::Chapter)
+        .filter(|chapter| read.contains(&chapter.id))
+        .filter(|chapter| {
+            chapter
+                .related(ResourceType::Manga)
+                .any(|m| manga.contains(&m))
+        })
+        .map(|chapter| chapter.id)
+        .collect()
+}
+
+fn owned_list(store: &Store, id: Uuid, user: Uuid) -> Result<&Resource, FakeResponse> {
+    let list = find(store, ResourceType::CustomList, id)?;
+    if list.related(ResourceType::User).any(|owner| owner == user) {
+        Ok(list)
+    } else {
+        Err(error(
+            403,
+            "forbidden_http_exception",
+            "You can only edit your own lists",
+        ))
+    }
+}
+
+fn visible_list<'a>(
+    store: &'a Store,
+    req: &FakeRequest,
+    id: Uuid,
+) -> Result<&'a Resource, FakeResponse> {
+    let list = find(store, ResourceType::CustomList, id)?;
+    let public = list.attributes.get("visibility") == Some(&json!("public"));
+    let own = authenticate(store, req)
+        .map(|user| list.related(ResourceType::User).any(|owner| owner == user))
+        .unwrap_or(false);
+    if public || own {
+        Ok(list)
+    } else {
+        Err(not_found("custom_list"))
+    }
+}
+
+fn user_lists(store: &Store, req: &FakeRequest, user: Uuid, own: bool) -> Reply {
+    let lists = store
+        .all(ResourceType::CustomList)
+        .filter(|list| list.related(ResourceType::User).any(|owner| owner == user))
+        .filter(|list| own || list.attributes.get("visibility") == Some(&json!("public")))
+        .collect();
+    paginate(lists, &req.query)
+}
+
+fn feed(store: &Store, req: &FakeRequest, include: impl Fn(&Resource) -> bool) -> Reply {
+    let chapters = store
+        .all(ResourceType::Chapter)
+        .filter(|chapter| include(chapter))
+        .collect();
+    paginate(filter(chapters, &req.query)?, &req.query)
+}
+
+fn aggregate(store: &Store, req: &FakeRequest, manga: Uuid) -> Reply {
+    let languages = req.query.all("translatedLanguage");
+    let mut volumes: BTreeMap<String, BTreeMap<String, i64>> = BTreeMap::new();
+
+    for chapter in store.all(ResourceType::Chapter) {
+        if !chapter.related(ResourceType::Manga).any(|m| m == manga) {
+            continue;
+        }
+        let attr = |key| {
+            chapter
+                .attributes
+                .get(key)
+                .and_then(Value::as_str)
+                .unwrap_or("none")
+                .to_string()
+        };
+        if !languages.is_empty() && !languages.contains(&attr("translatedLanguage").as_str()) {
+            continue;
+        }
+        *volumes
+            .entry(attr("volume"))
+            .or_default()
+            .entry(attr("chapter"))
+            .or_default() += 1;
+    }
+
+    let volumes: Map<String, Value> = volumes
+        .into_iter()
+        .map(|(volume, chapters)| {
+            let count: i64 = chapters.values().sum();
+            let chapters: Map<String, Value> = chapters
+                .into_iter()
+                .map(|(chapter, count)| {
+                    let value = json!({ "chapter": chapter, "count": count });
+                    (chapter, value)
+                })
+                .collect();
+            let value = json!({ "volume": volume, "count": count, "chapters": chapters });
+            (volume, value)
+        })
+        .collect();
+    ok(json!({ "result": "ok", "volumes": volumes }))
+}
+
+/// Move the fields that are relationships in responses out of a request body.
+fn take_relationships(
+    store: &Store,
+    kind: ResourceType,
+    body: &mut Map<String, Value>,
+) -> Result<Vec<(ResourceType, Uuid)>, FakeResponse> {
+    let fields: &[(&str, ResourceType)] = match kind {
+        ResourceType::Manga => &[
+            ("authors", ResourceType::Author),
+            ("artists", ResourceType::Artist),
+        ],
+        ResourceType::ScanlationGroup => &[("members", ResourceType::User)],
+        ResourceType::CustomList => &[("manga", ResourceType::Manga)],
+        _ => &[],
+    };
+
+    let mut relationships = Vec::new();
+    for (field, related) in fields {
+        if let Some(ids) = body.remove(*field) {
+            let ids: Vec<Uuid> = serde_json::from_value(ids)
+                .map_err(|_| bad_request(&format!("\"{}\" must be a list of uuids", field)))?;
+            relationships.extend(ids.into_iter().map(|id| (related.clone(), id)));
+        }
+    }
+
+    if kind == ResourceType::ScanlationGroup {
+        if let Some(leader) = body.get("leader").and_then(Value::as_str) {
+            let leader = parse_id(leader)?;
+            let user = store.user_object(leader).ok_or_else(|| not_found("user"))?;
+            body.insert("leader".to_string(), user);
+            relationships.push((ResourceType::User, leader));
+        }
+    }
+
+    Ok(relationships)
+}
+
+fn create(store: &mut Store, req: &FakeRequest, kind: ResourceType, user: Uuid) -> Reply {
+    let mut body = body(req)?;
+    body.remove("version");
+    body.remove("modNotes");
+
+    let mut relationships = take_relationships(store, kind.clone(), &mut body)?;
+    if kind == ResourceType::CustomList {
+        let owner = store.user_object(user).ok_or_else(|| not_found("user"))?;
+        body.insert("owner".to_string(), owner);
+        relationships.push((ResourceType::User, user));
+    }
+
+    let id = store.insert(kind.clone(), body, relationships);
+    ok(find(store, kind, id)?.entity())
+}
+
+fn update(store: &mut Store, req: &FakeRequest, kind: ResourceType, id: Uuid, user: Uuid) -> Reply {
+    let mut body = body(req)?;
+    body.remove("id");
+    body.remove("modNotes");
+
+    let current = if kind == ResourceType::CustomList {
+        owned_list(store, id, user)?.version()
+    } else {
+        find(store, kind.clone(), id)?.version()
+    };
+    let version = body
+        .remove("version")
+        .and_then(|v| v.as_i64())
+        .ok_or_else(|| bad_request("\"version\" is required"))?;
+    if version != current {
+        return Err(error(
+            409,
+            "conflict_http_exception",
+            &format!(
+                "Version mismatch: the current version is {}, got {}",
+                current, version
+            ),
+        ));
+    }
+
+    let replaced: Vec<ResourceType> = match &kind {
+        ResourceType::Manga => [
+            ("authors", ResourceType::Author),
+            ("artists", ResourceType::Artist),
+        ]
+        .iter()
+        .filter(|(field, _)| body.contains_key(*field))
+        .map(|(_, kind)| kind.clone())
+        .collect(),
+        ResourceType::ScanlationGroup => vec![ResourceType::User],
+        ResourceType::CustomList if body.contains_key("manga") => vec![ResourceType::Manga],
+        _ => Vec::new(),
+    };
+    let relationships = take_relationships(store, kind.clone(), &mut body)?;
+
+    let resource = store.get_mut(kind, id).expect("resource exists");
+    resource
+        .relationships
+        .retain(|(kind, _)| !replaced.contains(kind));
+    resource.relationships.extend(relationships);
+    resource.attributes.extend(body);
+    resource
+        .attributes
+        .insert("version".to_string(), json!(current + 1));
+    if resource.attributes.contains_key("updatedAt") {
+        resource
+            .attributes
+            .insert("updatedAt".to_string(), json!(timestamp(Utc::now())));
+    }
+
+    ok(resource.entity())
+}
+
+/// Query parameters that aren't attribute filters.
+const RESERVED: &[&str] = &[
+    "limit",
+    "offset",
+    "includes",
+    "ids",
+    "title",
+    "name",
+    "username",
+    "authors",
+    "artists",
+    "manga",
+    "groups",
+    "includedTags",
+    "includedTagsMode",
+    "excludedTags",
+    "excludedTagsMode",
+];
+
+fn filter<'a>(
+    mut resources: Vec<&'a Resource>,
+    query: &Query,
+) -> Result<Vec<&'a Resource>, FakeResponse> {
+    let ids = query
+        .all("ids")
+        .into_iter()
+        .map(parse_id)
+        .collect::<Result<HashSet<_>, _>>()?;
+    if !ids.is_empty() {
+        resources.retain(|r| ids.contains(&r.id));
+    }
+
+    for key in &["title", "name", "username"] {
+        if let Some(text) = query.get(key) {
+            let text = text.to_lowercase();
+            resources.retain(|r| {
+                let mut values = Vec::new();
+                strings(r.attributes.get(*key), &mut values);
+                if *key == "title" {
+                    strings(r.attributes.get("altTitles"), &mut values);
+                }
+                values.iter().any(|v| v.to_lowercase().contains(&text))
+            });
+        }
+    }
+
+    for (key, kind) in &[
+        ("authors", ResourceType::Author),
+        ("artists", ResourceType::Artist),
+        ("manga", ResourceType::Manga),
+        ("groups", ResourceType::ScanlationGroup),
+    ] {
+        let ids = query
+            .all(key)
+            .into_iter()
+            .map(parse_id)
+            .collect::<Result<HashSet<_>, _>>()?;
+        if !ids.is_empty() {
+            resources.retain(|r| r.related(kind.clone()).any(|id| ids.contains(&id)));
+        }
+    }
+
+    let tags = |key| {
+        query
+            .all(key)
+            .into_iter()
+            .map(parse_id)
+            .collect::<Result<HashSet<_>, _>>()
+    };
+    let (included, excluded) = (tags("includedTags")?, tags("excludedTags")?);
+    let included_or = query.get("includedTagsMode") == Some("OR");
+    let excluded_and = query.get("excludedTagsMode") == Some("AND");
+    resources.retain(|r| {
+        let manga_tags: HashSet<Uuid> = r
+            .attributes
+            .get("tags")
+            .and_then(Value::as_array)
+            .map(|tags| {
+                tags.iter()
+                    .filter_map(|tag| tag.get("id"))
+                    .filter_map(|id| serde_json::from_value(id.clone()).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let included = included.is_empty()
+            || if included_or {
+                included.iter().any(|t| manga_tags.contains(t))
+            } else {
+                included.iter().all(|t| manga_tags.contains(t))
+            };
+        let excluded = !excluded.is_empty()
+            && if excluded_and {
+                excluded.iter().all(|t| manga_tags.contains(t))
+            } else {
+                excluded.iter().any(|t| manga_tags.contains(t))
+            };
+        included && !excluded
+    });
+
+    let mut keys: Vec<&str> = query.0.iter().map(|(k, _)| k.as_str()).collect();
+    keys.dedup();
+    for key in keys {
+        if RESERVED.contains(&key) || key.starts_with("order[") {
+            continue;
+        }
+
+        if let Some(attribute) = key.strip_suffix("Since") {
+            let since = query
+                .get(key)
+                .and_then(parse_time)
+                .ok_or_else(|| bad_request(&format!("\"{}\" must be a date", key)))?;
+            resources.retain(|r| {
+                r.attributes
+                    .get(attribute)
+                    .and_then(Value::as_str)
+                    .and_then(parse_time)
+                    .is_some_and(|time| time >= since)
+            });
+            continue;
+        }
+
+        let values = query.all(key);
+        resources.retain(|r| match r.attributes.get(key) {
+            Some(Value::String(s)) => values.contains(&s.as_str()),
+            Some(Value::Number(n)) => values.contains(&n.to_string().as_str()),
+            Some(Value::Bool(b)) => values.contains(&b.to_string().as_str()),
+            // Not an attribute of this resource: ignored, like unknown parameters.
+            None => true,
+            Some(_) => false,
+        });
+    }
+
+    for (key, direction) in query.0.iter().rev() {
+        let field = match key.strip_prefix("order[").and_then(|k| k.strip_suffix(']')) {
+            Some(field) => field,
+            None => continue,
+        };
+        // Sorts are stable, so applying them in reverse gives priority to the first one.
+        resources.sort_by(|a, b| {
+            let ordering = compare(a.attributes.get(field), b.attributes.get(field));
+            if direction == "desc" {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+    }
+
+    Ok(resources)
+}
+
+/// Collect the strings of a string, localized string or list of localized strings.
+fn strings(value: Option<&Value>, out: &mut Vec<String>) {
+    match value {
+        Some(Value::String(s)) => out.push(s.clone()),
+        Some(Value::Object(map)) => map.values().for_each(|v| strings(Some(v), out)),
+        Some(Value::Array(list)) => list.iter().for_each(|v| strings(Some(v), out)),
+        _ => {}
+    }
+}
+
+/// Compare two attributes, numerically when both are numbers (even as strings).
+fn compare(a: Option<&Value>, b: Option<&Value>) -> Ordering {
+    let number = |v: Option<&Value>| match v {
+        Some(Value::Number(n)) => n.as_f64(),
+        Some(Value::String(s)) => s.parse::<f64>().ok(),
+        _ => None,
+    };
+    match (number(a), number(b)) {
+        (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        _ => {
+            let string =
+                |v: Option<&Value>| v.and_then(Value::as_str).unwrap_or_default().to_string();
+            string(a).cmp(&string(b))
+        }
+    }
+}
+
+fn paginate(resources: Vec<&Resource>, query: &Query) -> Reply {
+    let number = |key, default| match query.get(key) {
+        Some(value) => value
+            .parse::<usize>()
+            .map_err(|_| bad_request(&format!("\"{}\" must be a positive integer", key))),
+        None => Ok(default),
+    };
+    let limit = number("limit", 10)?;
+    let offset = number("offset", 0)?;
+    if limit > 100 {
+        return Err(bad_request("\"limit\" must be between 0 and 100"));
+    }
+    if offset + limit > 10_000 {
+        return Err(bad_request("\"offset\" + \"limit\" must be at most 10000"));
+    }
+
+    let results: Vec<Value> = resources
+        .iter()
+        .skip(offset)
+        .take(limit)
+        .map(|r| r.entity())
+        .collect();
+    ok(json!({
+        "results": results,
+        "limit": limit,
+        "offset": offset,
+        "total": resources.len(),
+    }))
+}
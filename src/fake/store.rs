@@ -0,0 +1,250 @@
+//! In-memory state of the fake server.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
+
+use crate::schema::ResourceType;
+
+/// A resource stored by the fake server.
+#[derive(Debug, Clone)]
+pub(crate) struct Resource {
+    pub(crate) id: Uuid,
+    pub(crate) kind: ResourceType,
+    pub(crate) attributes: Map<String, Value>,
+    pub(crate) relationships: Vec<(ResourceType, Uuid)>,
+}
+
+impl Resource {
+    /// The resource as a `data` object.
+    pub(crate) fn data(&self) -> Value {
+        json!({
+            "id": self.id,
+            "type": self.kind,
+            "attributes": self.attributes,
+        })
+    }
+
+    /// The resource in the single entity envelope.
+    pub(crate) fn entity(&self) -> Value {
+        json!({
+            "result": "ok",
+            "data": self.data(),
+            "relationships": self
+                .relationships
+                .iter()
+                .map(|(kind, id)| json!({ "id": id, "type": kind }))
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    pub(crate) fn related(&self, kind: ResourceType) -> impl Iterator<Item = Uuid> + '_ {
+        self.relationships
+            .iter()
+            .filter(move |(k, _)| *k == kind)
+            .map(|(_, id)| *id)
+    }
+
+    pub(crate) fn version(&self) -> i64 {
+        self.attributes
+            .get("version")
+            .and_then(Value::as_i64)
+            .unwrap_or(1)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Account {
+    pub(crate) password: String,
+    pub(crate) user_id: Uuid,
+}
+
+/// Everything known to the fake server.
+#[derive(Debug, Default)]
+pub(crate) struct Store {
+    next_id: u128,
+    /// Resources, in creation order.
+    pub(crate) resources: Vec<Resource>,
+    /// Accounts by username.
+    pub(crate) accounts: HashMap<String, Account>,
+    /// Users by session token.
+    pub(crate) sessions: HashMap<String, Uuid>,
+    /// Users by refresh token.
+    pub(crate) refresh_tokens: HashMap<String, Uuid>,
+    /// Followed manga, groups and users, by follower.
+    pub(crate) follows: HashMap<Uuid, Vec<Uuid>>,
+    /// Reading statuses, by user and manga.
+    pub(crate) statuses: HashMap<(Uuid, Uuid), String>,
+    /// Read chapters, by user.
+    pub(crate) read: HashMap<Uuid, HashSet<Uuid>>,
+    /// Static files (page images, covers), by path.
+    pub(crate) files: HashMap<String, Vec<u8>>,
+    /// Legacy id mappings, as (type, legacy id, new id).
+    pub(crate) legacy: Vec<(String, u32, Uuid)>,
+}
+
+impl Store {
+    /// Generate a new id. Ids are deterministic, so test runs are reproducible.
+    pub(crate) fn new_id(&mut self) -> Uuid {
+        self.next_id += 1;
+        // A version 4, variant 1 uuid built from the counter.
+        Uuid::from_u128(0xfa4e0000_0000_4000_8000_000000000000 | self.next_id)
+    }
+
+    /// Generate a new opaque token.
+    pub(crate) fn new_token(&mut self, prefix: &str) -> String {
+        format!("{}-{}", prefix, self.new_id().to_simple())
+    }
+
+    pub(crate) fn get(&self, kind: ResourceType, id: Uuid) -> Option<&Resource> {
+        self.resources.iter().find(|r| r.id == id && r.kind == kind)
+    }
+
+    pub(crate) fn get_mut(&mut self, kind: ResourceType, id: Uuid) -> Option<&mut Resource> {
+        self.resources
+            .iter_mut()
+            .find(|r| r.id == id && r.kind == kind)
+    }
+
+    pub(crate) fn all(&self, kind: ResourceType) -> impl Iterator<Item = &Resource> {
+        self.resources.iter().filter(move |r| r.kind == kind)
+    }
+
+    pub(crate) fn remove(&mut self, kind: ResourceType, id: Uuid) -> bool {
+        let len = self.resources.len();
+        self.resources.retain(|r| !(r.id == id && r.kind == kind));
+        for resource in &mut self.resources {
+            resource.relationships.retain(|(_, related)| *related != id);
+        }
+        self.resources.len() != len
+    }
+
+    /// Insert a new resource, filling the attributes the api always returns.
+    pub(crate) fn insert(
+        &mut self,
+        kind: ResourceType,
+        mut attributes: Map<String, Value>,
+        relationships: Vec<(ResourceType, Uuid)>,
+    ) -> Uuid {
+        let id = self.new_id();
+        let now = timestamp(Utc::now());
+
+        for (key, value) in defaults(&kind) {
+            attributes.entry(key).or_insert(value);
+        }
+        if kind == ResourceType::Chapter {
+            attributes
+                .entry("publishAt".to_string())
+                .or_insert_with(|| Value::String(now.clone()));
+        }
+        attributes.insert("version".to_string(), json!(1));
+        attributes.insert("createdAt".to_string(), json!(now));
+        attributes.insert("updatedAt".to_string(), json!(now));
+        if matches!(kind, ResourceType::User | ResourceType::CustomList) {
+            attributes.remove("createdAt");
+            attributes.remove("updatedAt");
+        }
+
+        self.resources.push(Resource {
+            id,
+            kind,
+            attributes,
+            relationships,
+        });
+        id
+    }
+
+    /// The `user` object embedded in groups and lists.
+    pub(crate) fn user_object(&self, id: Uuid) -> Option<Value> {
+        self.get(ResourceType::User, id).map(Resource::data)
+    }
+
+    pub(crate) fn follows(&self, user: Uuid) -> &[Uuid] {
+        self.follows.get(&user).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// The attributes a freshly created resource has if they aren't specified.
+fn defaults(kind: &ResourceType) -> Vec<(String, Value)> {
+    let defaults = match kind {
+        ResourceType::Manga => json!({
+            "title": {},
+            "altTitles": [],
+            "description": {},
+            "isLocked": false,
+            "links": null,
+            "originalLanguage": "ja",
+            "lastVolume": null,
+            "lastChapter": null,
+            "publicationDemographic": null,
+            "status": null,
+            "year": null,
+            "contentRating": "safe",
+            "tags": [],
+        }),
+        ResourceType::Chapter => json!({
+            "title": "",
+            "volume": null,
+            "chapter": null,
+            "translatedLanguage": "en",
+            "hash": "",
+            "data": [],
+            "dataSaver": [],
+            "uploader": Uuid::nil(),
+        }),
+        ResourceType::CoverArt => json!({
+            "volume": null,
+            "fileName": "",
+            "description": null,
+        }),
+        ResourceType::Author | ResourceType::Artist => json!({
+            "name": "",
+            "imageUrl": null,
+        }),
+        ResourceType::ScanlationGroup => json!({
+            "name": "",
+            "website": null,
+            "ircServer": null,
+            "ircChannel": null,
+            "discord": null,
+            "contactEmail": null,
+            "description": null,
+            "locked": false,
+        }),
+        ResourceType::Tag => json!({
+            "name": {},
+            "description": [],
+            "group": "genre",
+        }),
+        ResourceType::User => json!({ "username": "" }),
+        ResourceType::CustomList => json!({
+            "name": "",
+            "visibility": "private",
+        }),
+        _ => json!({}),
+    };
+
+    match defaults {
+        Value::Object(map) => map.into_iter().collect(),
+        _ => unreachable!(),
+    }
+}
+
+/// Format a time the way the api does.
+pub(crate) fn timestamp(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m-%dT%H:%M:%S+00:00").to_string()
+}
+
+/// Parse a time sent by a client or stored by the server.
+pub(crate) fn parse_time(time: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(time)
+        .map(|t| t.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M:%S")
+                .ok()
+                .map(|t| t.and_utc())
+        })
+}
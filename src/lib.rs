@@ -29,7 +29,9 @@
 //!
 //! #[tokio::main]
 //! async fn main() -> anyhow::Result<()> {
-//!     let client = Client::default();
+//!     let client = Client::builder()
+//!         .preferred_languages(vec![LanguageCode::English, LanguageCode::JapaneseRomanized])
+//!         .build()?;
 //!
 //!     let list_manga = ListMangaBuilder::default()
 //!         .add_status(MangaStatus::Ongoing)
@@ -39,13 +41,8 @@
 //!
 //!     for manga_result in &mangas.results {
 //!         if let Ok(manga) = &manga_result {
-//!             let english_title = manga
-//!                 .data
-//!                 .attributes
-//!                 .title
-//!                 .get(&LanguageCode::English)
-//!                 .unwrap();
-//!             println!("Got manga {} with id: {:x}", english_title, manga.data.id);
+//!             let title = manga.data.display_title(client.preferred_languages());
+//!             println!("Got manga {} with id: {:x}", title, manga.data.id);
 //!         }
 //!     }
 //!
@@ -59,17 +56,36 @@
 #[macro_use]
 mod client;
 
+mod coalesce;
 mod common;
 mod errors;
 // mod jwt;
+mod policy;
 
 pub mod api;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod cache;
+pub mod cassette;
+#[cfg(feature = "fake-server")]
+pub mod fake;
+pub mod middleware;
+pub mod migration;
+#[cfg(feature = "opds")]
+pub mod opds;
 pub mod schema;
+pub mod search;
+pub mod syndication;
+pub mod tags;
+pub mod watch;
+pub mod web;
 
 pub(crate) use common::*;
 
-pub use client::Client;
+pub use client::{Client, ClientBuilder};
+pub use common::{Endpoint, FromResponse, Multipart};
 pub use errors::{Errors, Result};
+pub use policy::{ContentPolicy, RateLimit, RetryPolicy};
 
 pub use reqwest;
 
@@ -0,0 +1,256 @@
+//! Request/response middleware
+//!
+//! Middlewares are registered on the [`ClientBuilder`][crate::ClientBuilder] and wrap every call
+//! made by the [`Client`][crate::Client], in the order they were added. Each middleware receives
+//! the outgoing request and decides what to do with it: it may modify it, pass it on to the next
+//! middleware with [`Next::run()`], retry it, or answer it directly without touching the network.
+//!
+//! ```rust,no_run
+//! use mangadex::middleware::{async_trait, Middleware, Next, RequestContext};
+//! use mangadex::reqwest::{Request, Response};
+//! use mangadex::Client;
+//!
+//! struct Logger;
+//!
+//! #[async_trait]
+//! impl Middleware for Logger {
+//!     async fn handle(
+//!         &self,
+//!         req: Request,
+//!         ctx: &RequestContext,
+//!         next: Next<'_>,
+//!     ) -> mangadex::Result<Response> {
+//!         println!("{} {}", ctx.method, ctx.path);
+//!         let res = next.run(req, ctx).await?;
+//!         println!("{} {} -> {}", ctx.method, ctx.path, res.status());
+//!         Ok(res)
+//!     }
+//! }
+//!
+//! # fn main() -> mangadex::Result<()> {
+//! let client = Client::builder().middleware(Logger).build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::sync::Arc;
+
+use reqwest::{Method, Request, Response};
+
+use crate::{Client, Result};
+
+pub use async_trait::async_trait;
+
+/// Information about the endpoint that originated a request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestContext {
+    /// The HTTP method of the endpoint.
+    pub method: Method,
+    /// The path of the endpoint, relative to the base url (e.g. `/manga/{id}` with the id filled).
+    pub path: String,
+    /// Whether the endpoint requires authentication.
+    pub require_auth: bool,
+}
+
+/// A middleware wrapping the requests sent by the [`Client`].
+#[async_trait]
+pub trait Middleware: Send + Sync + 'static {
+    /// Handle a request.
+    ///
+    /// Call `next.run(req, ctx)` to pass the request on to the rest of the chain. To retry a
+    /// request, clone it with [`Request::try_clone()`] before running it.
+    async fn handle(&self, req: Request, ctx: &RequestContext, next: Next<'_>) -> Result<Response>;
+}
+
+/// The rest of the middleware chain.
+#[derive(Clone, Copy)]
+pub struct Next<'a> {
+    client: &'a Client,
+    middlewares: &'a [Arc<dyn Middleware>],
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(client: &'a Client, middlewares: &'a [Arc<dyn Middleware>]) -> Self {
+        Self {
+            client,
+            middlewares,
+        }
+    }
+
+    /// Run the request through the rest of the chain, and finally send it.
+    pub async fn run(self, req: Request, ctx: &RequestContext) -> Result<Response> {
+        match self.middlewares.split_first() {
+            Some((middleware, rest)) => {
+                middleware
+                    .handle(req, ctx, Next::new(self.client, rest))
+                    .await
+            }
+            None => self.client.execute(req).await,
+        }
+    }
+}
+
+impl fmt::Debug for Next<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Next")
+            .field("middlewares", &self.middlewares.len())
+            .finish()
+    }
+}
+
+/// The middlewares registered on a client.
+#[derive(Clone, Default)]
+pub(crate) struct MiddlewareStack(pub(crate) Vec<Arc<dyn Middleware>>);
+
+impl fmt::Debug for MiddlewareStack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("MiddlewareStack")
+            .field(&self.0.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::api::auth::CheckToken;
+    use crate::schema::auth::AuthTokens;
+    use httpmock::Method::GET;
+    use httpmock::MockServer;
+    use pretty_assertions::assert_eq;
+    use reqwest::header::HeaderValue;
+    use serde_json::json;
+
+    struct SignRequest;
+
+    #[async_trait]
+    impl Middleware for SignRequest {
+        async fn handle(
+            &self,
+            mut req: Request,
+            ctx: &RequestContext,
+            next: Next<'_>,
+        ) -> Result<Response> {
+            let signature = format!("{} {}", ctx.method, ctx.path);
+            req.headers_mut().insert(
+                "X-Signature",
+                HeaderValue::from_str(&signature).expect("valid header"),
+            );
+            next.run(req, ctx).await
+        }
+    }
+
+    struct Record(Arc<Mutex<Vec<String>>>, &'static str);
+
+    #[async_trait]
+    impl Middleware for Record {
+        async fn handle(
+            &self,
+            req: Request,
+            ctx: &RequestContext,
+            next: Next<'_>,
+        ) -> Result<Response> {
+            self.0.lock().unwrap().push(format!("{} before", self.1));
+            let res = next.run(req, ctx).await;
+            self.0.lock().unwrap().push(format!("{} after", self.1));
+            res
+        }
+    }
+
+    struct Offline;
+
+    #[async_trait]
+    impl Middleware for Offline {
+        async fn handle(
+            &self,
+            _req: Request,
+            _ctx: &RequestContext,
+            _next: Next<'_>,
+        ) -> Result<Response> {
+            let res = http::Response::builder()
+                .status(200)
+                .header("Content-Type", "application/json")
+                .body(
+                    json!({
+                        "result": "ok",
+                        "isAuthenticated": true,
+                        "roles": [],
+                        "permissions": [],
+                    })
+                    .to_string(),
+                )
+                .expect("valid response");
+            Ok(res.into())
+        }
+    }
+
+    fn tokens() -> Option<AuthTokens> {
+        Some(AuthTokens {
+            session: "sessiontoken".to_string(),
+            refresh: "refreshtoken".to_string(),
+        })
+    }
+
+    #[tokio::test]
+    async fn modify_request() -> anyhow::Result<()> {
+        let server = MockServer::start_async().await;
+
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/auth/check")
+                    .header("X-Signature", "GET /auth/check");
+                then.status(200)
+                    .header("Content-Type", "application/json")
+                    .json_body(json!({
+                        "result": "ok",
+                        "isAuthenticated": true,
+                        "roles": [],
+                        "permissions": [],
+                    }));
+            })
+            .await;
+
+        let mut client = Client::builder()
+            .base_url(&server.base_url())
+            .middleware(SignRequest)
+            .build()?;
+        client.set_tokens(tokens());
+
+        CheckToken.send(&client).await?;
+
+        mock.assert_async().await;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn middleware_order() -> anyhow::Result<()> {
+        let log = Arc::new(Mutex::new(Vec::new()));
+
+        let mut client = Client::builder()
+            .middleware(Record(log.clone(), "first"))
+            .middleware(Record(log.clone(), "second"))
+            .middleware(Offline)
+            .build()?;
+        client.set_tokens(tokens());
+
+        let res = CheckToken.send(&client).await?;
+
+        assert!(res.is_authenticated);
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![
+                "first before",
+                "second before",
+                "second after",
+                "first after"
+            ]
+        );
+
+        Ok(())
+    }
+}
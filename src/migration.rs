@@ -0,0 +1,508 @@
+//! Bulk migration of legacy ids.
+//!
+//! A [`LegacyMapper`] maps any number of ids of the old website to their new uuids, by sending
+//! [`LegacyMapping`] requests in chunks and at a limited rate. The results are kept in a
+//! [`MappingStore`], so an id is only ever asked once. With a [`FileMappingStore`], an interrupted
+//! migration picks up where it stopped.
+//!
+//! ```rust,no_run
+//! use mangadex::migration::{FileMappingStore, LegacyMapper};
+//! use mangadex::schema::legacy::MappingType;
+//! use mangadex::Client;
+//!
+//! # async fn run(client: &Client, bookmarks: Vec<u32>) -> mangadex::Result<()> {
+//! let mapper = LegacyMapper::new(FileMappingStore::open("legacy-ids.jsonl")?);
+//! let report = mapper
+//!     .map(client, bookmarks.into_iter().map(|id| (MappingType::Manga, id)))
+//!     .await?;
+//!
+//! for (r#type, id) in &report.unmapped {
+//!     eprintln!("{} {} has no new id", r#type, id);
+//! }
+//! for (r#type, id) in &report.failed {
+//!     eprintln!("{} {} could not be looked up, it will be retried next time", r#type, id);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::api::legacy::LegacyMapping;
+use crate::policy::RateLimiter;
+use crate::schema::legacy::{MappingIdResponse, MappingType};
+use crate::{Client, RateLimit, Result};
+
+/// The default number of ids per request. The api limits the request body to 10KB.
+pub const DEFAULT_CHUNK_SIZE: usize = 500;
+
+/// A storage backend for a [`LegacyMapper`].
+pub trait MappingStore: Send + Sync + 'static {
+    /// Get the mapping of an id: `Some(None)` if the id is known to have no new id, `None` if it
+    /// was never looked up.
+    fn get(&self, r#type: &MappingType, legacy_id: u32) -> Option<Option<Uuid>>;
+
+    /// Record the mapping of an id, `None` if it has no new id.
+    fn put(&self, r#type: &MappingType, legacy_id: u32, new_id: Option<Uuid>) -> Result<()>;
+}
+
+/// An in-memory store.
+#[derive(Debug, Default)]
+pub struct MemoryMappingStore {
+    entries: Mutex<HashMap<(MappingType, u32), Option<Uuid>>>,
+}
+
+impl MemoryMappingStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of ids in the store.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Whether the store is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<(MappingType, u32), Option<Uuid>>> {
+        self.entries.lock().expect("mapping store lock poisoned")
+    }
+}
+
+impl MappingStore for MemoryMappingStore {
+    fn get(&self, r#type: &MappingType, legacy_id: u32) -> Option<Option<Uuid>> {
+        self.lock().get(&(r#type.clone(), legacy_id)).copied()
+    }
+
+    fn put(&self, r#type: &MappingType, legacy_id: u32, new_id: Option<Uuid>) -> Result<()> {
+        self.lock().insert((r#type.clone(), legacy_id), new_id);
+        Ok(())
+    }
+}
+
+/// A store keeping the mappings in a file, one json object per line.
+///
+/// The file is read when the store is opened, and every new mapping is appended to it right away.
+#[derive(Debug)]
+pub struct FileMappingStore {
+    memory: MemoryMappingStore,
+    file: Mutex<File>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FileEntry {
+    r#type: MappingType,
+    legacy_id: u32,
+    new_id: Option<Uuid>,
+}
+
+impl FileMappingStore {
+    /// Open the store at the given path, creating the file if needed.
+    ///
+    /// Lines that can't be parsed (e.g. the last line of a file cut short by a crash) are skipped.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let memory = MemoryMappingStore::new();
+        if path.exists() {
+            for line in BufReader::new(File::open(path)?).lines() {
+                if let Ok(entry) = serde_json::from_str::<FileEntry>(&line?) {
+                    memory.put(&entry.r#type, entry.legacy_id, entry.new_id)?;
+                }
+            }
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            memory,
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Number of ids in the store.
+    pub fn len(&self) -> usize {
+        self.memory.len()
+    }
+
+    /// Whether the store is empty.
+    pub fn is_empty(&self) -> bool {
+        self.memory.is_empty()
+    }
+}
+
+impl MappingStore for FileMappingStore {
+    fn get(&self, r#type: &MappingType, legacy_id: u32) -> Option<Option<Uuid>> {
+        self.memory.get(r#type, legacy_id)
+    }
+
+    fn put(&self, r#type: &MappingType, legacy_id: u32, new_id: Option<Uuid>) -> Result<()> {
+        let entry = FileEntry {
+            r#type: r#type.clone(),
+            legacy_id,
+            new_id,
+        };
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        self.file
+            .lock()
+            .expect("mapping store lock poisoned")
+            .write_all(line.as_bytes())?;
+
+        self.memory.put(r#type, legacy_id, new_id)
+    }
+}
+
+/// The result of [`LegacyMapper::map()`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MappingReport {
+    /// The new id of every mapped id.
+    pub mapped: BTreeMap<(MappingType, u32), Uuid>,
+    /// The ids without a new id.
+    pub unmapped: Vec<(MappingType, u32)>,
+    /// The ids whose lookup failed. They are not stored, so the next run asks for them again.
+    pub failed: Vec<(MappingType, u32)>,
+    /// Number of requests sent to the api.
+    pub requests: usize,
+}
+
+impl MappingReport {
+    /// Get the new id of a legacy id.
+    pub fn get(&self, r#type: &MappingType, legacy_id: u32) -> Option<Uuid> {
+        self.mapped.get(&(r#type.clone(), legacy_id)).copied()
+    }
+}
+
+/// Maps legacy ids in bulk. See the [module documentation](self).
+///
+/// By default, requests hold up to [`DEFAULT_CHUNK_SIZE`] ids and are sent at the rate of
+/// [`RateLimit::default()`], on top of any rate limit of the client.
+#[derive(Clone)]
+pub struct LegacyMapper {
+    store: Arc<dyn MappingStore>,
+    chunk_size: usize,
+    limiter: Arc<RateLimiter>,
+}
+
+impl LegacyMapper {
+    /// Create a mapper using the given store.
+    pub fn new(store: impl MappingStore) -> Self {
+        Self {
+            store: Arc::new(store),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            limiter: Arc::new(RateLimiter::new(RateLimit::default())),
+        }
+    }
+
+    /// Set the maximum number of ids per request.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Set the rate at which requests are sent.
+    pub fn rate_limit(mut self, limit: RateLimit) -> Self {
+        self.limiter = Arc::new(RateLimiter::new(limit));
+        self
+    }
+
+    /// Get the store backing the mapper.
+    pub fn store(&self) -> &dyn MappingStore {
+        &*self.store
+    }
+
+    /// Map legacy ids of any type to their new ids.
+    ///
+    /// Ids already in the store are not sent again. If a request fails, or the store can't be
+    /// written, the error is returned, and the mappings received until then are kept in the
+    /// store.
+    pub async fn map(
+        &self,
+        client: &Client,
+        ids: impl IntoIterator<Item = (MappingType, u32)>,
+    ) -> Result<MappingReport> {
+        let (mut report, requests) = self.plan(ids);
+        for request in requests {
+            let delay = self.limiter.reserve();
+            if !delay.is_zero() {
+                tokio::time::sleep(delay).await;
+            }
+            let responses = request.send(client).await?;
+            self.record(&mut report, &request, responses)?;
+        }
+        Ok(report)
+    }
+
+    /// Map legacy ids of any type to their new ids.
+    ///
+    /// See [`map()`](Self::map()).
+    #[cfg(feature = "blocking")]
+    pub fn map_blocking(
+        &self,
+        client: &crate::blocking::Client,
+        ids: impl IntoIterator<Item = (MappingType, u32)>,
+    ) -> Result<MappingReport> {
+        let (mut report, requests) = self.plan(ids);
+        for request in requests {
+            let delay = self.limiter.reserve();
+            if !delay.is_zero() {
+                std::thread::sleep(delay);
+            }
+            let responses = request.send_blocking(client)?;
+            self.record(&mut report, &request, responses)?;
+        }
+        Ok(report)
+    }
+
+    /// Fill a report with the stored mappings, and build the requests for the other ids.
+    fn plan(
+        &self,
+        ids: impl IntoIterator<Item = (MappingType, u32)>,
+    ) -> (MappingReport, Vec<LegacyMapping>) {
+        let mut report = MappingReport::default();
+        let mut missing: BTreeMap<MappingType, BTreeSet<u32>> = BTreeMap::new();
+
+        for (r#type, id) in ids {
+            match self.store.get(&r#type, id) {
+                Some(Some(new_id)) => {
+                    report.mapped.insert((r#type, id), new_id);
+                }
+                Some(None) => report.unmapped.push((r#type, id)),
+                None => {
+                    missing.entry(r#type).or_default().insert(id);
+                }
+            }
+        }
+
+        let requests = missing
+            .into_iter()
+            .flat_map(|(r#type, ids)| {
+                let ids: Vec<u32> = ids.into_iter().collect();
+                ids.chunks(self.chunk_size)
+                    .map(|chunk| LegacyMapping {
+                        r#type: r#type.clone(),
+                        ids: chunk.to_vec(),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        report.unmapped.sort();
+        report.unmapped.dedup();
+        (report, requests)
+    }
+
+    /// Store the response to a request, and add it to the report.
+    fn record(
+        &self,
+        report: &mut MappingReport,
+        request: &LegacyMapping,
+        responses: Vec<MappingIdResponse>,
+    ) -> Result<()> {
+        report.requests += 1;
+
+        let mut errors = false;
+        let mut mapped = HashMap::new();
+        for res in responses {
+            match res {
+                Ok(res) if res.data.attributes.r#type == request.r#type => {
+                    let mapping = res.data.attributes;
+                    mapped.insert(mapping.legacy_id, mapping.new_id);
+                }
+                Ok(_) => {}
+                Err(_) => errors = true,
+            }
+        }
+
+        for &id in &request.ids {
+            let key = (request.r#type.clone(), id);
+            match mapped.get(&id) {
+                Some(&new_id) => {
+                    self.store.put(&request.r#type, id, Some(new_id))?;
+                    report.mapped.insert(key, new_id);
+                }
+                // Failed entries don't say which id they are about, so a missing id only means
+                // that it has no new id if the whole response succeeded.
+                None if errors => report.failed.push(key),
+                None => {
+                    self.store.put(&request.r#type, id, None)?;
+                    report.unmapped.push(key);
+                }
+            }
+        }
+        report.unmapped.sort();
+        report.failed.sort();
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for LegacyMapper {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LegacyMapper")
+            .field("chunk_size", &self.chunk_size)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use httpmock::{Method::POST, MockServer};
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    const FIRST: &str = "c0ee660b-f9f2-45c3-8068-5123ff53f84a";
+    const THIRD: &str = "32d76d19-8a05-4db0-9fc2-e0b0648fe9d0";
+
+    fn mapping(legacy_id: u32, new_id: &str) -> serde_json::Value {
+        json!({
+            "result": "ok",
+            "data": {
+                "id": "24b6d026-a7cb-498e-8717-26b2831cf318",
+                "type": "mapping_id",
+                "attributes": { "type": "manga", "legacyId": legacy_id, "newId": new_id }
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn map_in_chunks_once() -> anyhow::Result<()> {
+        let server = MockServer::start_async().await;
+        let first = server
+            .mock_async(|when, then| {
+                when.method(POST)
+                    .path("/legacy/mapping")
+                    .json_body(json!({ "type": "manga", "ids": [1, 2] }));
+                then.header("Content-Type", "application/json")
+                    .json_body(json!([mapping(1, FIRST)]));
+            })
+            .await;
+        let second = server
+            .mock_async(|when, then| {
+                when.method(POST)
+                    .path("/legacy/mapping")
+                    .json_body(json!({ "type": "manga", "ids": [3] }));
+                then.header("Content-Type", "application/json")
+                    .json_body(json!([mapping(3, THIRD)]));
+            })
+            .await;
+
+        let client = Client::new(&server.base_url())?;
+        let mapper = LegacyMapper::new(MemoryMappingStore::new())
+            .chunk_size(2)
+            .rate_limit(RateLimit::per_second(100));
+        let ids = || {
+            vec![3, 1, 2, 1]
+                .into_iter()
+                .map(|id| (MappingType::Manga, id))
+        };
+
+        let report = mapper.map(&client, ids()).await?;
+        assert_eq!(report.requests, 2);
+        assert_eq!(report.get(&MappingType::Manga, 1), Some(FIRST.parse()?));
+        assert_eq!(report.get(&MappingType::Manga, 3), Some(THIRD.parse()?));
+        assert_eq!(report.unmapped, vec![(MappingType::Manga, 2)]);
+
+        // Everything, including the unmapped id, comes from the store the second time.
+        let again = mapper.map(&client, ids()).await?;
+        assert_eq!(again.requests, 0);
+        assert_eq!(again.mapped, report.mapped);
+        assert_eq!(again.unmapped, report.unmapped);
+        assert_eq!(first.hits_async().await, 1);
+        assert_eq!(second.hits_async().await, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn retry_failed_ids() -> anyhow::Result<()> {
+        let server = MockServer::start_async().await;
+        let partial = server
+            .mock_async(|when, then| {
+                when.method(POST)
+                    .path("/legacy/mapping")
+                    .json_body(json!({ "type": "manga", "ids": [1, 2] }));
+                then.header("Content-Type", "application/json")
+                    .json_body(json!([
+                        mapping(1, FIRST),
+                        {
+                            "result": "error",
+                            "errors": [{
+                                "id": "5e50fc7b-e185-45b1-a692-58e8091b22d2",
+                                "status": 500,
+                                "title": "Internal error",
+                                "detail": null
+                            }]
+                        }
+                    ]));
+            })
+            .await;
+        let retry = server
+            .mock_async(|when, then| {
+                when.method(POST)
+                    .path("/legacy/mapping")
+                    .json_body(json!({ "type": "manga", "ids": [2] }));
+                then.header("Content-Type", "application/json")
+                    .json_body(json!([]));
+            })
+            .await;
+
+        let client = Client::new(&server.base_url())?;
+        let store = Arc::new(MemoryMappingStore::new());
+        let mapper = LegacyMapper {
+            store: store.clone(),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            limiter: Arc::new(RateLimiter::new(RateLimit::per_second(100))),
+        };
+        let ids = || vec![(MappingType::Manga, 1), (MappingType::Manga, 2)];
+
+        let report = mapper.map(&client, ids()).await?;
+        assert_eq!(report.get(&MappingType::Manga, 1), Some(FIRST.parse()?));
+        assert_eq!(report.failed, vec![(MappingType::Manga, 2)]);
+        assert!(report.unmapped.is_empty());
+        assert_eq!(store.get(&MappingType::Manga, 2), None);
+
+        // The failed id is asked again, and is unmapped once the api answers without errors.
+        let again = mapper.map(&client, ids()).await?;
+        assert!(again.failed.is_empty());
+        assert_eq!(again.unmapped, vec![(MappingType::Manga, 2)]);
+        assert_eq!(store.get(&MappingType::Manga, 2), Some(None));
+        assert_eq!(partial.hits_async().await, 1);
+        assert_eq!(retry.hits_async().await, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn file_store_round_trip() -> anyhow::Result<()> {
+        let path =
+            std::env::temp_dir().join(format!("mangadex-mappings-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let store = FileMappingStore::open(&path)?;
+        store.put(&MappingType::Chapter, 42, Some(THIRD.parse()?))?;
+        store.put(&MappingType::Group, 7, None)?;
+        drop(store);
+
+        let store = FileMappingStore::open(&path)?;
+        assert_eq!(store.len(), 2);
+        assert_eq!(
+            store.get(&MappingType::Chapter, 42),
+            Some(Some(THIRD.parse()?))
+        );
+        assert_eq!(store.get(&MappingType::Group, 7), Some(None));
+        assert_eq!(store.get(&MappingType::Manga, 42), None);
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+}
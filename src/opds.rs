@@ -0,0 +1,941 @@
+//! An OPDS catalog of MangaDex, for reader apps
+//!
+//! [`OpdsServer`] serves an [OPDS 1.2](https://specs.opds.io/opds-1.2) catalog on top of a
+//! [`Client`]:
+//!
+//! * `/opds`: the root of the catalog,
+//! * `/opds/search?q=...`: a search, in the language of [`parse_query()`] (e.g.
+//!   `title:"solo leveling" status:ongoing`), described for apps by `/opds/search.xml`,
+//! * `/opds/latest`: the recently updated manga,
+//! * `/opds/followed` and `/opds/lists`: the followed manga and the custom lists of the logged user,
+//! * `/opds/list/{id}`: the manga of a custom list,
+//! * `/opds/manga/{id}`: the chapters of a manga,
+//! * `/opds/chapter/{id}/page/{n}`: the pages of a chapter (starting at 0), fetched from
+//!   MangaDex@Home.
+//!
+//! Chapters are read through the [OPDS Page Streaming Extension](https://vaemendis.net/opds-pse/),
+//! supported by most comic readers, so nothing is downloaded ahead. The content policy and the
+//! preferred languages of the client apply to the catalog.
+//!
+//! ```rust,no_run
+//! use mangadex::opds::OpdsServer;
+//! use mangadex::Client;
+//!
+//! # async fn run() -> mangadex::Result<()> {
+//! let mut client = Client::default();
+//! client.login("username", "password").await?;
+//!
+//! OpdsServer::new(client)
+//!     .title("MangaDex")
+//!     .serve(([0, 0, 0, 0], 8080).into())
+//!     .await
+//! # }
+//! ```
+//!
+//! This module is only available with the `opds` feature, which also builds the `mangadex-opds`
+//! binary.
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use hyper::header::{CONTENT_TYPE, LOCATION};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use tokio::sync::RwLock;
+use url::{form_urlencoded, Url};
+
+use crate::api::at_home::GetAtHomeServer;
+use crate::api::chapter::GetChapter;
+use crate::api::cover::ListCovers;
+use crate::api::feed::GetMangaFeed;
+use crate::api::list::{GetCustomList, GetLoggedUserCustomLists};
+use crate::api::manga::{GetManga, ListManga};
+use crate::api::user::ListFollowedManga;
+use crate::schema::chapter::{Chapter, PageQuality};
+use crate::schema::cover::{Cover, CoverSize};
+use crate::schema::feed::FeedOrder;
+use crate::schema::manga::{ContentRating, Manga, MangaList, MangaOrder};
+use crate::schema::{
+    ApiData, ChapterId, CoverId, ListId, MangaId, OrderType, PaginationQuery, ResourceType,
+};
+use crate::search::parse_query;
+use crate::syndication::{atom_date, Escape, TitleTemplate};
+use crate::{Client, Result};
+
+const NAVIGATION: &str = "application/atom+xml;profile=opds-catalog;kind=navigation";
+const ACQUISITION: &str = "application/atom+xml;profile=opds-catalog;kind=acquisition";
+const OPENSEARCH: &str = "application/opensearchdescription+xml";
+const PSE_STREAM: &str = "http://vaemendis.net/opds-pse/stream";
+
+/// Session tokens expire after 15 minutes.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// MangaDex@Home urls expire after 15 minutes.
+const AT_HOME_LIFETIME: Duration = Duration::from_secs(14 * 60);
+
+/// An OPDS catalog of MangaDex, see the [module docs][self].
+#[derive(Debug)]
+pub struct OpdsServer {
+    client: Arc<RwLock<Client>>,
+    title: String,
+    page_size: i32,
+    quality: PageQuality,
+    template: TitleTemplate,
+    /// The page urls of the chapters being read, by time of the MangaDex@Home request.
+    pages: Mutex<HashMap<ChapterId, (Instant, Vec<Url>)>>,
+}
+
+impl OpdsServer {
+    /// Create a catalog using the given client, logged in to list the followed manga and custom
+    /// lists.
+    pub fn new(client: Client) -> Self {
+        Self {
+            client: Arc::new(RwLock::new(client)),
+            title: "MangaDex".to_string(),
+            page_size: 20,
+            quality: PageQuality::Data,
+            template: TitleTemplate::default(),
+            pages: Mutex::default(),
+        }
+    }
+
+    /// Set the title of the catalog.
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Set the number of entries per page, at most 100.
+    pub fn page_size(mut self, page_size: i32) -> Self {
+        self.page_size = page_size.clamp(1, 100);
+        self
+    }
+
+    /// Serve the compressed pages instead of the original ones.
+    pub fn data_saver(mut self, enable: bool) -> Self {
+        self.quality = if enable {
+            PageQuality::DataSaver
+        } else {
+            PageQuality::Data
+        };
+        self
+    }
+
+    /// Set the format of the chapter titles.
+    pub fn template(mut self, template: TitleTemplate) -> Self {
+        self.template = template;
+        self
+    }
+
+    /// Serve the catalog on the given address, until an error occurs.
+    ///
+    /// The session of a logged in client is refreshed in the background.
+    pub async fn serve(self, addr: SocketAddr) -> Result<()> {
+        let server = Arc::new(self);
+
+        if server.client.read().await.get_tokens().is_some() {
+            let client = server.client.clone();
+            tokio::spawn(async move {
+                loop {
+                    tokio::time::sleep(REFRESH_INTERVAL).await;
+                    // Requests needing a session fail with the error until the next refresh.
+                    let _ = client.write().await.refresh_tokens().await;
+                }
+            });
+        }
+
+        let make_service = make_service_fn(move |_| {
+            let server = server.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    let server = server.clone();
+                    async move { Ok::<_, Infallible>(server.handle(req).await) }
+                }))
+            }
+        });
+        hyper::Server::try_bind(&addr)
+            .map_err(std::io::Error::other)?
+            .serve(make_service)
+            .await
+            .map_err(std::io::Error::other)?;
+        Ok(())
+    }
+
+    /// Answer a request to the catalog.
+    pub async fn handle(&self, req: Request<Body>) -> Response<Body> {
+        let client = self.client.read().await.clone();
+        let path: Vec<&str> = req
+            .uri()
+            .path()
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect();
+        let query: HashMap<String, String> =
+            form_urlencoded::parse(req.uri().query().unwrap_or_default().as_bytes())
+                .into_owned()
+                .collect();
+        let page = query
+            .get("page")
+            .and_then(|page| page.parse().ok())
+            .filter(|&page| page > 0)
+            .unwrap_or(1);
+
+        if req.method() != Method::GET {
+            return text(StatusCode::METHOD_NOT_ALLOWED, "Only GET is supported");
+        }
+
+        let res = match path.as_slice() {
+            [] => Ok(redirect("/opds")),
+            ["opds"] => Ok(self.root(&client)),
+            ["opds", "search.xml"] => Ok(self.search_description()),
+            ["opds", "search"] => self.search(&client, query.get("q"), page).await,
+            ["opds", "latest"] => self.latest(&client, page).await,
+            ["opds", "followed"] => self.followed(&client, page).await,
+            ["opds", "lists"] => self.lists(&client, page).await,
+            ["opds", "list", id] => match id.parse() {
+                Ok(id) => self.list(&client, id, page).await,
+                Err(_) => Ok(not_found()),
+            },
+            ["opds", "manga", id] => match id.parse() {
+                Ok(id) => self.chapters(&client, id, page).await,
+                Err(_) => Ok(not_found()),
+            },
+            ["opds", "chapter", id, "page", number] => match (id.parse(), number.parse()) {
+                (Ok(id), Ok(number)) => self.page(&client, id, number).await,
+                _ => Ok(not_found()),
+            },
+            _ => Ok(not_found()),
+        };
+
+        res.unwrap_or_else(|err| text(StatusCode::BAD_GATEWAY, &err.to_string()))
+    }
+
+    fn root(&self, client: &Client) -> Response<Body> {
+        let mut feed = Feed::new("/opds", &self.title, NAVIGATION);
+        feed.navigation(
+            "/opds/latest",
+            "Latest updates",
+            "The recently updated manga",
+        );
+        if client.get_tokens().is_some() {
+            feed.navigation("/opds/followed", "Followed manga", "The manga you follow");
+            feed.navigation("/opds/lists", "Custom lists", "Your custom lists");
+        }
+        feed.finish(NAVIGATION)
+    }
+
+    fn search_description(&self) -> Response<Body> {
+        let mut xml = String::new();
+        xml.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+        xml.push_str("<OpenSearchDescription xmlns=\"http://a9.com/-/spec/opensearch/1.1/\">\n");
+        let _ = writeln!(xml, "  <ShortName>{}</ShortName>", Escape(&self.title));
+        let _ = writeln!(
+            xml,
+            "  <Description>Search {}</Description>",
+            Escape(&self.title)
+        );
+        let _ = writeln!(
+            xml,
+            "  <Url type=\"{}\" template=\"/opds/search?q={{searchTerms}}\"/>",
+            Escape(ACQUISITION)
+        );
+        xml.push_str("</OpenSearchDescription>\n");
+        document(OPENSEARCH, xml)
+    }
+
+    async fn search(
+        &self,
+        client: &Client,
+        query: Option<&String>,
+        page: i32,
+    ) -> Result<Response<Body>> {
+        let query = query.map(String::as_str).unwrap_or_default();
+        let catalog = client.tag_catalog().await?;
+        let mut search = match parse_query(query, &catalog) {
+            Ok(search) => search,
+            Err(err) => return Ok(text(StatusCode::BAD_REQUEST, &err.to_string())),
+        };
+
+        let path = format!(
+            "/opds/search?{}",
+            form_urlencoded::Serializer::new(String::new())
+                .append_pair("q", query)
+                .finish()
+        );
+        search.request.limit = Some(self.page_size);
+        search.request.offset = Some(self.offset(page));
+        let manga = search.send(client).await?;
+        self.manga_feed(client, &path, &format!("Search: {}", query), page, manga)
+            .await
+    }
+
+    async fn latest(&self, client: &Client, page: i32) -> Result<Response<Body>> {
+        let manga = ListManga {
+            limit: Some(self.page_size),
+            offset: Some(self.offset(page)),
+            order: vec![MangaOrder::LatestUploadedChapter(OrderType::Descending)],
+            ..ListManga::default()
+        }
+        .send(client)
+        .await?;
+        self.manga_feed(client, "/opds/latest", "Latest updates", page, manga)
+            .await
+    }
+
+    async fn followed(&self, client: &Client, page: i32) -> Result<Response<Body>> {
+        let manga = ListFollowedManga {
+            limit: Some(self.page_size),
+            offset: Some(self.offset(page)),
+        }
+        .send(client)
+        .await?;
+        self.manga_feed(client, "/opds/followed", "Followed manga", page, manga)
+            .await
+    }
+
+    async fn lists(&self, client: &Client, page: i32) -> Result<Response<Body>> {
+        let lists = GetLoggedUserCustomLists {
+            pagination: PaginationQuery::new(Some(self.page_size), Some(self.offset(page))),
+        }
+        .send(client)
+        .await?;
+
+        let mut feed = Feed::new("/opds/lists", "Custom lists", NAVIGATION);
+        feed.pagination("/opds/lists", page, self.page_size, lists.total, NAVIGATION);
+        for list in lists.results {
+            let list = list?.data;
+            feed.navigation(
+                &format!("/opds/list/{}", list.id),
+                &list.attributes.name,
+                &format!("By {}", list.attributes.owner.attributes.username),
+            );
+        }
+        Ok(feed.finish(NAVIGATION))
+    }
+
+    async fn list(&self, client: &Client, id: ListId, page: i32) -> Result<Response<Body>> {
+        let list = GetCustomList { id }.send(client).await?;
+        let ids: Vec<MangaId> = list
+            .relationships
+            .iter()
+            .filter(|relationship| relationship.r#type == ResourceType::Manga)
+            .map(|relationship| relationship.id.into())
+            .collect();
+
+        // The list only gives the manga ids, a page of them is fetched at once.
+        let offset = self.offset(page);
+        let page_ids: Vec<MangaId> = ids
+            .iter()
+            .copied()
+            .skip(offset as usize)
+            .take(self.page_size as usize)
+            .collect();
+        let mut manga = MangaList {
+            results: Vec::new(),
+            limit: self.page_size,
+            offset,
+            total: 0,
+        };
+        if !page_ids.is_empty() {
+            // The api leaves pornographic manga out unless asked for (the content policy of the
+            // client still applies), and doesn't keep the order of the ids.
+            manga.results = ListManga {
+                limit: Some(self.page_size),
+                manga_ids: page_ids.clone(),
+                content_rating: vec![
+                    ContentRating::Safe,
+                    ContentRating::Suggestive,
+                    ContentRating::Erotica,
+                    ContentRating::Pornographic,
+                ],
+                ..ListManga::default()
+            }
+            .send(client)
+            .await?
+            .results;
+            manga.results.sort_by_key(|result| match result {
+                Ok(manga) => page_ids.iter().position(|id| *id == manga.data.id),
+                Err(_) => None,
+            });
+        }
+        manga.total = ids.len() as i32;
+
+        let path = format!("/opds/list/{}", id);
+        self.manga_feed(client, &path, &list.data.attributes.name, page, manga)
+            .await
+    }
+
+    async fn manga_feed(
+        &self,
+        client: &Client,
+        path: &str,
+        title: &str,
+        page: i32,
+        manga: MangaList,
+    ) -> Result<Response<Body>> {
+        let total = manga.total;
+        let manga = manga.results.into_iter().collect::<Result<Vec<_>>>()?;
+        let covers = covers(client, &manga).await?;
+
+        let mut feed = Feed::new(path, title, NAVIGATION);
+        feed.pagination(path, page, self.page_size, total, NAVIGATION);
+        for manga in &manga {
+            feed.manga(client, &manga.data, covers.get(&manga.data.id));
+        }
+        Ok(feed.finish(NAVIGATION))
+    }
+
+    async fn chapters(&self, client: &Client, id: MangaId, page: i32) -> Result<Response<Body>> {
+        let manga = GetManga { id }.send(client).await?;
+        let thumbnail = covers(client, std::slice::from_ref(&manga))
+            .await?
+            .get(&id)
+            .map(|cover| cover.image_url(client.uploads_url(), id, CoverSize::Small));
+        let chapters = GetMangaFeed {
+            manga_id: id,
+            limit: Some(self.page_size),
+            offset: Some(self.offset(page)),
+            translated_language: client.preferred_languages().to_vec(),
+            created_at_since: None,
+            updated_at_since: None,
+            publish_at_since: None,
+            order: Some(FeedOrder::Chapter(OrderType::Descending)),
+        }
+        .send(client)
+        .await?;
+
+        let path = format!("/opds/manga/{}", id);
+        let title = manga.data.display_title(client.preferred_languages());
+        let mut feed = Feed::new(&path, title, ACQUISITION);
+        feed.pagination(&path, page, self.page_size, chapters.total, ACQUISITION);
+        for chapter in chapters.results {
+            let chapter = chapter?.data;
+            feed.chapter(&chapter, &self.template, thumbnail.as_ref());
+        }
+        Ok(feed.finish(ACQUISITION))
+    }
+
+    async fn page(&self, client: &Client, id: ChapterId, number: usize) -> Result<Response<Body>> {
+        let url = match self.page_urls(client, id).await?.get(number) {
+            Some(url) => url.clone(),
+            None => return Ok(not_found()),
+        };
+
+        let res = match client
+            .http_client()
+            .get(url)
+            .send()
+            .await
+            .and_then(|res| res.error_for_status())
+        {
+            Ok(res) => res,
+            Err(err) => {
+                // The node may be gone, the next request asks for another one.
+                self.lock_pages().remove(&id);
+                return Err(err.into());
+            }
+        };
+        let content_type = res
+            .headers()
+            .get(CONTENT_TYPE)
+            .cloned()
+            .unwrap_or_else(|| hyper::header::HeaderValue::from_static("image/jpeg"));
+        let image = res.bytes().await?;
+        Ok(Response::builder()
+            .header(CONTENT_TYPE, content_type)
+            .body(Body::from(image))
+            .expect("valid response"))
+    }
+
+    /// Get the page urls of a chapter, reused while the MangaDex@Home url is valid: readers ask
+    /// for the pages one by one.
+    async fn page_urls(&self, client: &Client, id: ChapterId) -> Result<Vec<Url>> {
+        if let Some((fetched, urls)) = self.lock_pages().get(&id) {
+            if fetched.elapsed() < AT_HOME_LIFETIME {
+                return Ok(urls.clone());
+            }
+        }
+
+        let fetched = Instant::now();
+        let chapter = GetChapter { chapter_id: id }.send(client).await?.data;
+        let at_home = GetAtHomeServer {
+            chapter_id: id,
+            force_port443: false,
+        }
+        .send(client)
+        .await?;
+        let urls = chapter.page_urls(&at_home, self.quality);
+
+        let mut pages = self.lock_pages();
+        pages.retain(|_, (fetched, _)| fetched.elapsed() < AT_HOME_LIFETIME);
+        pages.insert(id, (fetched, urls.clone()));
+        Ok(urls)
+    }
+
+    fn lock_pages(&self) -> std::sync::MutexGuard<'_, HashMap<ChapterId, (Instant, Vec<Url>)>> {
+        self.pages.lock().expect("page cache lock poisoned")
+    }
+
+    fn offset(&self, page: i32) -> i32 {
+        (page - 1).saturating_mul(self.page_size)
+    }
+}
+
+/// Get the covers of the given manga.
+async fn covers(client: &Client, manga: &[ApiData<Manga>]) -> Result<HashMap<MangaId, Cover>> {
+    let mut covers = HashMap::new();
+
+    // A manga has a cover per volume, the main one is in its relationships.
+    let main: Vec<CoverId> = manga
+        .iter()
+        .flat_map(|manga| &manga.relationships)
+        .filter(|relationship| relationship.r#type == ResourceType::CoverArt)
+        .map(|relationship| relationship.id.into())
+        .collect();
+    if main.is_empty() {
+        return Ok(covers);
+    }
+    let list = ListCovers {
+        limit: Some(main.len() as i32),
+        covers: main,
+        ..ListCovers::default()
+    }
+    .send(client)
+    .await?;
+
+    for cover in list.results {
+        let cover = cover?;
+        let manga = cover
+            .relationships
+            .iter()
+            .find(|relationship| relationship.r#type == ResourceType::Manga)
+            .map(|relationship| MangaId::from(relationship.id));
+        if let Some(manga) = manga {
+            covers.insert(manga, cover.data);
+        }
+    }
+    Ok(covers)
+}
+
+/// An OPDS feed being written.
+struct Feed {
+    xml: String,
+}
+
+impl Feed {
+    fn new(path: &str, title: &str, kind: &str) -> Self {
+        let mut xml = String::new();
+        xml.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+        xml.push_str(concat!(
+            "<feed xmlns=\"http://www.w3.org/2005/Atom\"",
+            " xmlns:opds=\"http://opds-spec.org/2010/catalog\"",
+            " xmlns:opensearch=\"http://a9.com/-/spec/opensearch/1.1/\"",
+            " xmlns:pse=\"http://vaemendis.net/opds-pse/ns\"",
+            " xmlns:dcterms=\"http://purl.org/dc/terms/\">\n",
+        ));
+        let _ = writeln!(xml, "  <id>urn:mangadex-opds:{}</id>", Escape(path));
+        let _ = writeln!(xml, "  <title>{}</title>", Escape(title));
+        let _ = writeln!(xml, "  <updated>{}</updated>", atom_date(Utc::now()));
+        xml.push_str("  <author><name>MangaDex</name></author>\n");
+
+        let mut feed = Self { xml };
+        feed.link("self", path, kind);
+        feed.link("start", "/opds", NAVIGATION);
+        feed.link("search", "/opds/search.xml", OPENSEARCH);
+        feed
+    }
+
+    fn link(&mut self, rel: &str, href: &str, kind: &str) {
+        let _ = writeln!(
+            self.xml,
+            "  <link rel=\"{}\" href=\"{}\" type=\"{}\"/>",
+            Escape(rel),
+            Escape(href),
+            Escape(kind)
+        );
+    }
+
+    fn pagination(&mut self, path: &str, page: i32, page_size: i32, total: i32, kind: &str) {
+        let page_link = |page: i64| {
+            let separator = if path.contains('?') { '&' } else { '?' };
+            format!("{}{}page={}", path, separator, page)
+        };
+        // In i64, as pages come from the url and can be up to i32::MAX.
+        let (page, page_size, total) = (i64::from(page), i64::from(page_size), i64::from(total));
+        let last = ((total + page_size - 1) / page_size).max(1);
+        if page > 1 {
+            self.link("first", &page_link(1), kind);
+            self.link("previous", &page_link(page - 1), kind);
+        }
+        if page < last {
+            self.link("next", &page_link(page + 1), kind);
+            self.link("last", &page_link(last), kind);
+        }
+        let _ = writeln!(
+            self.xml,
+            "  <opensearch:totalResults>{}</opensearch:totalResults>",
+            total
+        );
+        let _ = writeln!(
+            self.xml,
+            "  <opensearch:itemsPerPage>{}</opensearch:itemsPerPage>",
+            page_size
+        );
+        let _ = writeln!(
+            self.xml,
+            "  <opensearch:startIndex>{}</opensearch:startIndex>",
+            (page - 1) * page_size + 1
+        );
+    }
+
+    fn navigation(&mut self, href: &str, title: &str, content: &str) {
+        self.xml.push_str("  <entry>\n");
+        let _ = writeln!(self.xml, "    <id>urn:mangadex-opds:{}</id>", Escape(href));
+        let _ = writeln!(self.xml, "    <title>{}</title>", Escape(title));
+        let _ = writeln!(self.xml, "    <updated>{}</updated>", atom_date(Utc::now()));
+        let _ = writeln!(
+            self.xml,
+            "    <content type=\"text\">{}</content>",
+            Escape(content)
+        );
+        let _ = writeln!(
+            self.xml,
+            "    <link rel=\"subsection\" href=\"{}\" type=\"{}\"/>",
+            Escape(href),
+            Escape(NAVIGATION)
+        );
+        self.xml.push_str("  </entry>\n");
+    }
+
+    fn manga(&mut self, client: &Client, manga: &Manga, cover: Option<&Cover>) {
+        let languages = client.preferred_languages();
+        self.xml.push_str("  <entry>\n");
+        let _ = writeln!(self.xml, "    <id>urn:uuid:{}</id>", manga.id);
+        let _ = writeln!(
+            self.xml,
+            "    <title>{}</title>",
+            Escape(manga.display_title(languages))
+        );
+        let _ = writeln!(
+            self.xml,
+            "    <updated>{}</updated>",
+            atom_date(manga.attributes.updated_at)
+        );
+        if let Some(description) = manga.description(languages) {
+            let _ = writeln!(
+                self.xml,
+                "    <content type=\"text\">{}</content>",
+                Escape(description)
+            );
+        }
+        let _ = writeln!(
+            self.xml,
+            "    <link rel=\"subsection\" href=\"/opds/manga/{}\" type=\"{}\"/>",
+            manga.id,
+            Escape(ACQUISITION)
+        );
+        self.images(client, manga.id, cover);
+        self.web_link(manga.web_url());
+        self.xml.push_str("  </entry>\n");
+    }
+
+    fn chapter(&mut self, chapter: &Chapter, template: &TitleTemplate, thumbnail: Option<&Url>) {
+        let attributes = &chapter.attributes;
+        self.xml.push_str("  <entry>\n");
+        let _ = writeln!(self.xml, "    <id>urn:uuid:{}</id>", chapter.id);
+        let _ = writeln!(
+            self.xml,
+            "    <title>{}</title>",
+            Escape(&template.format(chapter, &[]))
+        );
+        let _ = writeln!(
+            self.xml,
+            "    <updated>{}</updated>",
+            atom_date(attributes.updated_at)
+        );
+        let _ = writeln!(
+            self.xml,
+            "    <published>{}</published>",
+            atom_date(attributes.publish_at)
+        );
+        let _ = writeln!(
+            self.xml,
+            "    <dcterms:language>{}</dcterms:language>",
+            Escape(attributes.translated_language.as_str())
+        );
+        if !attributes.data.is_empty() {
+            let _ = writeln!(
+                self.xml,
+                "    <link rel=\"{}\" href=\"/opds/chapter/{}/page/{{pageNumber}}\" type=\"image/jpeg\" pse:count=\"{}\"/>",
+                PSE_STREAM,
+                chapter.id,
+                attributes.data.len()
+            );
+        }
+        if let Some(thumbnail) = thumbnail {
+            let _ = writeln!(
+                self.xml,
+                "    <link rel=\"http://opds-spec.org/image/thumbnail\" href=\"{}\" type=\"image/jpeg\"/>",
+                Escape(thumbnail.as_str())
+            );
+        }
+        self.web_link(chapter.web_url());
+        self.xml.push_str("  </entry>\n");
+    }
+
+    fn images(&mut self, client: &Client, manga: MangaId, cover: Option<&Cover>) {
+        if let Some(cover) = cover {
+            let uploads = client.uploads_url();
+            for (rel, size) in &[
+                ("http://opds-spec.org/image", CoverSize::Medium),
+                ("http://opds-spec.org/image/thumbnail", CoverSize::Small),
+            ] {
+                let _ = writeln!(
+                    self.xml,
+                    "    <link rel=\"{}\" href=\"{}\" type=\"image/jpeg\"/>",
+                    rel,
+                    Escape(cover.image_url(uploads, manga, *size).as_str())
+                );
+            }
+        }
+    }
+
+    fn web_link(&mut self, url: Option<Url>) {
+        if let Some(url) = url {
+            let _ = writeln!(
+                self.xml,
+                "    <link rel=\"alternate\" href=\"{}\" type=\"text/html\"/>",
+                Escape(url.as_str())
+            );
+        }
+    }
+
+    fn finish(mut self, kind: &str) -> Response<Body> {
+        self.xml.push_str("</feed>\n");
+        document(kind, self.xml)
+    }
+}
+
+fn document(kind: &str, body: String) -> Response<Body> {
+    Response::builder()
+        .header(CONTENT_TYPE, kind)
+        .body(Body::from(body))
+        .expect("valid response")
+}
+
+fn text(status: StatusCode, message: &str) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "text/plain; charset=utf-8")
+        .body(Body::from(message.to_string()))
+        .expect("valid response")
+}
+
+fn redirect(location: &str) -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::FOUND)
+        .header(LOCATION, location)
+        .body(Body::empty())
+        .expect("valid response")
+}
+
+fn not_found() -> Response<Body> {
+    text(StatusCode::NOT_FOUND, "Not found")
+}
+
+#[cfg(all(test, feature = "fake-server"))]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::api::list::CreateCustomList;
+    use crate::fake::FakeServer;
+    use crate::schema::list::CustomListVisibility;
+
+    async fn get(server: &OpdsServer, path: &str) -> (StatusCode, String) {
+        let req = Request::get(path).body(Body::empty()).unwrap();
+        let res = server.handle(req).await;
+        let status = res.status();
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        (status, String::from_utf8_lossy(&body).into_owned())
+    }
+
+    #[tokio::test]
+    async fn browse_and_read() -> anyhow::Result<()> {
+        let fake = FakeServer::start().await?;
+        let manga = fake.add_manga(json!({
+            "title": { "en": "Test manga" },
+            "description": { "en": "A <test>" },
+        }));
+        fake.add_cover(manga, "cover.jpg", b"cover".to_vec());
+        let chapter = fake.add_chapter(
+            manga,
+            json!({ "title": "Start", "chapter": "1", "translatedLanguage": "en" }),
+        );
+        fake.add_page(chapter, "1.jpg", b"first".to_vec());
+        fake.add_page(chapter, "2.jpg", b"second".to_vec());
+        let opds = OpdsServer::new(fake.client()?);
+
+        let (status, root) = get(&opds, "/opds").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(root.contains("href=\"/opds/latest\""));
+        assert!(!root.contains("/opds/followed"));
+
+        let (_, search) = get(&opds, "/opds/search?q=title%3Atest").await;
+        assert!(search.contains("<title>Test manga</title>"));
+        assert!(search.contains("<content type=\"text\">A &lt;test&gt;</content>"));
+        assert!(search.contains(&format!("href=\"/opds/manga/{}\"", manga)));
+        assert!(search.contains(&format!(
+            "href=\"{}covers/{}/cover.jpg.256.jpg\"",
+            fake.base_url(),
+            manga
+        )));
+        assert!(search.contains("<opensearch:totalResults>1</opensearch:totalResults>"));
+
+        let (status, _) = get(&opds, "/opds/search?q=status%3Anope").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (_, chapters) = get(&opds, &format!("/opds/manga/{}", manga)).await;
+        assert!(chapters.contains("<title>Ch. 1 - Start</title>"));
+        assert!(chapters.contains(&format!(
+            "href=\"/opds/chapter/{}/page/{{pageNumber}}\" type=\"image/jpeg\" pse:count=\"2\"",
+            chapter
+        )));
+
+        let (status, page) = get(&opds, &format!("/opds/chapter/{}/page/1", chapter)).await;
+        assert_eq!((status, page.as_str()), (StatusCode::OK, "second"));
+        let (status, _) = get(&opds, &format!("/opds/chapter/{}/page/2", chapter)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // The page list is kept while the MangaDex@Home url is valid.
+        fake.add_page(chapter, "3.jpg", b"third".to_vec());
+        let (status, _) = get(&opds, &format!("/opds/chapter/{}/page/2", chapter)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        opds.lock_pages().clear();
+        let (status, page) = get(&opds, &format!("/opds/chapter/{}/page/2", chapter)).await;
+        assert_eq!((status, page.as_str()), (StatusCode::OK, "third"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn search_year_ranges() -> anyhow::Result<()> {
+        let fake = FakeServer::start().await?;
+        for (title, year) in &[
+            ("Old", 2015),
+            ("First", 2018),
+            ("Second", 2019),
+            ("Older", 2010),
+            ("Third", 2020),
+        ] {
+            fake.add_manga(json!({ "title": { "en": title }, "year": year }));
+        }
+        let opds = OpdsServer::new(fake.client()?).page_size(2);
+
+        // Pages are filled with matching manga, and only count them.
+        let (_, first) = get(&opds, "/opds/search?q=year%3A%3E%3D2018").await;
+        assert!(first.contains("<title>First</title>") && first.contains("<title>Second</title>"));
+        assert!(first.contains("<opensearch:totalResults>3</opensearch:totalResults>"));
+        assert!(first.contains("rel=\"next\""));
+
+        let (_, second) = get(&opds, "/opds/search?q=year%3A%3E%3D2018&page=2").await;
+        assert!(second.contains("<title>Third</title>"));
+        assert!(!second.contains("<title>Old"));
+        assert!(!second.contains("rel=\"next\""));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn list_order_and_ratings() -> anyhow::Result<()> {
+        let fake = FakeServer::start().await?;
+        fake.add_user("reader", "password");
+        let manga: Vec<MangaId> = [
+            ("First", "safe"),
+            ("Second", "pornographic"),
+            ("Third", "erotica"),
+        ]
+        .iter()
+        .map(|(title, rating)| {
+            fake.add_manga(json!({ "title": { "en": title }, "contentRating": rating }))
+        })
+        .collect();
+
+        let mut client = fake.client()?;
+        client.login("reader", "password").await?;
+        let list = CreateCustomList {
+            name: "Favorites".to_string(),
+            visibility: CustomListVisibility::Private,
+            manga: manga.iter().rev().copied().collect(),
+            version: 1,
+        }
+        .send(&client)
+        .await?
+        .data;
+        let opds = OpdsServer::new(client);
+
+        let (_, feed) = get(&opds, &format!("/opds/list/{}", list.id)).await;
+        let position = |title: &str| feed.find(&format!("<title>{}</title>", title));
+        let positions = [position("Third"), position("Second"), position("First")];
+        assert!(positions.iter().all(Option::is_some), "{}", feed);
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn followed_manga_and_lists() -> anyhow::Result<()> {
+        let fake = FakeServer::start().await?;
+        let user = fake.add_user("reader", "password");
+        let titles = ["First", "Second", "Third"];
+        let manga: Vec<MangaId> = titles
+            .iter()
+            .map(|title| fake.add_manga(json!({ "title": { "en": title } })))
+            .collect();
+        fake.follow(user, manga[0]);
+
+        let mut client = fake.client()?;
+        client.login("reader", "password").await?;
+        let list = CreateCustomList {
+            name: "Favorites".to_string(),
+            visibility: CustomListVisibility::Private,
+            manga: manga.clone(),
+            version: 1,
+        }
+        .send(&client)
+        .await?
+        .data;
+        let opds = OpdsServer::new(client).page_size(2);
+
+        let (_, root) = get(&opds, "/opds").await;
+        assert!(root.contains("href=\"/opds/followed\""));
+
+        let (_, followed) = get(&opds, "/opds/followed").await;
+        assert!(followed.contains("<title>First</title>"));
+        assert!(!followed.contains("<title>Second</title>"));
+
+        let (_, lists) = get(&opds, "/opds/lists").await;
+        assert!(lists.contains("<title>Favorites</title>"));
+        assert!(lists.contains(&format!("href=\"/opds/list/{}\"", list.id)));
+
+        let (_, first) = get(&opds, &format!("/opds/list/{}", list.id)).await;
+        assert!(first.contains("<title>First</title>") && first.contains("<title>Second</title>"));
+        assert!(first.contains(&format!(
+            "rel=\"next\" href=\"/opds/list/{}?page=2\"",
+            list.id
+        )));
+
+        let (_, second) = get(&opds, &format!("/opds/list/{}?page=2", list.id)).await;
+        assert!(second.contains("<title>Third</title>"));
+        assert!(!second.contains("rel=\"next\""));
+
+        // Pages past the last one are empty, the list is paginated without the api.
+        let (status, past) = get(&opds, &format!("/opds/list/{}?page=2147483647", list.id)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(past.contains("<opensearch:startIndex>4294967293</opensearch:startIndex>"));
+        assert!(past.contains("rel=\"previous\""));
+        Ok(())
+    }
+}
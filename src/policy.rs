@@ -0,0 +1,427 @@
+//! Rate limiting, retry and content policies used by the [`Client`][crate::Client].
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use reqwest::{header::HeaderMap, Method, StatusCode, Url};
+use serde_json::Value;
+
+use crate::schema::{manga::ContentRating, GroupId, LanguageCode, TagId};
+
+/// A client side rate limit.
+///
+/// At most `requests` requests are sent in any window of length `per`. Requests over the limit
+/// are delayed until a slot frees up, instead of failing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    /// Maximum number of requests per window.
+    pub requests: u32,
+    /// Length of the window.
+    pub per: Duration,
+}
+
+impl RateLimit {
+    /// Create a new rate limit of `requests` requests per `per`.
+    pub fn new(requests: u32, per: Duration) -> Self {
+        Self { requests, per }
+    }
+
+    /// Create a new rate limit of `requests` requests per second.
+    pub fn per_second(requests: u32) -> Self {
+        Self::new(requests, Duration::from_secs(1))
+    }
+}
+
+impl Default for RateLimit {
+    /// The global limit enforced by the api: 5 requests per second.
+    fn default() -> Self {
+        Self::per_second(5)
+    }
+}
+
+/// Shared state of a [`RateLimit`].
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    limit: RateLimit,
+    slots: Mutex<VecDeque<Instant>>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            slots: Mutex::new(VecDeque::with_capacity(limit.requests as usize)),
+        }
+    }
+
+    /// Reserve a slot for a request, returning how long the caller has to wait before sending it.
+    pub(crate) fn reserve(&self) -> Duration {
+        let now = Instant::now();
+        let mut slots = self.slots.lock().expect("rate limiter lock poisoned");
+
+        if self.limit.requests == 0 {
+            return Duration::ZERO;
+        }
+
+        if slots.len() < self.limit.requests as usize {
+            slots.push_back(now);
+            return Duration::ZERO;
+        }
+
+        // The queue is full, so the next slot opens one window after the oldest reservation.
+        let oldest = slots.pop_front().expect("queue is not empty");
+        let next = (oldest + self.limit.per).max(now);
+        slots.push_back(next);
+        next - now
+    }
+}
+
+/// Policy used to retry requests that failed with a transient error.
+///
+/// Connection errors, timeouts, `429 Too Many Requests` and `502`/`503`/`504` responses are
+/// retried with an exponential backoff. When the api specifies when to retry (via the
+/// `X-RateLimit-Retry-After` or `Retry-After` headers), that delay is used instead.
+///
+/// Only idempotent requests (`GET`, `HEAD`, `PUT` and `DELETE`) are retried: a `POST` that timed
+/// out may still have been applied by the api, and sending it again could e.g. create a
+/// duplicate chapter or list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Maximum number of retries after the first attempt.
+    pub max_retries: u32,
+    /// Delay before the first retry. It is doubled after every attempt.
+    pub initial_backoff: Duration,
+    /// Upper bound for the delay between two attempts.
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Never retry a request.
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Retry a request at most `max_retries` times, with the default backoff.
+    pub fn with_max_retries(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            ..Self::default()
+        }
+    }
+
+    /// The delay before the given retry attempt (starting at 0), ignoring server hints.
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt);
+        self.initial_backoff
+            .checked_mul(factor)
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff)
+    }
+
+    /// Whether requests with the given method may be sent again.
+    pub(crate) fn is_retryable_method(method: &Method) -> bool {
+        matches!(
+            *method,
+            Method::GET | Method::HEAD | Method::PUT | Method::DELETE
+        )
+    }
+
+    /// Whether a response with the given status should be retried.
+    pub(crate) fn is_retryable_status(status: StatusCode) -> bool {
+        matches!(
+            status,
+            StatusCode::TOO_MANY_REQUESTS
+                | StatusCode::BAD_GATEWAY
+                | StatusCode::SERVICE_UNAVAILABLE
+                | StatusCode::GATEWAY_TIMEOUT
+        )
+    }
+
+    /// Whether a failed request should be retried.
+    pub(crate) fn is_retryable_error(err: &reqwest::Error) -> bool {
+        err.is_connect() || err.is_timeout()
+    }
+
+    /// The delay before the given retry attempt, honouring the api retry headers if present.
+    pub(crate) fn delay(&self, attempt: u32, headers: Option<&HeaderMap>) -> Duration {
+        headers
+            .and_then(retry_after)
+            .map(|delay| delay.min(self.max_backoff))
+            .unwrap_or_else(|| self.backoff(attempt))
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Parse the retry delay from the response headers.
+///
+/// `X-RateLimit-Retry-After` is a UNIX timestamp, while `Retry-After` is a number of seconds.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok())
+    };
+
+    if let Some(timestamp) = header("x-ratelimit-retry-after") {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?;
+        return Some(Duration::from_secs(timestamp).saturating_sub(now));
+    }
+
+    header("retry-after").map(Duration::from_secs)
+}
+
+/// Content filters applied to every listing sent by a [`Client`][crate::Client].
+///
+/// The filters are merged into the query of the listings (and of `/manga/random`): the allowed
+/// ratings and languages restrict the ones asked by the request (or are used if it asks none),
+/// and the excluded tags and groups are added to the ones it excludes. As a fallback, listings
+/// are filtered once more when they are received, dropping the manga and chapters that still
+/// don't match. Dropped entries are still counted in the `total` of the listing.
+///
+/// Empty filters allow everything.
+///
+/// ```rust,no_run
+/// use mangadex::schema::manga::ContentRating;
+/// use mangadex::{Client, ContentPolicy};
+///
+/// # fn main() -> mangadex::Result<()> {
+/// let policy = ContentPolicy::new()
+///     .allow_ratings(vec![ContentRating::Safe, ContentRating::Suggestive])
+///     .exclude_tags(vec!["b29d6a3d-1569-4e7a-8caf-7557bc92cd5d".parse().unwrap()]);
+/// let client = Client::builder().content_policy(policy).build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContentPolicy {
+    /// Content ratings of the manga to keep.
+    pub allowed_ratings: Vec<ContentRating>,
+    /// Tags of the manga to drop.
+    pub excluded_tags: Vec<TagId>,
+    /// Translated languages of the chapters to keep, and of the manga to search.
+    pub allowed_languages: Vec<LanguageCode>,
+    /// Scanlation groups of the chapters to drop.
+    pub excluded_groups: Vec<GroupId>,
+}
+
+/// The listings a policy applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Listing {
+    Manga,
+    Chapters,
+}
+
+impl ContentPolicy {
+    /// Create a policy allowing everything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only keep manga with one of the given content ratings.
+    pub fn allow_ratings(mut self, ratings: impl IntoIterator<Item = ContentRating>) -> Self {
+        self.allowed_ratings.extend(ratings);
+        self
+    }
+
+    /// Drop manga with any of the given tags.
+    pub fn exclude_tags(mut self, tags: impl IntoIterator<Item = TagId>) -> Self {
+        self.excluded_tags.extend(tags);
+        self
+    }
+
+    /// Only keep chapters translated in one of the given languages.
+    pub fn allow_languages(mut self, languages: impl IntoIterator<Item = LanguageCode>) -> Self {
+        self.allowed_languages.extend(languages);
+        self
+    }
+
+    /// Drop chapters from any of the given scanlation groups.
+    pub fn exclude_groups(mut self, groups: impl IntoIterator<Item = GroupId>) -> Self {
+        self.excluded_groups.extend(groups);
+        self
+    }
+
+    fn listing(path: &str) -> Option<Listing> {
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        match segments.as_slice() {
+            ["manga"] | ["user", "follows", "manga"] => Some(Listing::Manga),
+            ["chapter"] | ["manga", _, "feed"] | ["list", _, "feed"] => Some(Listing::Chapters),
+            ["user", "follows", "manga", "feed"] => Some(Listing::Chapters),
+            _ => None,
+        }
+    }
+
+    /// Whether the responses of the `GET` endpoint at `path` must be filtered.
+    pub(crate) fn filters(&self, path: &str) -> bool {
+        Self::listing(path).is_some()
+    }
+
+    /// Merge the policy into the query of a `GET` request to the endpoint at `path`.
+    pub(crate) fn apply_query(&self, path: &str, url: &mut Url) {
+        let mut query: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+        let mut changed = false;
+        match Self::listing(path) {
+            Some(Listing::Manga) => {
+                changed |= restrict(&mut query, "contentRating", &strings(&self.allowed_ratings));
+                changed |= exclude(&mut query, "excludedTags", &strings(&self.excluded_tags));
+                changed |= restrict(
+                    &mut query,
+                    "availableTranslatedLanguage",
+                    &strings(&self.allowed_languages),
+                );
+            }
+            Some(Listing::Chapters) => {
+                changed |= restrict(
+                    &mut query,
+                    "translatedLanguage",
+                    &strings(&self.allowed_languages),
+                );
+                changed |= exclude(
+                    &mut query,
+                    "excludedGroups",
+                    &strings(&self.excluded_groups),
+                );
+            }
+            // Not a listing, but the manga is picked among the ones matching the filters.
+            None if path.trim_matches('/') == "manga/random" => {
+                changed |= restrict(&mut query, "contentRating", &strings(&self.allowed_ratings));
+                changed |= exclude(&mut query, "excludedTags", &strings(&self.excluded_tags));
+            }
+            None => {}
+        }
+
+        if changed {
+            url.query_pairs_mut().clear().extend_pairs(query);
+        }
+    }
+
+    /// Drop the entries of a listing that don't match the policy.
+    pub(crate) fn filter_body(&self, body: &mut Value) {
+        if let Some(Value::Array(results)) = body.get_mut("results") {
+            results.retain(|entry| self.allows(entry));
+        }
+    }
+
+    /// Whether an entry of a listing matches the policy.
+    fn allows(&self, entry: &Value) -> bool {
+        let data = &entry["data"];
+        let attributes = &data["attributes"];
+        match data["type"].as_str() {
+            Some("manga") => {
+                let rating = attributes["contentRating"].as_str().unwrap_or("none");
+                let tags = attributes["tags"].as_array().map(Vec::as_slice);
+                (self.allowed_ratings.is_empty()
+                    || self.allowed_ratings.iter().any(|r| r.as_str() == rating))
+                    && !tags.unwrap_or_default().iter().any(|tag| {
+                        let id = tag["id"].as_str().and_then(|id| id.parse().ok());
+                        id.is_some_and(|id| self.excluded_tags.contains(&id))
+                    })
+            }
+            Some("chapter") => {
+                let language = attributes["translatedLanguage"].as_str();
+                let relationships = entry["relationships"]
+                    .as_array()
+                    .into_iter()
+                    .chain(data["relationships"].as_array())
+                    .flatten();
+                (self.allowed_languages.is_empty()
+                    || language.is_some_and(|language| {
+                        self.allowed_languages
+                            .iter()
+                            .any(|l| l.as_str() == language)
+                    }))
+                    && !relationships
+                        .filter(|rel| rel["type"] == "scanlation_group")
+                        .filter_map(|rel| rel["id"].as_str()?.parse().ok())
+                        .any(|id| self.excluded_groups.contains(&id))
+            }
+            _ => true,
+        }
+    }
+}
+
+fn strings<T: ToString>(values: &[T]) -> Vec<String> {
+    values.iter().map(ToString::to_string).collect()
+}
+
+/// The values of a parameter of a query, with or without brackets (`key[]` or `key[0]`).
+fn values(query: &[(String, String)], key: &str) -> Vec<String> {
+    query
+        .iter()
+        .filter(|(k, _)| is_param(k, key))
+        .map(|(_, v)| v.clone())
+        .collect()
+}
+
+fn is_param(param: &str, key: &str) -> bool {
+    param
+        .strip_prefix(key)
+        .is_some_and(|rest| rest.is_empty() || (rest.starts_with('[') && rest.ends_with(']')))
+}
+
+/// Replace the values of a parameter of a query.
+fn replace(query: &mut Vec<(String, String)>, key: &str, values: Vec<String>) {
+    query.retain(|(k, _)| !is_param(k, key));
+    query.extend(
+        values
+            .into_iter()
+            .enumerate()
+            .map(|(i, v)| (format!("{}[{}]", key, i), v)),
+    );
+}
+
+/// Restrict a parameter to the allowed values, returning whether the query changed.
+///
+/// If none of the values asked by the query are allowed, they are kept, so that the request
+/// doesn't fall back to the allowed values; their results are dropped when received.
+fn restrict(query: &mut Vec<(String, String)>, key: &str, allowed: &[String]) -> bool {
+    if allowed.is_empty() {
+        return false;
+    }
+
+    let asked = values(query, key);
+    let kept: Vec<String> = if asked.is_empty() {
+        allowed.to_vec()
+    } else {
+        asked
+            .iter()
+            .filter(|v| allowed.contains(v))
+            .cloned()
+            .collect()
+    };
+    if kept.is_empty() || kept == asked {
+        return false;
+    }
+    replace(query, key, kept);
+    true
+}
+
+/// Add excluded values to a parameter, returning whether the query changed.
+fn exclude(query: &mut Vec<(String, String)>, key: &str, excluded: &[String]) -> bool {
+    let mut values = values(query, key);
+    let before = values.len();
+    for value in excluded {
+        if !values.contains(value) {
+            values.push(value.clone());
+        }
+    }
+    if values.len() == before {
+        return false;
+    }
+    replace(query, key, values);
+    true
+}
@@ -1,7 +1,7 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use super::{ApiData, ApiObject, OrderType, Results};
+use super::{ApiData, ApiObject, AuthorId, OrderType, ResourceType, Results};
 use crate::Result;
 
 #[derive(Debug, Serialize, Deserialize, Clone, Hash, PartialEq, Eq)]
@@ -21,6 +21,6 @@ pub struct AuthorAttributes {
     pub updated_at: DateTime<Utc>,
 }
 
-pub type Author = ApiObject<AuthorAttributes>;
+pub type Author = ApiObject<AuthorAttributes, ResourceType, AuthorId>;
 pub type AuthorResponse = Result<ApiData<Author>>;
 pub type AuthorList = Results<AuthorResponse>;
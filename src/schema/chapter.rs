@@ -1,8 +1,10 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use uuid::Uuid;
+use url::Url;
 
-use super::{ApiData, ApiObject, OrderType, Results};
+use super::{
+    ApiData, ApiObject, ChapterId, LanguageCode, OrderType, ResourceType, Results, UserId,
+};
 use crate::Result;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -11,11 +13,11 @@ pub struct ChapterAttributes {
     pub title: String,
     pub volume: Option<String>,
     pub chapter: Option<String>,
-    pub translated_language: String,
+    pub translated_language: LanguageCode,
     pub hash: String,
     pub data: Vec<String>,
     pub data_saver: Vec<String>,
-    pub uploader: Uuid,
+    pub uploader: UserId,
     pub version: i32,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -32,6 +34,37 @@ pub enum ChapterOrder {
     Chapter(OrderType),
 }
 
-pub type Chapter = ApiObject<ChapterAttributes>;
+pub type Chapter = ApiObject<ChapterAttributes, ResourceType, ChapterId>;
 pub type ChapterResponse = Result<ApiData<Chapter>>;
 pub type ChapterList = Results<ChapterResponse>;
+
+/// The quality of chapter pages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PageQuality {
+    /// The original images.
+    Data,
+    /// Compressed images.
+    DataSaver,
+}
+
+impl Chapter {
+    /// Get the urls of the pages, in order, on a MangaDex@Home server (given by
+    /// [`GetAtHomeServer`][crate::api::at_home::GetAtHomeServer]).
+    pub fn page_urls(&self, at_home: &Url, quality: PageQuality) -> Vec<Url> {
+        let (segment, files) = match quality {
+            PageQuality::Data => ("data", &self.attributes.data),
+            PageQuality::DataSaver => ("data-saver", &self.attributes.data_saver),
+        };
+        files
+            .iter()
+            .map(|file| {
+                let mut url = at_home.clone();
+                url.path_segments_mut()
+                    .expect("the MangaDex@Home url is a base")
+                    .pop_if_empty()
+                    .extend(&[segment, &self.attributes.hash, file]);
+                url
+            })
+            .collect()
+    }
+}
@@ -1,148 +1,315 @@
-use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::iter::FromIterator;
+use std::ops::{Deref, DerefMut};
+use std::str::FromStr;
+
+use serde::de::{
+    self, value::MapAccessDeserializer, DeserializeOwned, IgnoredAny, MapAccess, SeqAccess, Visitor,
+};
+use serde::{Deserialize, Deserializer, Serialize};
+use thiserror::Error;
 use uuid::Uuid;
 
 use super::errors::ApiErrors;
 use crate::{errors::Errors, FromResponse};
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, Hash, PartialEq, Eq)]
-pub enum LanguageCode {
-    #[serde(rename = "ar")]
-    Arabic,
-
-    #[serde(rename = "bn")]
-    Bengali,
-
-    #[serde(rename = "bg")]
-    Bulgarian,
-
-    #[serde(rename = "my")]
-    Burmese,
-
-    #[serde(rename = "ca")]
-    Catalan,
-
-    #[serde(rename = "zh")]
-    ChineseSimp,
-
-    #[serde(rename = "zh-hk")]
-    ChineseTrad,
-
-    #[serde(rename = "cs")]
-    Czech,
-
-    #[serde(rename = "da")]
-    Danish,
-
-    #[serde(rename = "nl")]
-    Dutch,
-
-    #[serde(rename = "en")]
-    English,
-
-    #[serde(rename = "tl")]
-    Filipino,
-
-    #[serde(rename = "fi")]
-    Finnish,
-
-    #[serde(rename = "fr")]
-    French,
-
-    #[serde(rename = "de")]
-    German,
-
-    #[serde(rename = "el")]
-    Greek,
+open_enum! {
+    /// A language, identified by its ISO 639-1 code, with a region or a `ro` (romanized) subtag
+    /// for some of them.
+    pub enum LanguageCode {
+        Arabic = "ar",
+        Azerbaijani = "az",
+        Belarusian = "be",
+        Bengali = "bn",
+        Bulgarian = "bg",
+        Burmese = "my",
+        Catalan = "ca",
+        ChineseSimp = "zh",
+        ChineseTrad = "zh-hk",
+        ChineseRomanized = "zh-ro",
+        Chuvash = "cv",
+        Croatian = "hr",
+        Czech = "cs",
+        Danish = "da",
+        Dutch = "nl",
+        English = "en",
+        Esperanto = "eo",
+        Estonian = "et",
+        Filipino = "tl",
+        Finnish = "fi",
+        French = "fr",
+        Georgian = "ka",
+        German = "de",
+        Greek = "el",
+        Hebrew = "he",
+        Hindi = "hi",
+        Hungarian = "hu",
+        Indonesian = "id",
+        Italian = "it",
+        Japanese = "ja",
+        JapaneseRomanized = "ja-ro",
+        Kazakh = "kk",
+        Korean = "ko",
+        KoreanRomanized = "ko-ro",
+        Latin = "la",
+        Lithuanian = "lt",
+        Malay = "ms",
+        Mongolian = "mn",
+        Nepali = "ne",
+        Norwegian = "no",
+        Persian = "fa",
+        Polish = "pl",
+        PortugueseBr = "pt-br",
+        PortuguesePt = "pt",
+        Romanian = "ro",
+        Russian = "ru",
+        SerboCroatian = "sr",
+        Slovak = "sk",
+        SpanishEs = "es",
+        SpanishLATAM = "es-la",
+        Swedish = "sv",
+        Tamil = "ta",
+        Thai = "th",
+        Turkish = "tr",
+        Ukrainian = "uk",
+        Vietnamese = "vi",
+        /// Sent by the api when the language isn't set.
+        Null = "NULL",
+    }
+}
 
-    #[serde(rename = "he")]
-    Hebrew,
+impl LanguageCode {
+    /// Get the English name of the language.
+    pub fn name(&self) -> Option<&'static str> {
+        self.info().map(|(name, _, _)| name)
+    }
 
-    #[serde(rename = "hi")]
-    Hindi,
+    /// Get the name of the language, in the language itself.
+    pub fn native_name(&self) -> Option<&'static str> {
+        self.info().map(|(_, native, _)| native)
+    }
 
-    #[serde(rename = "hu")]
-    Hungarian,
+    /// Get the ISO 3166-1 code of the country whose flag represents the language.
+    pub fn country(&self) -> Option<&'static str> {
+        self.info().and_then(|(_, _, country)| country)
+    }
 
-    #[serde(rename = "id")]
-    Indonesian,
+    /// Get the flag emoji representing the language, e.g. 🇯🇵 for Japanese.
+    pub fn flag(&self) -> Option<String> {
+        self.country().map(|country| {
+            country
+                .chars()
+                .filter_map(|c| char::from_u32(0x1F1E6 + (c as u32 - 'a' as u32)))
+                .collect()
+        })
+    }
 
-    #[serde(rename = "it")]
-    Italian,
+    /// Get whether this is the romanized variant of a language, e.g. `ja-ro`.
+    pub fn is_romanized(&self) -> bool {
+        self.as_str().ends_with("-ro")
+    }
 
-    #[serde(rename = "ja")]
-    Japanese,
+    /// Get the romanized variant of the language, if there is one, e.g. `ja-ro` for `ja`.
+    pub fn romanized(&self) -> Option<Self> {
+        match self {
+            Self::Japanese => Some(Self::JapaneseRomanized),
+            Self::Korean => Some(Self::KoreanRomanized),
+            Self::ChineseSimp | Self::ChineseTrad => Some(Self::ChineseRomanized),
+            _ => None,
+        }
+    }
 
-    #[serde(rename = "ko")]
-    Korean,
+    fn info(&self) -> Option<(&'static str, &'static str, Option<&'static str>)> {
+        let info = match self {
+            Self::Arabic => ("Arabic", "العربية", Some("sa")),
+            Self::Azerbaijani => ("Azerbaijani", "Azərbaycan dili", Some("az")),
+            Self::Belarusian => ("Belarusian", "Беларуская", Some("by")),
+            Self::Bengali => ("Bengali", "বাংলা", Some("bd")),
+            Self::Bulgarian => ("Bulgarian", "Български", Some("bg")),
+            Self::Burmese => ("Burmese", "မြန်မာဘာသာ", Some("mm")),
+            Self::Catalan => ("Catalan", "Català", Some("ad")),
+            Self::ChineseSimp => ("Chinese (Simplified)", "简体中文", Some("cn")),
+            Self::ChineseTrad => ("Chinese (Traditional)", "繁體中文", Some("hk")),
+            Self::ChineseRomanized => ("Chinese (Romanized)", "Zhōngwén", Some("cn")),
+            Self::Chuvash => ("Chuvash", "Чӑвашла", Some("ru")),
+            Self::Croatian => ("Croatian", "Hrvatski", Some("hr")),
+            Self::Czech => ("Czech", "Čeština", Some("cz")),
+            Self::Danish => ("Danish", "Dansk", Some("dk")),
+            Self::Dutch => ("Dutch", "Nederlands", Some("nl")),
+            Self::English => ("English", "English", Some("gb")),
+            Self::Esperanto => ("Esperanto", "Esperanto", None),
+            Self::Estonian => ("Estonian", "Eesti", Some("ee")),
+            Self::Filipino => ("Filipino", "Filipino", Some("ph")),
+            Self::Finnish => ("Finnish", "Suomi", Some("fi")),
+            Self::French => ("French", "Français", Some("fr")),
+            Self::Georgian => ("Georgian", "ქართული", Some("ge")),
+            Self::German => ("German", "Deutsch", Some("de")),
+            Self::Greek => ("Greek", "Ελληνικά", Some("gr")),
+            Self::Hebrew => ("Hebrew", "עברית", Some("il")),
+            Self::Hindi => ("Hindi", "हिन्दी", Some("in")),
+            Self::Hungarian => ("Hungarian", "Magyar", Some("hu")),
+            Self::Indonesian => ("Indonesian", "Bahasa Indonesia", Some("id")),
+            Self::Italian => ("Italian", "Italiano", Some("it")),
+            Self::Japanese => ("Japanese", "日本語", Some("jp")),
+            Self::JapaneseRomanized => ("Japanese (Romanized)", "Nihongo", Some("jp")),
+            Self::Kazakh => ("Kazakh", "Қазақ тілі", Some("kz")),
+            Self::Korean => ("Korean", "한국어", Some("kr")),
+            Self::KoreanRomanized => ("Korean (Romanized)", "Hangugeo", Some("kr")),
+            Self::Latin => ("Latin", "Latina", None),
+            Self::Lithuanian => ("Lithuanian", "Lietuvių", Some("lt")),
+            Self::Malay => ("Malay", "Bahasa Melayu", Some("my")),
+            Self::Mongolian => ("Mongolian", "Монгол", Some("mn")),
+            Self::Nepali => ("Nepali", "नेपाली", Some("np")),
+            Self::Norwegian => ("Norwegian", "Norsk", Some("no")),
+            Self::Persian => ("Persian", "فارسی", Some("ir")),
+            Self::Polish => ("Polish", "Polski", Some("pl")),
+            Self::PortugueseBr => ("Portuguese (Brazil)", "Português (Brasil)", Some("br")),
+            Self::PortuguesePt => ("Portuguese", "Português", Some("pt")),
+            Self::Romanian => ("Romanian", "Română", Some("ro")),
+            Self::Russian => ("Russian", "Русский", Some("ru")),
+            Self::SerboCroatian => ("Serbian", "Српски", Some("rs")),
+            Self::Slovak => ("Slovak", "Slovenčina", Some("sk")),
+            Self::SpanishEs => ("Spanish", "Español", Some("es")),
+            Self::SpanishLATAM => (
+                "Spanish (Latin America)",
+                "Español (Latinoamérica)",
+                Some("mx"),
+            ),
+            Self::Swedish => ("Swedish", "Svenska", Some("se")),
+            Self::Tamil => ("Tamil", "தமிழ்", Some("in")),
+            Self::Thai => ("Thai", "ไทย", Some("th")),
+            Self::Turkish => ("Turkish", "Türkçe", Some("tr")),
+            Self::Ukrainian => ("Ukrainian", "Українська", Some("ua")),
+            Self::Vietnamese => ("Vietnamese", "Tiếng Việt", Some("vn")),
+            Self::Null | Self::Unknown(_) => return None,
+        };
+        Some(info)
+    }
+}
 
-    #[serde(rename = "lt")]
-    Lithuanian,
+impl FromStr for LanguageCode {
+    type Err = ParseLanguageCodeError;
 
-    #[serde(rename = "ms")]
-    Malay,
+    /// Parse a language code, ignoring case and accepting `_` as a separator (e.g. `pt_BR`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let code = s.to_ascii_lowercase().replace('_', "-");
+        match Self::from(code.as_str()) {
+            Self::Unknown(_) | Self::Null => Err(ParseLanguageCodeError(s.to_string())),
+            language => Ok(language),
+        }
+    }
+}
 
-    #[serde(rename = "mn")]
-    Mongolian,
+/// The error returned when parsing an unknown [`LanguageCode`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("unknown language code: {0:?}")]
+pub struct ParseLanguageCodeError(pub String);
 
-    #[serde(rename = "no")]
-    Norwegian,
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum OrderType {
+    #[serde(rename = "asc")]
+    Ascending,
+    #[serde(rename = "desc")]
+    Descending,
+}
 
-    #[serde(rename = "fa")]
-    Persian,
+/// A string in several languages, such as a title or a description.
+///
+/// It dereferences to the underlying map, use [`best()`][Self::best()] to pick a value following a
+/// list of preferred languages. The api sends empty ones as `[]` instead of `{}`, and sometimes as
+/// `null`, both are decoded as an empty map.
+#[derive(Debug, Default, Serialize, Clone, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct LocalizedString(pub HashMap<LanguageCode, String>);
+
+impl LocalizedString {
+    /// Create an empty localized string.
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-    #[serde(rename = "pl")]
-    Polish,
+    /// Get the value in the first of `languages` it is available in.
+    pub fn best(&self, languages: &[LanguageCode]) -> Option<&str> {
+        languages
+            .iter()
+            .find_map(|language| self.0.get(language))
+            .map(String::as_str)
+    }
 
-    #[serde(rename = "pt-br")]
-    PortugueseBr,
+    /// Get any value, preferring English, then the first language by code, so that the choice
+    /// doesn't change between calls.
+    pub fn any(&self) -> Option<&str> {
+        self.best(&[LanguageCode::English]).or_else(|| {
+            self.0
+                .iter()
+                .min_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()))
+                .map(|(_, value)| value.as_str())
+        })
+    }
+}
 
-    #[serde(rename = "pt")]
-    PortuguesePt,
+impl Deref for LocalizedString {
+    type Target = HashMap<LanguageCode, String>;
 
-    #[serde(rename = "ro")]
-    Romanian,
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
 
-    #[serde(rename = "ru")]
-    Russian,
+impl DerefMut for LocalizedString {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
 
-    #[serde(rename = "sr")]
-    SerboCroatian,
+impl From<HashMap<LanguageCode, String>> for LocalizedString {
+    fn from(map: HashMap<LanguageCode, String>) -> Self {
+        Self(map)
+    }
+}
 
-    #[serde(rename = "es")]
-    SpanishEs,
+impl FromIterator<(LanguageCode, String)> for LocalizedString {
+    fn from_iter<I: IntoIterator<Item = (LanguageCode, String)>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
 
-    #[serde(rename = "es-la")]
-    SpanishLATAM,
+impl<'de> Deserialize<'de> for LocalizedString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct LocalizedVisitor;
 
-    #[serde(rename = "sv")]
-    Swedish,
+        impl<'de> Visitor<'de> for LocalizedVisitor {
+            type Value = LocalizedString;
 
-    #[serde(rename = "th")]
-    Thai,
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a map of language codes to strings, an empty array or null")
+            }
 
-    #[serde(rename = "tr")]
-    Turkish,
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                HashMap::deserialize(MapAccessDeserializer::new(map)).map(LocalizedString)
+            }
 
-    #[serde(rename = "uk")]
-    Ukrainian,
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                match seq.next_element::<IgnoredAny>()? {
+                    None => Ok(LocalizedString::default()),
+                    Some(_) => Err(de::Error::invalid_length(1, &"an empty array")),
+                }
+            }
 
-    #[serde(rename = "vi")]
-    Vietnamese,
+            fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+                Ok(LocalizedString::default())
+            }
 
-    #[serde(rename = "NULL")]
-    Null,
-}
+            fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+                Ok(LocalizedString::default())
+            }
+        }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, Hash, PartialEq, Eq)]
-pub enum OrderType {
-    #[serde(rename = "asc")]
-    Ascending,
-    #[serde(rename = "desc")]
-    Descending,
+        deserializer.deserialize_any(LocalizedVisitor)
+    }
 }
 
-pub type LocalizedString = std::collections::HashMap<LanguageCode, String>;
-
 #[derive(Debug, Serialize, Deserialize, Clone, Hash, PartialEq, Eq)]
 pub struct Relationship {
     pub id: Uuid,
@@ -159,12 +326,24 @@ pub struct ApiData<T> {
 
 #[derive(Debug, Default, Serialize, Deserialize, Clone, Hash, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
-pub struct ApiObject<A, T = ResourceType> {
-    pub id: Uuid,
+pub struct ApiObject<A, T = ResourceType, I = Uuid> {
+    pub id: I,
     pub r#type: T,
     pub attributes: A,
 }
 
+/// The `type` of an [`ApiObject`], which tells what kind of resource it is.
+pub trait ObjectType {
+    /// Get the kind of resource.
+    fn resource_type(&self) -> ResourceType;
+}
+
+impl ObjectType for ResourceType {
+    fn resource_type(&self) -> ResourceType {
+        self.clone()
+    }
+}
+
 #[derive(Debug, Default, Serialize, Deserialize, Clone, Hash, PartialEq, Eq)]
 pub struct NoData;
 
@@ -188,18 +367,21 @@ impl PaginationQuery {
     }
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, Hash, PartialEq, Eq)]
-#[serde(rename_all = "snake_case")]
-pub enum ResourceType {
-    Manga,
-    Chapter,
-    CoverArt,
-    Author,
-    Artist,
-    ScanlationGroup,
-    Tag,
-    User,
-    CustomList,
+open_enum! {
+    pub enum ResourceType {
+        Manga = "manga",
+        Chapter = "chapter",
+        CoverArt = "cover_art",
+        Author = "author",
+        Artist = "artist",
+        ScanlationGroup = "scanlation_group",
+        Tag = "tag",
+        User = "user",
+        CustomList = "custom_list",
+        UploadSession = "upload_session",
+        UploadSessionFile = "upload_session_file",
+        MappingId = "mapping_id",
+    }
 }
 
 #[derive(Deserialize)]
@@ -211,19 +393,19 @@ enum ApiResultDef<T, E> {
     Err(E),
 }
 
+/// A single object, tagged with `"result": "ok"` or `"result": "error"`.
 #[derive(Deserialize)]
 #[serde(bound = "T: DeserializeOwned, E: DeserializeOwned")]
-pub(crate) struct ApiResult<T, E = ApiErrors>(
-    #[serde(with = "ApiResultDef")] std::result::Result<T, E>,
-);
+pub struct ApiResult<T, E = ApiErrors>(#[serde(with = "ApiResultDef")] std::result::Result<T, E>);
 
 impl<T, E> ApiResult<T, E> {
+    /// Get the inner result.
     pub fn into_result(self) -> Result<T, E> {
         self.0
     }
 }
 
-impl<T> FromResponse for Result<T, Errors> {
+impl<T: DeserializeOwned> FromResponse for Result<T, Errors> {
     type Response = ApiResult<T, ApiErrors>;
 
     fn from_response(value: Self::Response) -> Self {
@@ -231,7 +413,7 @@ impl<T> FromResponse for Result<T, Errors> {
     }
 }
 
-impl<T> FromResponse for Results<Result<T, Errors>> {
+impl<T: DeserializeOwned> FromResponse for Results<Result<T, Errors>> {
     type Response = Results<ApiResult<T, ApiErrors>>;
 
     fn from_response(value: Self::Response) -> Self {
@@ -248,7 +430,7 @@ impl<T> FromResponse for Results<Result<T, Errors>> {
     }
 }
 
-impl<T> FromResponse for Vec<Result<T, Errors>> {
+impl<T: DeserializeOwned> FromResponse for Vec<Result<T, Errors>> {
     type Response = Vec<ApiResult<T, ApiErrors>>;
 
     fn from_response(value: Self::Response) -> Self {
@@ -1,11 +1,12 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use url::Url;
 
 use crate::{schema::OrderType, Result};
 
-use super::{ApiData, ApiObject, Results};
+use super::{ApiData, ApiObject, CoverId, MangaId, ResourceType, Results};
 
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub enum CoverOrder {
     CreatedAt(OrderType),
@@ -24,6 +25,42 @@ pub struct CoverAttributes {
     pub updated_at: DateTime<Utc>,
 }
 
-pub type Cover = ApiObject<CoverAttributes>;
+pub type Cover = ApiObject<CoverAttributes, ResourceType, CoverId>;
 pub type CoverResponse = Result<ApiData<Cover>>;
 pub type CoverList = Results<CoverResponse>;
+
+/// The size of a cover image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CoverSize {
+    /// The uploaded image.
+    Original,
+    /// A thumbnail 512 pixels wide.
+    Medium,
+    /// A thumbnail 256 pixels wide.
+    Small,
+}
+
+impl Cover {
+    /// Get the url of the image, on the uploads server (usually
+    /// [`Client::uploads_url()`][crate::Client::uploads_url()]).
+    ///
+    /// The url of a cover contains the id of its manga, which is found in the relationships of the
+    /// cover.
+    pub fn image_url(&self, uploads_url: &Url, manga: MangaId, size: CoverSize) -> Url {
+        let suffix = match size {
+            CoverSize::Original => "",
+            CoverSize::Medium => ".512.jpg",
+            CoverSize::Small => ".256.jpg",
+        };
+        let mut url = uploads_url.clone();
+        url.path_segments_mut()
+            .expect("the uploads url is a base")
+            .pop_if_empty()
+            .extend(&[
+                "covers",
+                &manga.to_string(),
+                &format!("{}{}", self.attributes.file_name, suffix),
+            ]);
+        url
+    }
+}
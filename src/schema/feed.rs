@@ -1,10 +1,13 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use super::OrderType;
 
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub enum FeedOrder {
+    CreatedAt(OrderType),
+    UpdatedAt(OrderType),
+    PublishAt(OrderType),
     Volume(OrderType),
     Chapter(OrderType),
 }
@@ -1,7 +1,7 @@
 use chrono::{DateTime, Utc};
 use serde::Deserialize;
 
-use super::{user::User, ApiData, ApiObject, Results};
+use super::{user::User, ApiData, ApiObject, GroupId, ObjectType, ResourceType, Results};
 use crate::Result;
 
 #[derive(Debug, Deserialize, Clone)]
@@ -27,7 +27,13 @@ pub enum ScanlationGroupType {
     ScanlationGroup,
 }
 
-pub type ScanlationGroup = ApiObject<ScanlationGroupAttributes, ScanlationGroupType>;
+impl ObjectType for ScanlationGroupType {
+    fn resource_type(&self) -> ResourceType {
+        ResourceType::ScanlationGroup
+    }
+}
+
+pub type ScanlationGroup = ApiObject<ScanlationGroupAttributes, ScanlationGroupType, GroupId>;
 pub type ScanlationGroupData = ApiData<ScanlationGroup>;
 pub type ScanlationGroupResponse = Result<ScanlationGroupData>;
 pub type ScanlationGroupList = Results<ScanlationGroupResponse>;
@@ -0,0 +1,89 @@
+//! Typed resource ids
+//!
+//! Every kind of resource has its own id type, so that passing a chapter id where a manga id is
+//! expected is a compile error instead of a 404. They all wrap a [`Uuid`], and are (de)serialized
+//! and displayed like one.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+macro_rules! id_type {
+    ($($(#[$meta:meta])* $name:ident,)*) => {
+        $(
+            $(#[$meta])*
+            #[derive(
+                Debug, Default, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize,
+                Deserialize,
+            )]
+            #[serde(transparent)]
+            pub struct $name(pub Uuid);
+
+            impl $name {
+                /// Wrap an untyped id.
+                pub fn new(id: Uuid) -> Self {
+                    Self(id)
+                }
+
+                /// Get the untyped id.
+                pub fn as_uuid(&self) -> &Uuid {
+                    &self.0
+                }
+            }
+
+            impl From<Uuid> for $name {
+                fn from(id: Uuid) -> Self {
+                    Self(id)
+                }
+            }
+
+            impl From<$name> for Uuid {
+                fn from(id: $name) -> Self {
+                    id.0
+                }
+            }
+
+            impl FromStr for $name {
+                type Err = uuid::Error;
+
+                fn from_str(s: &str) -> Result<Self, Self::Err> {
+                    Uuid::parse_str(s).map(Self)
+                }
+            }
+
+            /// Formats the id as a lowercase hyphenated uuid.
+            impl fmt::Display for $name {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    fmt::Display::fmt(&self.0, f)
+                }
+            }
+
+            impl fmt::LowerHex for $name {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    fmt::LowerHex::fmt(&self.0, f)
+                }
+            }
+        )*
+    };
+}
+
+id_type! {
+    /// The id of a manga.
+    MangaId,
+    /// The id of a chapter.
+    ChapterId,
+    /// The id of an author or an artist.
+    AuthorId,
+    /// The id of a cover art.
+    CoverId,
+    /// The id of a scanlation group.
+    GroupId,
+    /// The id of a user.
+    UserId,
+    /// The id of a custom list.
+    ListId,
+    /// The id of a tag.
+    TagId,
+}
@@ -3,15 +3,15 @@ use uuid::Uuid;
 
 use crate::Result;
 
-use super::{ApiData, ApiObject};
+use super::{ApiData, ApiObject, ObjectType, ResourceType};
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, Hash, PartialEq, Eq)]
-#[serde(rename_all = "snake_case")]
-pub enum MappingType {
-    Group,
-    Manga,
-    Chapter,
-    Tag,
+open_enum! {
+    pub enum MappingType {
+        Group = "group",
+        Manga = "manga",
+        Chapter = "chapter",
+        Tag = "tag",
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Hash, PartialEq, Eq)]
@@ -20,6 +20,12 @@ pub enum MappingIdType {
     MappingId,
 }
 
+impl ObjectType for MappingIdType {
+    fn resource_type(&self) -> ResourceType {
+        ResourceType::MappingId
+    }
+}
+
 #[derive(Debug, Deserialize, Clone, Hash, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct MappingIdAttributes {
@@ -1,24 +1,24 @@
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 
 use crate::Result;
 
-use super::{user::User, ApiData, ApiObject, Results};
+use super::{user::User, ApiData, ApiObject, ListId, ResourceType, Results};
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
-#[serde(rename_all = "camelCase")]
-pub enum CustomListVisibility {
-    Public,
-    Private,
+open_enum! {
+    pub enum CustomListVisibility {
+        Public = "public",
+        Private = "private",
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct CustomListAttributes {
-    name: String,
-    visibility: CustomListVisibility,
-    owner: User,
-    version: i32,
+    pub name: String,
+    pub visibility: CustomListVisibility,
+    pub owner: User,
+    pub version: i32,
 }
 
-pub type CustomList = ApiObject<CustomListAttributes>;
+pub type CustomList = ApiObject<CustomListAttributes, ResourceType, ListId>;
 pub type CustomListResponse = Result<ApiData<CustomList>>;
 pub type CustomListList = Results<CustomListResponse>;
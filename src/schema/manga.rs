@@ -4,9 +4,11 @@ use derive_builder::Builder;
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 use std::collections::HashMap;
-use uuid::Uuid;
 
-use super::{ApiData, ApiObject, LocalizedString, OrderType, Results};
+use super::{
+    ApiData, ApiObject, AuthorId, ChapterId, LanguageCode, LocalizedString, MangaId, OrderType,
+    ResourceType, Results, TagId,
+};
 
 /// The tag mode.
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, Hash, PartialEq, Eq)]
@@ -22,49 +24,66 @@ pub enum TagMode {
 #[serde(rename_all = "camelCase")]
 pub struct TagAttributes {
     pub name: LocalizedString,
-    // TODO: Known issue: empty descriptions return [] instead of {}
-    #[serde(skip)]
     pub description: LocalizedString,
-    pub group: String,
+    pub group: TagGroup,
     pub version: i32,
 }
 
-/// The status of a manga.
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, Hash, PartialEq, Eq)]
-#[serde(rename_all = "lowercase")]
-pub enum MangaStatus {
-    Ongoing,
-    Completed,
-    Hiatus,
-    Cancelled,
+open_enum! {
+    /// The group of a tag.
+    pub enum TagGroup {
+        Genre = "genre",
+        Theme = "theme",
+        Format = "format",
+        Content = "content",
+    }
 }
 
-/// The publication demographic.
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, Hash, PartialEq, Eq)]
-#[serde(rename_all = "lowercase")]
-pub enum Demographic {
-    Shounen,
-    Shoujo,
-    Josei,
-    Seinen,
-    None,
+open_enum! {
+    /// The status of a manga.
+    pub enum MangaStatus {
+        Ongoing = "ongoing",
+        Completed = "completed",
+        Hiatus = "hiatus",
+        Cancelled = "cancelled",
+    }
 }
 
-/// The content rating of the publication.
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, Hash, PartialEq, Eq)]
-#[serde(rename_all = "lowercase")]
-pub enum ContentRating {
-    Safe,
-    Suggestive,
-    Erotica,
-    Pornographic,
+open_enum! {
+    /// The publication demographic.
+    pub enum Demographic {
+        Shounen = "shounen",
+        Shoujo = "shoujo",
+        Josei = "josei",
+        Seinen = "seinen",
+        None = "none",
+    }
+}
+
+open_enum! {
+    /// The content rating of the publication.
+    pub enum ContentRating {
+        /// Not rated, only used to search.
+        None = "none",
+        Safe = "safe",
+        Suggestive = "suggestive",
+        Erotica = "erotica",
+        Pornographic = "pornographic",
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Hash, PartialEq, Eq)]
+/// A sort key of a manga search, with its direction.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Hash, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub enum MangaOrder {
+    Title(OrderType),
+    Year(OrderType),
     CreatedAt(OrderType),
     UpdatedAt(OrderType),
+    LatestUploadedChapter(OrderType),
+    FollowedCount(OrderType),
+    /// How well the manga matches the searched title.
+    Relevance(OrderType),
 }
 
 pub type Links = HashMap<String, String>;
@@ -73,13 +92,12 @@ pub type Links = HashMap<String, String>;
 #[serde(rename_all = "camelCase")]
 pub struct MangaAttributes {
     pub title: LocalizedString,
+    #[serde(deserialize_with = "deserialize_null_default")]
     pub alt_titles: Vec<LocalizedString>,
-    // TODO: Known issue: empty descriptions return [] instead of {}
-    #[serde(skip)]
     pub description: LocalizedString,
     #[serde(deserialize_with = "deserialize_null_default")]
     pub links: Links,
-    pub original_language: String,
+    pub original_language: LanguageCode,
     pub last_volume: Option<String>,
     pub last_chapter: Option<String>,
     pub publication_demographic: Option<Demographic>,
@@ -107,16 +125,16 @@ pub struct MangaRequest {
     pub description: Option<LocalizedString>,
 
     #[builder(default)]
-    pub authors: Option<Vec<Uuid>>,
+    pub authors: Option<Vec<AuthorId>>,
 
     #[builder(default)]
-    pub artists: Option<Vec<Uuid>>,
+    pub artists: Option<Vec<AuthorId>>,
 
     #[builder(default)]
     pub links: Option<Links>,
 
     #[builder(default)]
-    pub original_language: Option<String>,
+    pub original_language: Option<LanguageCode>,
 
     #[builder(default)]
     pub last_volume: Option<String>,
@@ -146,18 +164,18 @@ pub struct MangaRequest {
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct MangaReadingStatuses {
-    pub statuses: HashMap<Uuid, MangaReadingStatus>,
+    pub statuses: HashMap<MangaId, MangaReadingStatus>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "snake_case")]
-pub enum MangaReadingStatus {
-    Reading,
-    OnHold,
-    PlanToRead,
-    Dropped,
-    ReReading,
-    Completed,
+open_enum! {
+    pub enum MangaReadingStatus {
+        Reading = "reading",
+        OnHold = "on_hold",
+        PlanToRead = "plan_to_read",
+        Dropped = "dropped",
+        ReReading = "re_reading",
+        Completed = "completed",
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -168,31 +186,72 @@ pub struct MangaReadingStatusBody {
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct ChapterAggregate {
-    chapter: String,
-    count: i32,
+    pub chapter: String,
+    pub count: i32,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct VolumeAggregate {
-    volume: String,
-    count: i32,
-    chapters: HashMap<String, ChapterAggregate>,
+    pub volume: String,
+    pub count: i32,
+    pub chapters: HashMap<String, ChapterAggregate>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct MangaAggregate {
-    volumes: HashMap<String, VolumeAggregate>,
+    pub volumes: HashMap<String, VolumeAggregate>,
 }
 
 pub type MangaAggregateResponse = Result<MangaAggregate>;
 
-pub type Tag = ApiObject<TagAttributes>;
+pub type Tag = ApiObject<TagAttributes, ResourceType, TagId>;
 pub type TagResponse = Result<ApiData<Tag>>;
 pub type TagList = Vec<TagResponse>;
 
-pub type Manga = ApiObject<MangaAttributes>;
+pub type Manga = ApiObject<MangaAttributes, ResourceType, MangaId>;
 pub type MangaResponse = Result<ApiData<Manga>>;
 pub type MangaList = Results<MangaResponse>;
 
-pub type MangaReadMarkers = Vec<Uuid>;
+impl Manga {
+    /// Get the title to display, following `languages` (usually
+    /// [`Client::preferred_languages()`][crate::Client::preferred_languages()]).
+    ///
+    /// The main title and the alternative titles are searched in each of `languages`, then in the
+    /// romanized original language, English, and the original language. If none of them is
+    /// available, any title is returned, or an empty string if the manga has none.
+    pub fn display_title(&self, languages: &[LanguageCode]) -> &str {
+        let attributes = &self.attributes;
+        let titles = || std::iter::once(&attributes.title).chain(&attributes.alt_titles);
+        self.fallback_languages(languages)
+            .find_map(|language| titles().find_map(|title| title.get(&language)))
+            .map(String::as_str)
+            .or_else(|| attributes.title.any())
+            .unwrap_or_default()
+    }
+
+    /// Get the description, following `languages` like
+    /// [`display_title()`][Self::display_title()].
+    pub fn description(&self, languages: &[LanguageCode]) -> Option<&str> {
+        let description = &self.attributes.description;
+        self.fallback_languages(languages)
+            .find_map(|language| description.get(&language))
+            .map(String::as_str)
+            .or_else(|| description.any())
+    }
+
+    fn fallback_languages<'a>(
+        &'a self,
+        languages: &'a [LanguageCode],
+    ) -> impl Iterator<Item = LanguageCode> + 'a {
+        let original = &self.attributes.original_language;
+        languages
+            .iter()
+            .cloned()
+            .chain(original.romanized())
+            .chain(std::iter::once(LanguageCode::English))
+            .chain(std::iter::once(original.clone()))
+    }
+}
+
+pub type MangaReadMarkers = Vec<ChapterId>;
 pub type MangaReadMarkerResponse = Result<ApiData<MangaReadMarkers>>;
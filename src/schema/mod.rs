@@ -1,4 +1,75 @@
 //! Data structures for API abstraction
+//!
+//! The enums of values sent by the api (languages, resource types, statuses...) have an `Unknown`
+//! variant, holding the values added to the api after this version of the crate, so that a new
+//! value doesn't make whole responses fail to decode. Enable the `strict` feature to make unknown
+//! values an error instead.
+
+/// Define an enum of string values, with an `Unknown(String)` fallback.
+///
+/// Each variant is given with the value used by the api: `Variant = "value"`.
+macro_rules! open_enum {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $($(#[$variant_meta:meta])* $variant:ident = $value:literal,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+        pub enum $name {
+            $($(#[$variant_meta])* $variant,)*
+            /// A value unknown to this version of the crate.
+            Unknown(String),
+        }
+
+        impl $name {
+            /// The values known to this version of the crate, as sent by the api.
+            pub const VALUES: &'static [&'static str] = &[$($value),*];
+
+            /// Get the value sent by the api.
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $(Self::$variant => $value,)*
+                    Self::Unknown(value) => value,
+                }
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(value: &str) -> Self {
+                match value {
+                    $($value => Self::$variant,)*
+                    _ => Self::Unknown(value.to_string()),
+                }
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = String::deserialize(deserializer)?;
+                match Self::from(&*value) {
+                    Self::Unknown(value) if cfg!(feature = "strict") => Err(
+                        serde::de::Error::unknown_variant(&value, Self::VALUES),
+                    ),
+                    known => Ok(known),
+                }
+            }
+        }
+    };
+}
 
 pub mod at_home;
 pub mod auth;
@@ -12,7 +83,84 @@ pub mod legacy;
 pub mod list;
 pub mod manga;
 pub mod report;
+pub mod upload;
 pub mod user;
 
 mod common;
+mod ids;
 pub use common::*;
+pub use ids::*;
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::manga::MangaStatus;
+    use super::{LanguageCode, LocalizedString, MangaId, ResourceType};
+
+    #[test]
+    fn known_values() {
+        let kind: ResourceType = serde_json::from_value(json!("scanlation_group")).unwrap();
+        assert_eq!(kind, ResourceType::ScanlationGroup);
+        assert_eq!(
+            serde_json::to_value(&kind).unwrap(),
+            json!("scanlation_group")
+        );
+        assert_eq!(MangaStatus::from("hiatus"), MangaStatus::Hiatus);
+    }
+
+    #[test]
+    fn unknown_values() {
+        let kind = serde_json::from_value::<ResourceType>(json!("thread"));
+        if cfg!(feature = "strict") {
+            assert!(kind.is_err());
+        } else {
+            let kind = kind.unwrap();
+            assert_eq!(kind, ResourceType::Unknown("thread".to_string()));
+            assert_eq!(serde_json::to_value(&kind).unwrap(), json!("thread"));
+        }
+    }
+
+    #[test]
+    fn language_codes() {
+        assert_eq!("ja-ro".parse(), Ok(LanguageCode::JapaneseRomanized));
+        assert_eq!("pt_BR".parse(), Ok(LanguageCode::PortugueseBr));
+        assert!("klingon".parse::<LanguageCode>().is_err());
+        assert_eq!(LanguageCode::SpanishLATAM.to_string(), "es-la");
+
+        assert_eq!(LanguageCode::Japanese.name(), Some("Japanese"));
+        assert_eq!(LanguageCode::Japanese.native_name(), Some("日本語"));
+        assert_eq!(LanguageCode::Japanese.flag().as_deref(), Some("🇯🇵"));
+        assert_eq!(LanguageCode::Esperanto.flag(), None);
+        assert!(LanguageCode::KoreanRomanized.is_romanized());
+    }
+
+    #[test]
+    fn localized_strings() {
+        let title: LocalizedString =
+            serde_json::from_value(json!({ "ja": "進撃の巨人", "ja-ro": "Shingeki no Kyojin" }))
+                .unwrap();
+        let languages = [LanguageCode::English, LanguageCode::JapaneseRomanized];
+        assert_eq!(title.best(&languages), Some("Shingeki no Kyojin"));
+        assert_eq!(title.best(&[LanguageCode::French]), None);
+        assert_eq!(title.any(), Some("進撃の巨人"));
+
+        let empty: LocalizedString = serde_json::from_value(json!([])).unwrap();
+        assert!(empty.is_empty());
+        let null: LocalizedString = serde_json::from_value(json!(null)).unwrap();
+        assert!(null.is_empty());
+        assert!(serde_json::from_value::<LocalizedString>(json!(["en"])).is_err());
+    }
+
+    #[test]
+    fn typed_ids() {
+        let id: MangaId = "32D76D19-8A05-4DB0-9FC2-E0B0648FE9D0".parse().unwrap();
+        assert_eq!(id.to_string(), "32d76d19-8a05-4db0-9fc2-e0b0648fe9d0");
+        assert_eq!(
+            serde_json::to_value(id).unwrap(),
+            json!("32d76d19-8a05-4db0-9fc2-e0b0648fe9d0")
+        );
+        let uuid: uuid::Uuid = id.into();
+        assert_eq!(MangaId::from(uuid), id);
+    }
+}
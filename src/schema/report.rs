@@ -3,24 +3,13 @@ use serde::{Deserialize, Serialize};
 use super::{ApiData, ApiObject, LocalizedString, Results};
 use crate::Result;
 
-/// The report category.
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, Hash, PartialEq, Eq)]
-#[serde(rename_all = "lowercase")]
-pub enum ReportCategory {
-    Manga,
-    Chapter,
-    ScanlationGroup,
-    User,
-}
-
-impl std::fmt::Display for ReportCategory {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::Manga => write!(f, "manga"),
-            Self::Chapter => write!(f, "chapter"),
-            Self::ScanlationGroup => write!(f, "scanlation_group"),
-            Self::User => write!(f, "user"),
-        }
+open_enum! {
+    /// The report category.
+    pub enum ReportCategory {
+        Manga = "manga",
+        Chapter = "chapter",
+        ScanlationGroup = "scanlation_group",
+        User = "user",
     }
 }
 
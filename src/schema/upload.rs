@@ -0,0 +1,47 @@
+// This file is generated from `api.yaml` by `cargo xtask codegen`, do not edit it by hand.
+
+//! Upload sessions
+
+use serde::{Deserialize, Serialize};
+
+use super::{ApiData, ApiObject, LanguageCode};
+use crate::Result;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ChapterDraft {
+    pub volume: Option<String>,
+    pub chapter: Option<String>,
+    pub title: Option<String>,
+    pub translated_language: LanguageCode,
+}
+
+/// The response of [`PutUploadSessionFile`](crate::api::upload::PutUploadSessionFile).
+pub type PutUploadSessionFileResponse = Result<ApiData<Vec<UploadSessionFile>>>;
+
+pub type UploadSession = ApiObject<UploadSessionAttributes>;
+pub type UploadSessionResponse = Result<ApiData<UploadSession>>;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadSessionAttributes {
+    pub is_committed: bool,
+    pub is_processed: bool,
+    pub is_deleted: bool,
+    pub version: i32,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+pub type UploadSessionFile = ApiObject<UploadSessionFileAttributes>;
+pub type UploadSessionFileResponse = Result<ApiData<UploadSessionFile>>;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadSessionFileAttributes {
+    pub original_file_name: String,
+    pub file_hash: String,
+    pub file_size: f64,
+    pub mime_type: String,
+    pub version: i32,
+}
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use super::{ApiData, ApiObject, OrderType, Results};
+use super::{ApiData, ApiObject, ObjectType, OrderType, ResourceType, Results, UserId};
 use crate::Result;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -16,12 +16,18 @@ pub enum UserType {
     User,
 }
 
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub enum UserOrder {
     Username(OrderType),
 }
 
-pub type User = ApiObject<UserAttributes, UserType>;
+impl ObjectType for UserType {
+    fn resource_type(&self) -> ResourceType {
+        ResourceType::User
+    }
+}
+
+pub type User = ApiObject<UserAttributes, UserType, UserId>;
 pub type UserResponse = Result<ApiData<User>>;
 pub type UserList = Results<UserResponse>;
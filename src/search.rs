@@ -0,0 +1,700 @@
+//! A query language for manga searches.
+//!
+//! [`parse_query()`] compiles queries such as the ones typed in a chat bot into a [`Search`]
+//! wrapping a [`ListManga`] request:
+//!
+//! ```text
+//! title:"solo leveling" status:ongoing tag:action -tag:gore lang:ko year:>=2018 sort:updated-desc
+//! ```
+//!
+//! A query is a list of `key:value` terms separated by spaces. Values containing spaces are
+//! quoted, and keys taking several values accept a comma separated list (`status:ongoing,hiatus`).
+//! Words without a key are searched in the title.
+//!
+//! The api only searches an exact year: year ranges are left out of the request and applied to
+//! the results by [`Search::send()`], which pages through the api until enough manga match.
+//!
+//! | Key | Value |
+//! |-----|-------|
+//! | `title` | Text in the title |
+//! | `status` | [`MangaStatus`] values |
+//! | `demographic`, `demo` | [`Demographic`] values |
+//! | `rating` | [`ContentRating`] values |
+//! | `tag`, `-tag` | Tag names to include or exclude, see [`TagCatalog`] |
+//! | `lang`, `language` | Original [`LanguageCode`]s |
+//! | `available` | [`LanguageCode`]s the manga has chapters in |
+//! | `year` | Year of release, or a range such as `>=2018`, `>2018`, `<=2018` or `<2018` |
+//! | `author`, `artist` | Author ids |
+//! | `sort` | `title`, `year`, `created`, `updated`, `chapter` (latest upload), `follows` or `relevance`, followed by `-asc` or `-desc` (the default). Several sorts are applied by decreasing priority. |
+//!
+//! ```rust,no_run
+//! use mangadex::search::parse_query;
+//! use mangadex::Client;
+//!
+//! # async fn run(client: &Client) -> anyhow::Result<()> {
+//! let catalog = client.tag_catalog().await?;
+//! match parse_query("status:ongoin", &catalog) {
+//!     Ok(search) => println!("{} results", search.send(client).await?.results.len()),
+//!     // invalid status "ongoin", expected one of: ongoing, completed, hiatus, cancelled (at 7)
+//!     Err(err) => println!("{}", err),
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::api::manga::ListManga;
+use crate::schema::manga::{ContentRating, Demographic, Manga, MangaList, MangaOrder, MangaStatus};
+use crate::schema::{AuthorId, LanguageCode, OrderType};
+use crate::tags::TagCatalog;
+use crate::{Client, Result};
+
+/// The error returned by [`parse_query()`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{kind} (at {offset})")]
+pub struct ParseQueryError {
+    /// Byte offset of the faulty part of the query.
+    pub offset: usize,
+    /// What is wrong.
+    pub kind: QueryErrorKind,
+}
+
+/// The kinds of [`ParseQueryError`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum QueryErrorKind {
+    #[error("missing closing quote")]
+    UnterminatedQuote,
+
+    #[error("missing value for {0:?}")]
+    MissingValue(String),
+
+    #[error("unknown key {0:?}, expected one of: {}", KEYS.join(", "))]
+    UnknownKey(String),
+
+    #[error("{0:?} can't be negated")]
+    NotNegatable(String),
+
+    #[error("invalid {key} {value:?}, expected {expected}")]
+    InvalidValue {
+        key: String,
+        value: String,
+        expected: String,
+    },
+
+    #[error("unknown tag {0:?}")]
+    UnknownTag(String),
+
+    #[error("{0:?} takes a single value")]
+    SingleValue(String),
+}
+
+const SORTS: &[&str] = &[
+    "title",
+    "year",
+    "created",
+    "updated",
+    "chapter",
+    "follows",
+    "relevance",
+];
+
+/// Number of manga requested at once when filtering the results.
+const SCAN_LIMIT: i32 = 100;
+
+/// The api doesn't list manga past the 10000th.
+const MAX_RESULTS: i32 = 10_000;
+
+const KEYS: &[&str] = &[
+    "title",
+    "status",
+    "demographic",
+    "rating",
+    "tag",
+    "lang",
+    "available",
+    "year",
+    "author",
+    "artist",
+    "sort",
+];
+
+/// A compiled search query.
+#[derive(Debug, Clone, Default)]
+pub struct Search {
+    /// The request to send.
+    pub request: ListManga,
+
+    /// Years of release to keep, for ranges such as `year:>=2018`.
+    ///
+    /// The api only searches an exact year, so ranges are not sent but applied to the results by
+    /// [`Search::send()`].
+    pub years: Option<RangeInclusive<i32>>,
+}
+
+impl Search {
+    /// Whether `manga` passes the filters applied to the results.
+    pub fn matches(&self, manga: &Manga) -> bool {
+        match &self.years {
+            Some(years) => manga
+                .attributes
+                .year
+                .is_some_and(|year| years.contains(&year)),
+            None => true,
+        }
+    }
+
+    /// Send the request, keeping only the manga [matching](Search::matches()) the query.
+    ///
+    /// When the results are filtered, the api is paged through until `limit` manga match, and the
+    /// `offset` of the request counts the matching manga only. The `total` counts the matching
+    /// manga found so far plus the ones left to fetch, so it is exact once the last page is
+    /// reached.
+    pub async fn send(&self, client: &Client) -> Result<MangaList> {
+        if self.years.is_none() {
+            return self.request.send(client).await;
+        }
+
+        let limit = self.request.limit.unwrap_or(10);
+        let offset = self.request.offset.unwrap_or(0);
+        let mut request = self.request.clone();
+        let mut results = Vec::new();
+        let mut matched = 0;
+        let mut fetched = 0;
+        loop {
+            request.offset = Some(fetched);
+            request.limit = Some(SCAN_LIMIT.min(MAX_RESULTS - fetched));
+            let page = request.send(client).await?;
+            let last = page.results.is_empty();
+            fetched += page.results.len() as i32;
+            for result in page.results {
+                if let Ok(manga) = &result {
+                    if !self.matches(&manga.data) {
+                        continue;
+                    }
+                }
+                if matched >= offset && (results.len() as i32) < limit {
+                    results.push(result);
+                }
+                matched += 1;
+            }
+
+            let remaining = (page.total.min(MAX_RESULTS) - fetched).max(0);
+            if last || remaining == 0 || results.len() as i32 == limit {
+                return Ok(MangaList {
+                    results,
+                    limit,
+                    offset,
+                    total: matched + remaining,
+                });
+            }
+        }
+    }
+}
+
+/// Compile a search query into a [`Search`], looking up tag names in `catalog`.
+///
+/// See the [module documentation](self) for the syntax.
+pub fn parse_query(query: &str, catalog: &TagCatalog) -> Result<Search, ParseQueryError> {
+    let mut request = ListManga::default();
+    let mut years = None;
+    let mut title = Vec::new();
+
+    for term in Lexer::new(query).terms() {
+        let term = term?;
+        let key = match &term.key {
+            Some(key) => key.to_lowercase(),
+            None => {
+                title.extend(term.values.into_iter().map(|v| v.text));
+                continue;
+            }
+        };
+
+        let error = |kind| ParseQueryError {
+            offset: term.offset,
+            kind,
+        };
+        if term.values.is_empty() {
+            return Err(error(QueryErrorKind::MissingValue(key)));
+        }
+        if term.negated && key != "tag" {
+            return Err(error(QueryErrorKind::NotNegatable(key)));
+        }
+
+        match key.as_str() {
+            "title" => title.push(join(term.values)),
+            "status" => {
+                for value in term.values {
+                    request.status.push(value.open_enum("status")?);
+                }
+            }
+            "demographic" | "demo" => {
+                for value in term.values {
+                    let demographic = value.open_enum("demographic")?;
+                    request.publication_demographic.push(demographic);
+                }
+            }
+            "rating" => {
+                for value in term.values {
+                    request.content_rating.push(value.open_enum("rating")?);
+                }
+            }
+            "tag" => {
+                for value in term.values {
+                    let id = catalog
+                        .require(&value.text)
+                        .map_err(|err| value.error(QueryErrorKind::UnknownTag(err.0)))?;
+                    if term.negated {
+                        request.excluded_tags.push(id);
+                    } else {
+                        request.included_tags.push(id);
+                    }
+                }
+            }
+            "lang" | "language" => {
+                for value in term.values {
+                    let language = value.parse::<LanguageCode>(
+                        "language",
+                        "a language code such as \"ko\" or \"ja-ro\"",
+                    )?;
+                    request.original_language.push(language);
+                }
+            }
+            "available" => {
+                for value in term.values {
+                    let language = value.parse::<LanguageCode>(
+                        "language",
+                        "a language code such as \"en\" or \"pt-br\"",
+                    )?;
+                    request.available_translated_language.push(language);
+                }
+            }
+            "year" => {
+                if let Some(value) = term.values.get(1) {
+                    return Err(value.error(QueryErrorKind::SingleValue(key)));
+                }
+                let value = &term.values[0];
+                let (op, year) = match value.text.find(|c: char| !"<>=".contains(c)) {
+                    Some(i) => value.text.split_at(i),
+                    None => (value.text.as_str(), ""),
+                };
+                let op = op.to_string();
+                let year = Value {
+                    text: year.to_string(),
+                    offset: value.offset + op.len(),
+                }
+                .parse::<i32>("year", "a year such as 2018")?;
+                match op.as_str() {
+                    "" | "=" => request.year = Some(year),
+                    ">=" => years = Some(year..=i32::MAX),
+                    ">" => years = Some(year.saturating_add(1)..=i32::MAX),
+                    "<=" => years = Some(i32::MIN..=year),
+                    "<" => years = Some(i32::MIN..=year.saturating_sub(1)),
+                    _ => {
+                        return Err(value.invalid(
+                            "year",
+                            "a year such as 2018, optionally after >=, >, <= or <".to_string(),
+                        ))
+                    }
+                }
+            }
+            "author" | "artist" => {
+                for value in term.values {
+                    let id = value.parse::<AuthorId>(&key, "an author id")?;
+                    if key == "author" {
+                        request.authors.push(id);
+                    } else {
+                        request.artists.push(id);
+                    }
+                }
+            }
+            "sort" => {
+                for value in term.values {
+                    request.order.push(parse_order(&value)?);
+                }
+            }
+            _ => return Err(error(QueryErrorKind::UnknownKey(key))),
+        }
+    }
+
+    if !title.is_empty() {
+        request.title = Some(title.join(" "));
+    }
+    Ok(Search { request, years })
+}
+
+/// Parse an order such as `updated-desc`.
+fn parse_order(value: &Value) -> Result<MangaOrder, ParseQueryError> {
+    let text = value.text.to_lowercase();
+    let (field, direction) = match text.rsplit_once('-') {
+        Some((field, "asc")) => (field, OrderType::Ascending),
+        Some((field, "desc")) => (field, OrderType::Descending),
+        _ => (text.as_str(), OrderType::Descending),
+    };
+
+    match field {
+        "title" => Ok(MangaOrder::Title(direction)),
+        "year" => Ok(MangaOrder::Year(direction)),
+        "created" | "createdat" => Ok(MangaOrder::CreatedAt(direction)),
+        "updated" | "updatedat" => Ok(MangaOrder::UpdatedAt(direction)),
+        "chapter" | "latestuploadedchapter" => Ok(MangaOrder::LatestUploadedChapter(direction)),
+        "follows" | "followedcount" => Ok(MangaOrder::FollowedCount(direction)),
+        "relevance" => Ok(MangaOrder::Relevance(direction)),
+        _ => Err(value.invalid(
+            "sort",
+            format!("one of: {}, followed by -asc or -desc", SORTS.join(", ")),
+        )),
+    }
+}
+
+fn join(values: Vec<Value>) -> String {
+    values
+        .into_iter()
+        .map(|v| v.text)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// A `[-]key:value,value` term, or a word without key.
+#[derive(Debug)]
+struct Term {
+    offset: usize,
+    negated: bool,
+    key: Option<String>,
+    values: Vec<Value>,
+}
+
+/// A value of a term, quotes removed.
+#[derive(Debug)]
+struct Value {
+    text: String,
+    offset: usize,
+}
+
+impl Value {
+    fn error(&self, kind: QueryErrorKind) -> ParseQueryError {
+        ParseQueryError {
+            offset: self.offset,
+            kind,
+        }
+    }
+
+    fn invalid(&self, key: &str, expected: String) -> ParseQueryError {
+        self.error(QueryErrorKind::InvalidValue {
+            key: key.to_string(),
+            value: self.text.clone(),
+            expected,
+        })
+    }
+
+    fn parse<T: FromStr>(&self, key: &str, expected: &str) -> Result<T, ParseQueryError> {
+        self.text
+            .parse()
+            .map_err(|_| self.invalid(key, expected.to_string()))
+    }
+
+    /// Parse a value of an [`open_enum!`], rejecting the values unknown to the crate.
+    fn open_enum<T>(&self, key: &str) -> Result<T, ParseQueryError>
+    where
+        T: for<'a> From<&'a str> + OpenEnum,
+    {
+        let text = self.text.to_lowercase();
+        if T::values().contains(&text.as_str()) {
+            Ok(T::from(&text))
+        } else {
+            Err(self.invalid(key, format!("one of: {}", T::values().join(", "))))
+        }
+    }
+}
+
+/// The known values of the enums accepted in queries.
+trait OpenEnum {
+    fn values() -> &'static [&'static str];
+}
+
+macro_rules! impl_open_enum {
+    ($($name:ty),*) => {
+        $(impl OpenEnum for $name {
+            fn values() -> &'static [&'static str] {
+                Self::VALUES
+            }
+        })*
+    };
+}
+
+impl_open_enum!(MangaStatus, Demographic, ContentRating);
+
+struct Lexer<'a> {
+    query: &'a str,
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(query: &'a str) -> Self {
+        Self { query, pos: 0 }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.query[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+        let start = self.pos;
+        while self.peek().is_some_and(&f) {
+            self.bump();
+        }
+        &self.query[start..self.pos]
+    }
+
+    fn terms(mut self) -> impl Iterator<Item = Result<Term, ParseQueryError>> + 'a {
+        std::iter::from_fn(move || {
+            self.eat_while(char::is_whitespace);
+            self.peek()?;
+            Some(self.term())
+        })
+    }
+
+    fn term(&mut self) -> Result<Term, ParseQueryError> {
+        let offset = self.pos;
+
+        // A key, possibly negated, followed by a colon.
+        let negated = self.peek() == Some('-');
+        if negated {
+            self.bump();
+        }
+        let key = self.eat_while(|c| c.is_alphanumeric() || c == '_');
+        if !key.is_empty() && self.peek() == Some(':') {
+            self.bump();
+            let mut values = Vec::new();
+            while self.peek().is_some_and(|c| !c.is_whitespace()) {
+                values.push(self.value(|c| c == ',' || c.is_whitespace())?);
+                if self.peek() == Some(',') {
+                    self.bump();
+                }
+            }
+            return Ok(Term {
+                offset,
+                negated,
+                key: Some(key.to_string()),
+                values,
+            });
+        }
+
+        // A word of the title.
+        self.pos = offset;
+        let value = self.value(char::is_whitespace)?;
+        Ok(Term {
+            offset,
+            negated: false,
+            key: None,
+            values: vec![value],
+        })
+    }
+
+    fn value(&mut self, end: impl Fn(char) -> bool) -> Result<Value, ParseQueryError> {
+        let offset = self.pos;
+        if self.peek() != Some('"') {
+            let text = self.eat_while(|c| !end(c));
+            return Ok(Value {
+                text: text.to_string(),
+                offset,
+            });
+        }
+
+        self.bump();
+        let text = self.eat_while(|c| c != '"');
+        if self.bump().is_none() {
+            return Err(ParseQueryError {
+                offset,
+                kind: QueryErrorKind::UnterminatedQuote,
+            });
+        }
+        Ok(Value {
+            text: text.to_string(),
+            offset: offset + 1,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use httpmock::Method::GET;
+    use httpmock::MockServer;
+    use pretty_assertions::assert_eq;
+
+    use crate::cassette::test_client;
+
+    use super::*;
+
+    const ACTION: &str = "391b0423-d847-456f-aff0-8b0cfc03066b";
+    const ROMANCE: &str = "423e2eae-a7a2-4a8b-ac03-a8351462d71d";
+
+    async fn catalog() -> TagCatalog {
+        TagCatalog::load(&test_client("manga/tag_list"))
+            .await
+            .unwrap()
+    }
+
+    fn error(query: &str, catalog: &TagCatalog) -> (usize, String) {
+        let err = parse_query(query, catalog).unwrap_err();
+        (err.offset, err.kind.to_string())
+    }
+
+    #[tokio::test]
+    async fn compile_query() -> anyhow::Result<()> {
+        let catalog = catalog().await;
+        let request = parse_query(
+            r#"title:"solo leveling" status:ongoing,HIATUS tag:action -tag:romance lang:ko available:en,FR year:2018 rating:safe,suggestive sort:updated-asc,follows"#,
+            &catalog,
+        )?
+        .request;
+
+        assert_eq!(request.title.as_deref(), Some("solo leveling"));
+        assert_eq!(
+            request.status,
+            vec![MangaStatus::Ongoing, MangaStatus::Hiatus]
+        );
+        assert_eq!(request.included_tags, vec![ACTION.parse()?]);
+        assert_eq!(request.excluded_tags, vec![ROMANCE.parse()?]);
+        assert_eq!(request.original_language, vec![LanguageCode::Korean]);
+        assert_eq!(request.year, Some(2018));
+        assert_eq!(
+            request.available_translated_language,
+            vec![LanguageCode::English, LanguageCode::French]
+        );
+        assert_eq!(
+            request.content_rating,
+            vec![ContentRating::Safe, ContentRating::Suggestive]
+        );
+        assert_eq!(
+            request.order,
+            vec![
+                MangaOrder::UpdatedAt(OrderType::Ascending),
+                MangaOrder::FollowedCount(OrderType::Descending)
+            ]
+        );
+
+        let request = parse_query("solo   leveling demo:seinen", &catalog)?.request;
+        assert_eq!(request.title.as_deref(), Some("solo leveling"));
+        assert_eq!(request.publication_demographic, vec![Demographic::Seinen]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn year_ranges() -> anyhow::Result<()> {
+        let catalog = catalog().await;
+        let years = |query| parse_query(query, &catalog).map(|search| search.years);
+        assert_eq!(years("year:>=2018")?, Some(2018..=i32::MAX));
+        assert_eq!(years("year:>2018")?, Some(2019..=i32::MAX));
+        assert_eq!(years("year:<=2018")?, Some(i32::MIN..=2018));
+        assert_eq!(years("year:<2018")?, Some(i32::MIN..=2017));
+        assert_eq!(years("year:=2018")?, None);
+        assert_eq!(parse_query("year:>=2018", &catalog)?.request.year, None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn page_through_year_ranges() -> anyhow::Result<()> {
+        let catalog = catalog().await;
+        let cassette: serde_json::Value =
+            serde_json::from_str(include_str!("../tests/cassettes/manga/list_manga.json"))?;
+        let template = &cassette[0]["response"]["body"]["results"][0];
+        let page = |years: &[i32], total: i32| {
+            let results: Vec<serde_json::Value> = years
+                .iter()
+                .map(|year| {
+                    let mut manga = template.clone();
+                    manga["data"]["attributes"]["year"] = (*year).into();
+                    manga
+                })
+                .collect();
+            serde_json::json!({ "results": results, "limit": 100, "offset": 0, "total": total })
+        };
+
+        let server = MockServer::start_async().await;
+        let first = server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/manga")
+                    .query_param("limit", "100")
+                    .query_param("offset", "0");
+                then.status(200)
+                    .header("Content-Type", "application/json")
+                    .json_body(page(&[2018, 2015, 2020], 6));
+            })
+            .await;
+        let second = server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/manga")
+                    .query_param("limit", "100")
+                    .query_param("offset", "3");
+                then.status(200)
+                    .header("Content-Type", "application/json")
+                    .json_body(page(&[2010, 2019], 6));
+            })
+            .await;
+        let client = Client::builder().base_url(&server.base_url()).build()?;
+
+        // Skips the first matching manga, and fetches the second page to fill the limit.
+        let mut search = parse_query("year:>=2018", &catalog)?;
+        search.request.limit = Some(2);
+        search.request.offset = Some(1);
+        let manga = search.send(&client).await?;
+        first.assert_async().await;
+        second.assert_async().await;
+        let years: Vec<i32> = manga
+            .results
+            .into_iter()
+            .map(|manga| manga.unwrap().data.attributes.year.unwrap())
+            .collect();
+        assert_eq!(years, vec![2020, 2019]);
+        assert_eq!((manga.limit, manga.offset), (2, 1));
+        // 3 matching manga so far, and a manga left to fetch.
+        assert_eq!(manga.total, 4);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn query_errors() {
+        let catalog = catalog().await;
+        assert_eq!(
+            error("tag:action status:ongoin", &catalog),
+            (
+                18,
+                "invalid status \"ongoin\", expected one of: ongoing, completed, hiatus, cancelled"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            error("tag:action,isekai", &catalog),
+            (11, "unknown tag \"isekai\"".to_string())
+        );
+        assert_eq!(
+            error(r#"title:"solo"#, &catalog),
+            (6, "missing closing quote".to_string())
+        );
+        assert_eq!(
+            error("x -status:ongoing", &catalog),
+            (2, "\"status\" can't be negated".to_string())
+        );
+        assert_eq!(error("colour:red", &catalog).0, 0);
+        assert_eq!(error("year:>=twenty", &catalog).0, 7);
+        assert_eq!(error("year:=>2018", &catalog).0, 5);
+        assert_eq!(
+            error("year:2018,2019", &catalog),
+            (10, "\"year\" takes a single value".to_string())
+        );
+        assert_eq!(error("lang:xx", &catalog).0, 5);
+        assert_eq!(error("sort:", &catalog).0, 0);
+    }
+}
@@ -0,0 +1,711 @@
+//! Atom and RSS feeds of chapters.
+//!
+//! A [`FeedWriter`] turns the chapters of [`GetMangaFeed`][crate::api::feed::GetMangaFeed],
+//! [`CustomListMangaFeed`][crate::api::feed::CustomListMangaFeed] or
+//! [`FollowedMangaFeed`][crate::api::feed::FollowedMangaFeed] into an Atom or RSS 2.0 document,
+//! for feed readers. The api only gives the ids of the scanlation groups of a chapter, so their
+//! names are resolved with [`FeedWriter::resolve_groups()`].
+//!
+//! ```rust,no_run
+//! use mangadex::api::feed::GetMangaFeedBuilder;
+//! use mangadex::syndication::{FeedFormat, FeedWriter};
+//! use mangadex::web::WebResource;
+//! use mangadex::Client;
+//!
+//! # async fn run(client: &Client) -> anyhow::Result<()> {
+//! let manga = "32d76d19-8a05-4db0-9fc2-e0b0648fe9d0".parse()?;
+//! let chapters = GetMangaFeedBuilder::default()
+//!     .manga_id(manga)
+//!     .limit(50)
+//!     .offset(0)
+//!     .build()?
+//!     .send(client)
+//!     .await?
+//!     .results
+//!     .into_iter()
+//!     .collect::<mangadex::Result<Vec<_>>>()?;
+//!
+//! let mut writer = FeedWriter::new("Some manga", WebResource::Manga(manga).url())
+//!     .template("[Vol. {volume} ][Ch. {chapter} ]{title}".parse()?);
+//! writer.resolve_groups(client, &chapters).await?;
+//! println!("{}", writer.render(FeedFormat::Atom, &chapters));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::{self, Write};
+use std::str::FromStr;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use thiserror::Error;
+use url::Url;
+
+use crate::api::group::ListGroups;
+use crate::schema::chapter::Chapter;
+use crate::schema::group::ScanlationGroupList;
+use crate::schema::{ApiData, GroupId, ResourceType};
+use crate::web::WebResource;
+use crate::{Client, Result};
+
+/// The api accepts at most 100 group ids per request.
+const GROUPS_PER_REQUEST: usize = 100;
+
+/// A syndication format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedFormat {
+    /// Atom, [RFC 4287](https://tools.ietf.org/html/rfc4287).
+    Atom,
+    /// [RSS 2.0](https://www.rssboard.org/rss-specification).
+    Rss,
+}
+
+impl FeedFormat {
+    /// The media type of the documents.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Atom => "application/atom+xml",
+            Self::Rss => "application/rss+xml",
+        }
+    }
+}
+
+/// The format of the entry titles.
+///
+/// Placeholders are replaced by the fields of the chapter:
+///
+/// * `{title}`: the chapter title,
+/// * `{volume}`: the volume number,
+/// * `{chapter}`: the chapter number,
+/// * `{language}`: the name of the language,
+/// * `{groups}`: the names of the scanlation groups, separated by commas.
+///
+/// A part of the template in square brackets is left out when one of its placeholders is empty, so
+/// the default `"[Vol. {volume} ][Ch. {chapter} ][- {title}]"` gives `"Vol. 1 Ch. 3 - Title"`, or
+/// only `"Ch. 3"` for a chapter without volume nor title. Braces and brackets are escaped by
+/// doubling them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TitleTemplate {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Field(Field),
+    Optional(Vec<Part>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Title,
+    Volume,
+    Chapter,
+    Language,
+    Groups,
+}
+
+impl Default for TitleTemplate {
+    fn default() -> Self {
+        "[Vol. {volume} ][Ch. {chapter} ][- {title}]"
+            .parse()
+            .expect("the default template is valid")
+    }
+}
+
+impl FromStr for TitleTemplate {
+    type Err = TemplateError;
+
+    fn from_str(template: &str) -> Result<Self, Self::Err> {
+        let mut stack = vec![Vec::new()];
+        let mut text = String::new();
+        let mut chars = template.char_indices().peekable();
+
+        while let Some((offset, c)) = chars.next() {
+            match c {
+                '{' | '}' | '[' | ']' if chars.peek().map(|&(_, next)| next) == Some(c) => {
+                    chars.next();
+                    text.push(c);
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some((_, '}')) => break,
+                            Some((_, c)) => name.push(c),
+                            None => return Err(TemplateError::Unbalanced(offset)),
+                        }
+                    }
+                    let field = match name.as_str() {
+                        "title" => Field::Title,
+                        "volume" => Field::Volume,
+                        "chapter" => Field::Chapter,
+                        "language" => Field::Language,
+                        "groups" => Field::Groups,
+                        _ => return Err(TemplateError::UnknownField(name)),
+                    };
+                    let parts = stack.last_mut().unwrap();
+                    flush(parts, &mut text);
+                    parts.push(Part::Field(field));
+                }
+                '[' => {
+                    flush(stack.last_mut().unwrap(), &mut text);
+                    stack.push(Vec::new());
+                }
+                ']' if stack.len() > 1 => {
+                    let mut parts = stack.pop().unwrap();
+                    flush(&mut parts, &mut text);
+                    stack.last_mut().unwrap().push(Part::Optional(parts));
+                }
+                '}' | ']' => return Err(TemplateError::Unbalanced(offset)),
+                c => text.push(c),
+            }
+        }
+
+        if stack.len() > 1 {
+            return Err(TemplateError::Unbalanced(template.len()));
+        }
+        let mut parts = stack.pop().unwrap();
+        flush(&mut parts, &mut text);
+        Ok(Self { parts })
+    }
+}
+
+fn flush(parts: &mut Vec<Part>, text: &mut String) {
+    if !text.is_empty() {
+        parts.push(Part::Text(std::mem::take(text)));
+    }
+}
+
+impl TitleTemplate {
+    /// Format the title of a chapter.
+    pub fn format(&self, chapter: &Chapter, groups: &[&str]) -> String {
+        let mut title = String::new();
+        Self::write(&self.parts, chapter, groups, &mut title);
+        title.trim().to_string()
+    }
+
+    /// Write the parts, returning whether every field had a value.
+    fn write(parts: &[Part], chapter: &Chapter, groups: &[&str], out: &mut String) -> bool {
+        let mut complete = true;
+        for part in parts {
+            match part {
+                Part::Text(text) => out.push_str(text),
+                Part::Field(field) => {
+                    let value = field.value(chapter, groups);
+                    complete &= !value.is_empty();
+                    out.push_str(&value);
+                }
+                Part::Optional(parts) => {
+                    let mut optional = String::new();
+                    if Self::write(parts, chapter, groups, &mut optional) {
+                        out.push_str(&optional);
+                    }
+                }
+            }
+        }
+        complete
+    }
+}
+
+impl Field {
+    fn value(self, chapter: &Chapter, groups: &[&str]) -> String {
+        let attributes = &chapter.attributes;
+        match self {
+            Self::Title => attributes.title.clone(),
+            Self::Volume => attributes.volume.clone().unwrap_or_default(),
+            Self::Chapter => attributes.chapter.clone().unwrap_or_default(),
+            Self::Language => language_name(chapter).to_string(),
+            Self::Groups => groups.join(", "),
+        }
+    }
+}
+
+/// The error returned when parsing an invalid [`TitleTemplate`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum TemplateError {
+    #[error("unknown placeholder {{{0}}}")]
+    UnknownField(String),
+    #[error("unbalanced bracket at offset {0}")]
+    Unbalanced(usize),
+}
+
+/// Writes chapters as an Atom or RSS document.
+#[derive(Debug, Clone)]
+pub struct FeedWriter {
+    title: String,
+    link: Url,
+    template: TitleTemplate,
+    groups: HashMap<GroupId, String>,
+}
+
+impl FeedWriter {
+    /// Create a writer of feeds with the given title, about the given page.
+    pub fn new(title: impl Into<String>, link: Url) -> Self {
+        Self {
+            title: title.into(),
+            link,
+            template: TitleTemplate::default(),
+            groups: HashMap::new(),
+        }
+    }
+
+    /// Set the format of the entry titles.
+    pub fn template(mut self, template: TitleTemplate) -> Self {
+        self.template = template;
+        self
+    }
+
+    /// Add known group names.
+    pub fn group_names(mut self, names: impl IntoIterator<Item = (GroupId, String)>) -> Self {
+        self.groups.extend(names);
+        self
+    }
+
+    /// Fetch the names of the groups of the chapters that aren't known yet.
+    pub async fn resolve_groups(
+        &mut self,
+        client: &Client,
+        chapters: &[ApiData<Chapter>],
+    ) -> Result<()> {
+        for ids in self.unknown_groups(chapters).chunks(GROUPS_PER_REQUEST) {
+            let groups = ListGroups {
+                limit: Some(ids.len() as i32),
+                group_ids: ids.to_vec(),
+                ..ListGroups::default()
+            }
+            .send(client)
+            .await?;
+            self.add_groups(groups)?;
+        }
+        Ok(())
+    }
+
+    /// Fetch the names of the groups of the chapters that aren't known yet.
+    #[cfg(feature = "blocking")]
+    pub fn resolve_groups_blocking(
+        &mut self,
+        client: &crate::blocking::Client,
+        chapters: &[ApiData<Chapter>],
+    ) -> Result<()> {
+        for ids in self.unknown_groups(chapters).chunks(GROUPS_PER_REQUEST) {
+            let groups = ListGroups {
+                limit: Some(ids.len() as i32),
+                group_ids: ids.to_vec(),
+                ..ListGroups::default()
+            }
+            .send_blocking(client)?;
+            self.add_groups(groups)?;
+        }
+        Ok(())
+    }
+
+    fn unknown_groups(&self, chapters: &[ApiData<Chapter>]) -> Vec<GroupId> {
+        chapters
+            .iter()
+            .flat_map(group_ids)
+            .filter(|id| !self.groups.contains_key(id))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    fn add_groups(&mut self, groups: ScanlationGroupList) -> Result<()> {
+        for group in groups.results {
+            let group = group?.data;
+            self.groups.insert(group.id, group.attributes.name);
+        }
+        Ok(())
+    }
+
+    /// Write the chapters in the given format.
+    pub fn render(&self, format: FeedFormat, chapters: &[ApiData<Chapter>]) -> String {
+        match format {
+            FeedFormat::Atom => self.atom(chapters),
+            FeedFormat::Rss => self.rss(chapters),
+        }
+    }
+
+    /// Write the chapters as an Atom feed.
+    pub fn atom(&self, chapters: &[ApiData<Chapter>]) -> String {
+        let updated = chapters
+            .iter()
+            .map(|chapter| chapter.data.attributes.updated_at)
+            .max()
+            .unwrap_or_else(Utc::now);
+
+        let mut xml = String::new();
+        xml.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+        xml.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+        let _ = writeln!(xml, "  <id>{}</id>", Escape(self.link.as_str()));
+        let _ = writeln!(xml, "  <title>{}</title>", Escape(&self.title));
+        let _ = writeln!(xml, "  <updated>{}</updated>", atom_date(updated));
+        let _ = writeln!(
+            xml,
+            "  <link rel=\"alternate\" href=\"{}\"/>",
+            Escape(self.link.as_str())
+        );
+        xml.push_str("  <author><name>MangaDex</name></author>\n");
+
+        for chapter in chapters {
+            let entry = self.entry(chapter);
+            xml.push_str("  <entry>\n");
+            let _ = writeln!(xml, "    <id>urn:uuid:{}</id>", chapter.data.id);
+            let _ = writeln!(xml, "    <title>{}</title>", Escape(&entry.title));
+            let _ = writeln!(xml, "    <updated>{}</updated>", atom_date(entry.updated));
+            let _ = writeln!(
+                xml,
+                "    <published>{}</published>",
+                atom_date(entry.published)
+            );
+            let _ = writeln!(
+                xml,
+                "    <link rel=\"alternate\" href=\"{}\"/>",
+                Escape(entry.link.as_str())
+            );
+            let _ = writeln!(
+                xml,
+                "    <category term=\"{}\" label=\"{}\"/>",
+                Escape(entry.language_code),
+                Escape(entry.language)
+            );
+            let _ = writeln!(xml, "    <summary>{}</summary>", Escape(&entry.summary));
+            xml.push_str("  </entry>\n");
+        }
+
+        xml.push_str("</feed>\n");
+        xml
+    }
+
+    /// Write the chapters as an RSS 2.0 feed.
+    pub fn rss(&self, chapters: &[ApiData<Chapter>]) -> String {
+        let mut xml = String::new();
+        xml.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+        xml.push_str("<rss version=\"2.0\">\n");
+        xml.push_str("  <channel>\n");
+        let _ = writeln!(xml, "    <title>{}</title>", Escape(&self.title));
+        let _ = writeln!(xml, "    <link>{}</link>", Escape(self.link.as_str()));
+        let _ = writeln!(
+            xml,
+            "    <description>{}</description>",
+            Escape(&self.title)
+        );
+        if let Some(latest) = chapters
+            .iter()
+            .map(|chapter| chapter.data.attributes.publish_at)
+            .max()
+        {
+            let _ = writeln!(xml, "    <pubDate>{}</pubDate>", latest.to_rfc2822());
+        }
+
+        for chapter in chapters {
+            let entry = self.entry(chapter);
+            xml.push_str("    <item>\n");
+            let _ = writeln!(xml, "      <title>{}</title>", Escape(&entry.title));
+            let _ = writeln!(xml, "      <link>{}</link>", Escape(entry.link.as_str()));
+            let _ = writeln!(
+                xml,
+                "      <guid isPermaLink=\"false\">urn:uuid:{}</guid>",
+                chapter.data.id
+            );
+            let _ = writeln!(
+                xml,
+                "      <pubDate>{}</pubDate>",
+                entry.published.to_rfc2822()
+            );
+            let _ = writeln!(xml, "      <category>{}</category>", Escape(entry.language));
+            let _ = writeln!(
+                xml,
+                "      <description>{}</description>",
+                Escape(&entry.summary)
+            );
+            xml.push_str("    </item>\n");
+        }
+
+        xml.push_str("  </channel>\n");
+        xml.push_str("</rss>\n");
+        xml
+    }
+
+    fn entry<'a>(&'a self, chapter: &'a ApiData<Chapter>) -> Entry<'a> {
+        let groups: Vec<&str> = group_ids(chapter)
+            .filter_map(|id| self.groups.get(&id).map(String::as_str))
+            .collect();
+        let data = &chapter.data;
+        let attributes = &data.attributes;
+
+        let mut summary = Vec::new();
+        if let Some(volume) = &attributes.volume {
+            summary.push(format!("Volume {}", volume));
+        }
+        if let Some(number) = &attributes.chapter {
+            summary.push(format!("Chapter {}", number));
+        }
+        summary.push(format!("Language: {}", language_name(data)));
+        if !groups.is_empty() {
+            summary.push(format!("Groups: {}", groups.join(", ")));
+        }
+
+        Entry {
+            title: self.template.format(data, &groups),
+            link: WebResource::Chapter {
+                id: data.id,
+                page: None,
+            }
+            .url(),
+            language_code: attributes.translated_language.as_str(),
+            language: language_name(data),
+            summary: summary.join(", "),
+            published: attributes.publish_at,
+            updated: attributes.updated_at,
+        }
+    }
+}
+
+/// The fields of a chapter, as shown in feeds.
+struct Entry<'a> {
+    title: String,
+    link: Url,
+    language_code: &'a str,
+    language: &'a str,
+    summary: String,
+    published: DateTime<Utc>,
+    updated: DateTime<Utc>,
+}
+
+fn group_ids(chapter: &ApiData<Chapter>) -> impl Iterator<Item = GroupId> + '_ {
+    chapter
+        .relationships
+        .iter()
+        .filter(|relationship| relationship.r#type == ResourceType::ScanlationGroup)
+        .map(|relationship| relationship.id.into())
+}
+
+fn language_name(chapter: &Chapter) -> &str {
+    let language = &chapter.attributes.translated_language;
+    language.name().unwrap_or_else(|| language.as_str())
+}
+
+pub(crate) fn atom_date(date: DateTime<Utc>) -> String {
+    date.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Xml escaping of text and attribute values.
+pub(crate) struct Escape<'a>(pub(crate) &'a str);
+
+impl fmt::Display for Escape<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '&' => f.write_str("&amp;")?,
+                '<' => f.write_str("&lt;")?,
+                '>' => f.write_str("&gt;")?,
+                '"' => f.write_str("&quot;")?,
+                '\'' => f.write_str("&apos;")?,
+                // Control characters aren't allowed in xml 1.0.
+                c if c.is_control() && !matches!(c, '\t' | '\n' | '\r') => {}
+                c => f.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use httpmock::{Method::GET, MockServer};
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    const CHAPTER: &str = "c0ee660b-f9f2-45c3-8068-5123ff53f84a";
+    const GROUP: &str = "e11e461b-8c3a-4b5c-8b07-8892c2dcc449";
+    const MANGA: &str = "32d76d19-8a05-4db0-9fc2-e0b0648fe9d0";
+
+    fn chapter(title: &str, volume: Option<&str>, number: Option<&str>) -> ApiData<Chapter> {
+        serde_json::from_value(json!({
+            "data": {
+                "id": CHAPTER,
+                "type": "chapter",
+                "attributes": {
+                    "title": title,
+                    "volume": volume,
+                    "chapter": number,
+                    "translatedLanguage": "fr",
+                    "hash": "",
+                    "data": [],
+                    "dataSaver": [],
+                    "uploader": "0b3ad6e1-9c1f-4e2e-9f59-3e6f5d1d8a01",
+                    "version": 1,
+                    "createdAt": "2021-05-01T10:00:00Z",
+                    "updatedAt": "2021-05-02T10:00:00Z",
+                    "publishAt": "2021-05-01T10:00:00Z",
+                }
+            },
+            "relationships": [
+                { "id": MANGA, "type": "manga" },
+                { "id": GROUP, "type": "scanlation_group" },
+            ]
+        }))
+        .unwrap()
+    }
+
+    fn writer() -> FeedWriter {
+        FeedWriter::new(
+            "Tom & Jerry",
+            WebResource::Manga(MANGA.parse().unwrap()).url(),
+        )
+        .group_names(vec![(GROUP.parse().unwrap(), "<Scans>".to_string())])
+    }
+
+    #[test]
+    fn title_templates() {
+        let template = TitleTemplate::default();
+        let format =
+            |title, volume, number| template.format(&chapter(title, volume, number).data, &[]);
+        assert_eq!(
+            format("Start", Some("1"), Some("3")),
+            "Vol. 1 Ch. 3 - Start"
+        );
+        assert_eq!(format("", None, Some("3")), "Ch. 3");
+        assert_eq!(format("Oneshot", Some("2"), None), "Vol. 2 - Oneshot");
+
+        let template: TitleTemplate = "{{{chapter}}} [[{language}]][ by {groups}]"
+            .parse()
+            .unwrap();
+        let chapter = chapter("", None, Some("3"));
+        assert_eq!(template.format(&chapter.data, &[]), "{3} [French]");
+        assert_eq!(
+            template.format(&chapter.data, &["A", "B"]),
+            "{3} [French] by A, B"
+        );
+
+        assert_eq!(
+            "{name}".parse::<TitleTemplate>(),
+            Err(TemplateError::UnknownField("name".to_string()))
+        );
+        assert_eq!(
+            "[Ch. {chapter}".parse::<TitleTemplate>(),
+            Err(TemplateError::Unbalanced(14))
+        );
+        assert_eq!(
+            "Ch. {chapter".parse::<TitleTemplate>(),
+            Err(TemplateError::Unbalanced(4))
+        );
+    }
+
+    #[test]
+    fn atom_feed() {
+        let atom = writer().atom(&[chapter("Start", Some("1"), Some("3"))]);
+        assert_eq!(
+            atom,
+            format!(
+                r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <id>https://mangadex.org/title/{manga}</id>
+  <title>Tom &amp; Jerry</title>
+  <updated>2021-05-02T10:00:00Z</updated>
+  <link rel="alternate" href="https://mangadex.org/title/{manga}"/>
+  <author><name>MangaDex</name></author>
+  <entry>
+    <id>urn:uuid:{chapter}</id>
+    <title>Vol. 1 Ch. 3 - Start</title>
+    <updated>2021-05-02T10:00:00Z</updated>
+    <published>2021-05-01T10:00:00Z</published>
+    <link rel="alternate" href="https://mangadex.org/chapter/{chapter}"/>
+    <category term="fr" label="French"/>
+    <summary>Volume 1, Chapter 3, Language: French, Groups: &lt;Scans&gt;</summary>
+  </entry>
+</feed>
+"#,
+                manga = MANGA,
+                chapter = CHAPTER
+            )
+        );
+    }
+
+    #[test]
+    fn rss_feed() {
+        let rss = writer().render(FeedFormat::Rss, &[chapter("", None, Some("3"))]);
+        assert_eq!(
+            rss,
+            format!(
+                r#"<?xml version="1.0" encoding="utf-8"?>
+<rss version="2.0">
+  <channel>
+    <title>Tom &amp; Jerry</title>
+    <link>https://mangadex.org/title/{manga}</link>
+    <description>Tom &amp; Jerry</description>
+    <pubDate>Sat, 1 May 2021 10:00:00 +0000</pubDate>
+    <item>
+      <title>Ch. 3</title>
+      <link>https://mangadex.org/chapter/{chapter}</link>
+      <guid isPermaLink="false">urn:uuid:{chapter}</guid>
+      <pubDate>Sat, 1 May 2021 10:00:00 +0000</pubDate>
+      <category>French</category>
+      <description>Chapter 3, Language: French, Groups: &lt;Scans&gt;</description>
+    </item>
+  </channel>
+</rss>
+"#,
+                manga = MANGA,
+                chapter = CHAPTER
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn resolve_group_names() -> anyhow::Result<()> {
+        let server = MockServer::start_async().await;
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(GET).path("/group").query_param("ids[0]", GROUP);
+                then.header("Content-Type", "application/json")
+                    .json_body(json!({
+                        "results": [{
+                            "result": "ok",
+                            "data": {
+                                "id": GROUP,
+                                "type": "scanlation_group",
+                                "attributes": {
+                                    "name": "Some Scans",
+                                    "leader": {
+                                        "id": "0b3ad6e1-9c1f-4e2e-9f59-3e6f5d1d8a01",
+                                        "type": "user",
+                                        "attributes": { "username": "leader", "version": 1 }
+                                    },
+                                    "website": null,
+                                    "ircServer": null,
+                                    "ircChannel": null,
+                                    "discord": null,
+                                    "contactEmail": null,
+                                    "description": null,
+                                    "locked": false,
+                                    "version": 1,
+                                    "createdAt": "2021-05-01T10:00:00Z",
+                                    "updatedAt": "2021-05-01T10:00:00Z",
+                                }
+                            },
+                            "relationships": []
+                        }],
+                        "limit": 1,
+                        "offset": 0,
+                        "total": 1
+                    }));
+            })
+            .await;
+
+        let client = Client::new(&server.base_url())?;
+        let chapters = [chapter("", None, Some("3"))];
+        let mut writer = FeedWriter::new("Feed", WebResource::Manga(MANGA.parse()?).url())
+            .template("{groups}".parse()?);
+        writer.resolve_groups(&client, &chapters).await?;
+        assert!(writer.atom(&chapters).contains("<title>Some Scans</title>"));
+
+        // Known groups aren't fetched again.
+        writer.resolve_groups(&client, &chapters).await?;
+        mock.assert_hits_async(1).await;
+        Ok(())
+    }
+}
@@ -0,0 +1,194 @@
+//! Lookup of tags by name.
+//!
+//! Requests such as [`ListManga`][crate::api::manga::ListManga] take tag ids, while people think
+//! in tag names. A [`TagCatalog`] holds every tag of the api, and finds them by any of their
+//! localized names or by user defined aliases.
+//!
+//! ```rust,no_run
+//! use mangadex::api::manga::ListMangaBuilder;
+//! use mangadex::Client;
+//!
+//! # async fn run(client: &Client) -> anyhow::Result<()> {
+//! let catalog = client.tag_catalog().await?;
+//! let search = ListMangaBuilder::default()
+//!     .include_tag_named(&catalog, "Romance")?
+//!     .exclude_tag_named(&catalog, "Sci-Fi")?
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use thiserror::Error;
+
+use crate::api::manga::ListTags;
+use crate::schema::manga::{Tag, TagGroup};
+use crate::schema::TagId;
+use crate::{Client, Result};
+
+/// Every tag of the api, by id and by name.
+#[derive(Debug, Clone, Default)]
+pub struct TagCatalog {
+    tags: BTreeMap<TagId, Tag>,
+    names: HashMap<String, TagId>,
+}
+
+impl TagCatalog {
+    /// Create a catalog of the given tags.
+    pub fn new(tags: impl IntoIterator<Item = Tag>) -> Self {
+        let mut catalog = Self::default();
+        for tag in tags {
+            for name in tag.attributes.name.values() {
+                catalog.names.insert(normalize(name), tag.id);
+            }
+            catalog.tags.insert(tag.id, tag);
+        }
+        catalog
+    }
+
+    /// Fetch the tags from the api.
+    ///
+    /// [`Client::tag_catalog()`] fetches them only once per client.
+    pub async fn load(client: &Client) -> Result<Self> {
+        let tags = ListTags.send(client).await?;
+        Self::from_responses(tags)
+    }
+
+    /// Fetch the tags from the api.
+    #[cfg(feature = "blocking")]
+    pub fn load_blocking(client: &crate::blocking::Client) -> Result<Self> {
+        let tags = ListTags.send_blocking(client)?;
+        Self::from_responses(tags)
+    }
+
+    fn from_responses(tags: crate::schema::manga::TagList) -> Result<Self> {
+        let tags = tags
+            .into_iter()
+            .map(|res| res.map(|res| res.data))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self::new(tags))
+    }
+
+    /// Add an alternative name for a tag, e.g. `"isekai"` for `"Isekai"` or `"BL"` for
+    /// `"Boys' Love"`.
+    pub fn add_alias(&mut self, alias: &str, id: TagId) {
+        self.names.insert(normalize(alias), id);
+    }
+
+    /// Get a tag by id.
+    pub fn get(&self, id: &TagId) -> Option<&Tag> {
+        self.tags.get(id)
+    }
+
+    /// Find a tag by any of its localized names or aliases.
+    ///
+    /// The lookup ignores case, spaces and punctuation, so `"sci fi"` finds `"Sci-Fi"`.
+    pub fn find(&self, name: &str) -> Option<&Tag> {
+        self.names
+            .get(&normalize(name))
+            .and_then(|id| self.tags.get(id))
+    }
+
+    /// Find the id of a tag by any of its localized names or aliases.
+    pub fn id(&self, name: &str) -> Option<TagId> {
+        self.find(name).map(|tag| tag.id)
+    }
+
+    /// Find the id of a tag, failing with an error naming the tag if it doesn't exist.
+    pub fn require(&self, name: &str) -> Result<TagId, UnknownTagError> {
+        self.id(name)
+            .ok_or_else(|| UnknownTagError(name.to_string()))
+    }
+
+    /// Get the tags of a group.
+    pub fn group<'a>(&'a self, group: &'a TagGroup) -> impl Iterator<Item = &'a Tag> + 'a {
+        self.iter()
+            .filter(move |tag| &tag.attributes.group == group)
+    }
+
+    /// Get every tag.
+    pub fn iter(&self) -> impl Iterator<Item = &Tag> {
+        self.tags.values()
+    }
+
+    /// Number of tags.
+    pub fn len(&self) -> usize {
+        self.tags.len()
+    }
+
+    /// Whether the catalog is empty.
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+}
+
+/// The error returned when looking up a tag that isn't in a [`TagCatalog`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("unknown tag: {0:?}")]
+pub struct UnknownTagError(pub String);
+
+/// Fold a name for lookups: lowercase, without spaces nor punctuation.
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::api::manga::ListMangaBuilder;
+    use crate::cassette::test_client;
+
+    use super::*;
+
+    const ROMANCE: &str = "423e2eae-a7a2-4a8b-ac03-a8351462d71d";
+
+    #[tokio::test]
+    async fn find_tags_by_name() -> anyhow::Result<()> {
+        let client = test_client("manga/tag_list");
+        let mut catalog = TagCatalog::load(&client).await?;
+
+        assert_eq!(catalog.len(), 7);
+        assert_eq!(catalog.id("Romance"), Some(ROMANCE.parse()?));
+        assert_eq!(catalog.id(" ROMANCE! "), Some(ROMANCE.parse()?));
+        assert_eq!(catalog.id("Isekai"), None);
+
+        catalog.add_alias("love story", ROMANCE.parse()?);
+        assert_eq!(
+            catalog
+                .find("Love-Story")
+                .and_then(|tag| tag.attributes.name.any()),
+            Some("Romance")
+        );
+
+        assert_eq!(catalog.group(&TagGroup::Genre).count(), 5);
+        assert_eq!(catalog.group(&TagGroup::Format).count(), 2);
+        assert_eq!(catalog.group(&TagGroup::Theme).count(), 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn build_search_with_tag_names() -> anyhow::Result<()> {
+        let client = test_client("manga/tag_list");
+        let catalog = client.tag_catalog().await?;
+
+        let search = ListMangaBuilder::default()
+            .include_tag_named(&catalog, "romance")?
+            .build()?;
+        assert_eq!(search.included_tags, vec![ROMANCE.parse()?]);
+
+        let err = ListMangaBuilder::default()
+            .exclude_tag_named(&catalog, "Isekai")
+            .err();
+        assert_eq!(err, Some(UnknownTagError("Isekai".to_string())));
+
+        // The catalog is fetched once per client.
+        let again = client.tag_catalog().await?;
+        assert!(std::sync::Arc::ptr_eq(&catalog, &again));
+        Ok(())
+    }
+}
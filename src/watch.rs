@@ -0,0 +1,542 @@
+//! Watching feeds for new chapters.
+//!
+//! A [`ChapterWatcher`] polls a chapter feed and yields the chapters it hasn't seen yet. It keeps a
+//! [`WatchCursor`]: the time up to which the feed has been read, and the chapters seen shortly
+//! before it. Every poll asks for the chapters since the cursor minus the allowed clock skew, so
+//! chapters indexed late by the api aren't missed, and drops the ones already seen. With a cursor
+//! file, a restarted watcher picks up where it stopped.
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//!
+//! use futures_util::StreamExt;
+//! use mangadex::watch::{ChapterWatcher, FeedSource};
+//! use mangadex::Client;
+//!
+//! # async fn run(client: Client) -> mangadex::Result<()> {
+//! let watcher = ChapterWatcher::new(client, FeedSource::Followed)
+//!     .interval(Duration::from_secs(300))
+//!     .cursor_file("followed.cursor.json")?;
+//!
+//! let chapters = watcher.into_stream();
+//! futures_util::pin_mut!(chapters);
+//! while let Some(chapter) = chapters.next().await {
+//!     let chapter = chapter?;
+//!     println!("new chapter {}", chapter.id);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{DateTime, SubsecRound, Utc};
+use futures_core::Stream;
+use serde::{Deserialize, Serialize};
+
+use crate::api::feed::{CustomListMangaFeed, FollowedMangaFeed, GetMangaFeed};
+use crate::schema::chapter::{Chapter, ChapterList};
+use crate::schema::feed::FeedOrder;
+use crate::schema::{ChapterId, LanguageCode, ListId, MangaId, OrderType};
+use crate::{Client, Result};
+
+/// The default time between two polls.
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(300);
+
+/// The default allowed difference between the clocks of the api and of the watcher.
+pub const DEFAULT_CLOCK_SKEW: Duration = Duration::from_secs(300);
+
+/// The feed to watch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FeedSource {
+    /// The feed of the manga followed by the logged user (requires authentication).
+    Followed,
+    /// The feeds of the given manga.
+    Manga(Vec<MangaId>),
+    /// The feed of a custom list.
+    List(ListId),
+}
+
+/// The chapter timestamp the watcher follows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchField {
+    /// New chapters, by `publishAt`.
+    PublishAt,
+    /// New and edited chapters, by `updatedAt`.
+    ///
+    /// A chapter edited again after it left the skew window is yielded again.
+    UpdatedAt,
+}
+
+impl WatchField {
+    fn of(self, chapter: &Chapter) -> DateTime<Utc> {
+        match self {
+            Self::PublishAt => chapter.attributes.publish_at,
+            Self::UpdatedAt => chapter.attributes.updated_at,
+        }
+    }
+}
+
+/// How far a watcher has read a feed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchCursor {
+    /// The latest chapter time seen, `None` before the first poll.
+    pub since: Option<DateTime<Utc>>,
+    /// The chapters seen within the skew window before `since`, with their time.
+    pub seen: BTreeMap<ChapterId, DateTime<Utc>>,
+}
+
+impl WatchCursor {
+    /// Read a cursor from a json file, or start a new one if the file doesn't exist.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        match fs::read(path) {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Write the cursor to a json file.
+    ///
+    /// The file is replaced at once, so a crash never leaves a partial cursor behind.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        fs::write(&tmp, serde_json::to_vec(self)?)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+/// Polls a chapter feed for new chapters.
+#[derive(Debug, Clone)]
+pub struct ChapterWatcher {
+    client: Client,
+    source: FeedSource,
+    field: WatchField,
+    languages: Vec<LanguageCode>,
+    interval: Duration,
+    clock_skew: chrono::Duration,
+    page_size: i32,
+    cursor: WatchCursor,
+    cursor_file: Option<PathBuf>,
+}
+
+impl ChapterWatcher {
+    /// Create a watcher of a feed, starting from now.
+    pub fn new(client: Client, source: FeedSource) -> Self {
+        Self {
+            client,
+            source,
+            field: WatchField::PublishAt,
+            languages: Vec::new(),
+            interval: DEFAULT_INTERVAL,
+            clock_skew: chrono::Duration::from_std(DEFAULT_CLOCK_SKEW).unwrap(),
+            page_size: 100,
+            cursor: WatchCursor::default(),
+            cursor_file: None,
+        }
+    }
+
+    /// Follow another chapter timestamp, [`WatchField::PublishAt`] by default.
+    pub fn field(mut self, field: WatchField) -> Self {
+        self.field = field;
+        self
+    }
+
+    /// Only watch chapters in the given languages.
+    pub fn languages(mut self, languages: impl IntoIterator<Item = LanguageCode>) -> Self {
+        self.languages = languages.into_iter().collect();
+        self
+    }
+
+    /// Set the time between two polls of [`into_stream`][Self::into_stream].
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Set how far before the cursor each poll looks again.
+    pub fn clock_skew(mut self, skew: Duration) -> Self {
+        self.clock_skew = chrono::Duration::from_std(skew).unwrap_or(chrono::Duration::MAX);
+        self
+    }
+
+    /// Set the number of chapters per request, at most 500.
+    pub fn page_size(mut self, page_size: i32) -> Self {
+        self.page_size = page_size.clamp(1, 500);
+        self
+    }
+
+    /// Start from a known cursor.
+    pub fn cursor(mut self, cursor: WatchCursor) -> Self {
+        self.cursor = cursor;
+        self
+    }
+
+    /// Keep the cursor in a json file: read it now, and write it after every poll.
+    pub fn cursor_file(mut self, path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        self.cursor = WatchCursor::load(&path)?;
+        self.cursor_file = Some(path);
+        Ok(self)
+    }
+
+    /// The current cursor.
+    pub fn current_cursor(&self) -> &WatchCursor {
+        &self.cursor
+    }
+
+    /// Read the feed once, and return the new chapters, oldest first.
+    ///
+    /// The first poll of a new cursor only marks the current chapters as seen, and returns nothing.
+    /// Chapters scheduled in the future are returned by the first poll after their release.
+    pub async fn poll(&mut self) -> Result<Vec<Chapter>> {
+        let now = Utc::now().trunc_subsecs(0);
+        let first = self.cursor.since.is_none();
+        let since = self.cursor.since.unwrap_or(now);
+        let from = self.skewed(since);
+
+        let chapters = self.fetch(from).await?;
+
+        let mut new = Vec::new();
+        let mut latest = since;
+        for chapter in chapters {
+            let time = self.field.of(&chapter);
+            // A chapter scheduled in the future is left to the first poll after its release.
+            if time > now {
+                continue;
+            }
+            latest = latest.max(time);
+            if self.cursor.seen.insert(chapter.id, time).is_none() && !first {
+                new.push(chapter);
+            }
+        }
+
+        let horizon = self.skewed(latest);
+        self.cursor.seen.retain(|_, time| *time >= horizon);
+        self.cursor.since = Some(latest);
+        if let Some(path) = &self.cursor_file {
+            self.cursor.save(path)?;
+        }
+
+        new.sort_by_key(|chapter| self.field.of(chapter));
+        Ok(new)
+    }
+
+    /// `time` minus the clock skew, no earlier than the UNIX epoch.
+    fn skewed(&self, time: DateTime<Utc>) -> DateTime<Utc> {
+        time.checked_sub_signed(self.clock_skew)
+            .unwrap_or(DateTime::UNIX_EPOCH)
+            .max(DateTime::UNIX_EPOCH)
+    }
+
+    /// Poll the feed forever, yielding every new chapter.
+    ///
+    /// Errors are yielded as well, and polling goes on at the next interval.
+    pub fn into_stream(self) -> impl Stream<Item = Result<Chapter>> {
+        struct State {
+            watcher: ChapterWatcher,
+            pending: VecDeque<Chapter>,
+            polled: bool,
+        }
+
+        let state = State {
+            watcher: self,
+            pending: VecDeque::new(),
+            polled: false,
+        };
+        futures_util::stream::unfold(state, |mut state| async move {
+            loop {
+                if let Some(chapter) = state.pending.pop_front() {
+                    return Some((Ok(chapter), state));
+                }
+                if state.polled {
+                    tokio::time::sleep(state.watcher.interval).await;
+                }
+                state.polled = true;
+                match state.watcher.poll().await {
+                    Ok(chapters) => state.pending.extend(chapters),
+                    Err(err) => return Some((Err(err), state)),
+                }
+            }
+        })
+    }
+
+    /// The request for a page of the feed since `from`, oldest first: chapters indexed while the
+    /// pages are read land on the last page instead of shifting the ones not read yet.
+    fn feed_query<Q: FeedQuery>(&self, from: DateTime<Utc>, limit: i32, offset: i32) -> Q {
+        let (publish_at_since, updated_at_since, order) = match self.field {
+            WatchField::PublishAt => (Some(from), None, FeedOrder::PublishAt(OrderType::Ascending)),
+            WatchField::UpdatedAt => (None, Some(from), FeedOrder::UpdatedAt(OrderType::Ascending)),
+        };
+        Q::new(
+            limit,
+            offset,
+            self.languages.clone(),
+            publish_at_since,
+            updated_at_since,
+            order,
+        )
+    }
+
+    /// Read every chapter of the feed since the given time.
+    async fn fetch(&self, from: DateTime<Utc>) -> Result<Vec<Chapter>> {
+        let mut chapters = Vec::new();
+        match &self.source {
+            FeedSource::Followed => {
+                self.fetch_pages(&mut chapters, |limit, offset| async move {
+                    self.feed_query::<FollowedMangaFeed>(from, limit, offset)
+                        .send(&self.client)
+                        .await
+                })
+                .await?
+            }
+            FeedSource::Manga(ids) => {
+                for &manga_id in ids {
+                    self.fetch_pages(&mut chapters, |limit, offset| async move {
+                        GetMangaFeed {
+                            manga_id,
+                            ..self.feed_query(from, limit, offset)
+                        }
+                        .send(&self.client)
+                        .await
+                    })
+                    .await?
+                }
+            }
+            &FeedSource::List(list_id) => {
+                self.fetch_pages(&mut chapters, |limit, offset| async move {
+                    CustomListMangaFeed {
+                        list_id,
+                        ..self.feed_query(from, limit, offset)
+                    }
+                    .send(&self.client)
+                    .await
+                })
+                .await?
+            }
+        }
+        Ok(chapters)
+    }
+
+    /// Read every page of a feed.
+    async fn fetch_pages<'a, F, Fut>(&'a self, chapters: &mut Vec<Chapter>, fetch: F) -> Result<()>
+    where
+        F: Fn(i32, i32) -> Fut,
+        Fut: std::future::Future<Output = Result<ChapterList>> + 'a,
+    {
+        let mut offset = 0;
+        loop {
+            let page = fetch(self.page_size, offset).await?;
+            let count = page.results.len() as i32;
+            for chapter in page.results {
+                chapters.push(chapter?.data);
+            }
+            offset += count;
+            if count == 0 || offset >= page.total {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// The common fields of the feed requests.
+trait FeedQuery {
+    fn new(
+        limit: i32,
+        offset: i32,
+        translated_language: Vec<LanguageCode>,
+        publish_at_since: Option<DateTime<Utc>>,
+        updated_at_since: Option<DateTime<Utc>>,
+        order: FeedOrder,
+    ) -> Self;
+}
+
+macro_rules! feed_query {
+    ($($query:ident { $($id:ident: $default:expr),* }),*) => {$(
+        impl FeedQuery for $query {
+            fn new(
+                limit: i32,
+                offset: i32,
+                translated_language: Vec<LanguageCode>,
+                publish_at_since: Option<DateTime<Utc>>,
+                updated_at_since: Option<DateTime<Utc>>,
+                order: FeedOrder,
+            ) -> Self {
+                Self {
+                    $($id: $default,)*
+                    limit: Some(limit),
+                    offset: Some(offset),
+                    translated_language,
+                    created_at_since: None,
+                    updated_at_since,
+                    publish_at_since,
+                    order: Some(order),
+                }
+            }
+        }
+    )*};
+}
+
+feed_query! {
+    FollowedMangaFeed {},
+    GetMangaFeed { manga_id: MangaId::default() },
+    CustomListMangaFeed { list_id: ListId::default() }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::StreamExt;
+    use httpmock::{Method::GET, MockServer};
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    const MANGA: &str = "32d76d19-8a05-4db0-9fc2-e0b0648fe9d0";
+    const OLD: &str = "c0ee660b-f9f2-45c3-8068-5123ff53f84a";
+    const NEW: &str = "24b6d026-a7cb-498e-8717-26b2831cf318";
+
+    fn chapter(id: &str, publish_at: DateTime<Utc>) -> serde_json::Value {
+        json!({
+            "result": "ok",
+            "data": {
+                "id": id,
+                "type": "chapter",
+                "attributes": {
+                    "title": "",
+                    "volume": null,
+                    "chapter": "1",
+                    "translatedLanguage": "en",
+                    "hash": "",
+                    "data": [],
+                    "dataSaver": [],
+                    "uploader": "0b3ad6e1-9c1f-4e2e-9f59-3e6f5d1d8a01",
+                    "version": 1,
+                    "createdAt": publish_at,
+                    "updatedAt": publish_at,
+                    "publishAt": publish_at,
+                }
+            },
+            "relationships": []
+        })
+    }
+
+    fn page(chapters: Vec<serde_json::Value>, offset: usize, total: usize) -> serde_json::Value {
+        json!({ "results": chapters, "limit": 1, "offset": offset, "total": total })
+    }
+
+    #[tokio::test]
+    async fn yield_new_chapters_once() -> anyhow::Result<()> {
+        let server = MockServer::start_async().await;
+        let path = format!("/manga/{}/feed", MANGA);
+        let now = Utc::now().trunc_subsecs(0);
+        let old = chapter(OLD, now - chrono::Duration::seconds(60));
+        let new = chapter(NEW, now);
+
+        let first = server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path(&path)
+                    .query_param_exists("publishAtSince")
+                    .query_param("order[publishAt]", "asc");
+                then.header("Content-Type", "application/json")
+                    .json_body(page(vec![old.clone()], 0, 1));
+            })
+            .await;
+
+        let cursor_file = std::env::temp_dir().join(format!("mangadex-watch-{}.json", NEW));
+        let _ = fs::remove_file(&cursor_file);
+        let client = Client::new(&server.base_url())?;
+        let mut watcher =
+            ChapterWatcher::new(client.clone(), FeedSource::Manga(vec![MANGA.parse()?]))
+                .page_size(1)
+                .cursor_file(&cursor_file)?;
+
+        // The chapters already in the feed when the watcher starts aren't new.
+        assert_eq!(watcher.poll().await?.len(), 0);
+        first.assert_async().await;
+        first.delete_async().await;
+
+        // A page of chapters already seen, then a new one.
+        server
+            .mock_async(|when, then| {
+                when.method(GET).path(&path).query_param("offset", "0");
+                then.header("Content-Type", "application/json")
+                    .json_body(page(vec![old.clone()], 0, 2));
+            })
+            .await;
+        server
+            .mock_async(|when, then| {
+                when.method(GET).path(&path).query_param("offset", "1");
+                then.header("Content-Type", "application/json")
+                    .json_body(page(vec![new.clone()], 1, 2));
+            })
+            .await;
+
+        // A restarted watcher picks up the saved cursor.
+        let watcher = ChapterWatcher::new(client, FeedSource::Manga(vec![MANGA.parse()?]))
+            .page_size(1)
+            .interval(Duration::from_millis(10))
+            .cursor_file(&cursor_file)?;
+        assert_eq!(watcher.current_cursor().seen.len(), 1);
+
+        let chapters = watcher.into_stream();
+        futures_util::pin_mut!(chapters);
+        let chapter = chapters.next().await.unwrap()?;
+        assert_eq!(chapter.id, NEW.parse()?);
+
+        let cursor = WatchCursor::load(&cursor_file)?;
+        assert!(cursor.since >= Some(now));
+        assert_eq!(cursor.seen.len(), 2);
+
+        // Nothing else is new.
+        let polled = tokio::time::timeout(Duration::from_millis(100), chapters.next()).await;
+        assert!(polled.is_err());
+
+        fs::remove_file(&cursor_file)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn hold_back_scheduled_chapters() -> anyhow::Result<()> {
+        let server = MockServer::start_async().await;
+        let now = Utc::now().trunc_subsecs(0);
+        let release = now + chrono::Duration::seconds(1);
+        let feed = server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path(format!("/manga/{}/feed", MANGA))
+                    .query_param("publishAtSince", "1970-01-01T00:00:00Z");
+                then.header("Content-Type", "application/json")
+                    .json_body(page(vec![chapter(NEW, release)], 0, 1));
+            })
+            .await;
+
+        // A huge clock skew looks back to the UNIX epoch, instead of overflowing.
+        let client = Client::new(&server.base_url())?;
+        let mut watcher = ChapterWatcher::new(client, FeedSource::Manga(vec![MANGA.parse()?]))
+            .clock_skew(Duration::MAX)
+            .cursor(WatchCursor {
+                since: Some(now),
+                seen: BTreeMap::new(),
+            });
+
+        // The chapter is only new once it is released.
+        assert_eq!(watcher.poll().await?.len(), 0);
+        assert_eq!(watcher.current_cursor().since, Some(now));
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        let chapters = watcher.poll().await?;
+        assert_eq!(chapters.len(), 1);
+        assert_eq!(chapters[0].id, NEW.parse()?);
+        assert_eq!(watcher.current_cursor().since, Some(release));
+        feed.assert_hits_async(2).await;
+        Ok(())
+    }
+}
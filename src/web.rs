@@ -0,0 +1,365 @@
+//! Links to the MangaDex website
+//!
+//! [`WebLink`] parses the links users paste, such as `https://mangadex.org/title/<id>/<slug>` or
+//! `https://mangadex.org/chapter/<id>/3`, into a typed [`WebResource`]. Links of the old website
+//! use numeric ids (`https://mangadex.org/title/123`), which are resolved with
+//! [`LegacyMapping`].
+//!
+//! ```rust,no_run
+//! use mangadex::web::{WebLink, WebResource};
+//! use mangadex::Client;
+//!
+//! # async fn run(client: &Client) -> mangadex::Result<()> {
+//! let link: WebLink = "https://mangadex.org/title/123/some-manga".parse().unwrap();
+//! if let Some(WebResource::Manga(id)) = link.resolve(client).await? {
+//!     println!("https://mangadex.org/title/123 is now {}", WebResource::Manga(id).url());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! In the other direction, [`ApiObject::web_url()`] gives the link to the page of an object.
+
+use std::fmt;
+use std::str::FromStr;
+
+use thiserror::Error;
+use url::Url;
+use uuid::Uuid;
+
+use crate::api::legacy::LegacyMapping;
+use crate::schema::legacy::{MappingIdResponse, MappingType};
+use crate::schema::{
+    ApiObject, AuthorId, ChapterId, GroupId, ListId, MangaId, ObjectType, ResourceType, TagId,
+    UserId,
+};
+use crate::{Client, Result};
+
+/// The base url of the website.
+pub const WEB_URL: &str = "https://mangadex.org";
+
+/// A resource with a page on the website.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WebResource {
+    Manga(MangaId),
+    /// A chapter, opened at a page (starting at 1).
+    Chapter {
+        id: ChapterId,
+        page: Option<u32>,
+    },
+    Author(AuthorId),
+    Group(GroupId),
+    User(UserId),
+    List(ListId),
+    Tag(TagId),
+}
+
+impl WebResource {
+    /// Get the resource of the given kind, if it has a page on the website.
+    pub fn new(kind: &ResourceType, id: Uuid) -> Option<Self> {
+        let resource = match kind {
+            ResourceType::Manga => Self::Manga(id.into()),
+            ResourceType::Chapter => Self::Chapter {
+                id: id.into(),
+                page: None,
+            },
+            ResourceType::Author | ResourceType::Artist => Self::Author(id.into()),
+            ResourceType::ScanlationGroup => Self::Group(id.into()),
+            ResourceType::User => Self::User(id.into()),
+            ResourceType::CustomList => Self::List(id.into()),
+            ResourceType::Tag => Self::Tag(id.into()),
+            _ => return None,
+        };
+        Some(resource)
+    }
+
+    /// Get the canonical link to the page of the resource.
+    pub fn url(&self) -> Url {
+        let path = match self {
+            Self::Manga(id) => format!("title/{}", id),
+            Self::Chapter { id, page: None } => format!("chapter/{}", id),
+            Self::Chapter {
+                id,
+                page: Some(page),
+            } => format!("chapter/{}/{}", id, page),
+            Self::Author(id) => format!("author/{}", id),
+            Self::Group(id) => format!("group/{}", id),
+            Self::User(id) => format!("user/{}", id),
+            Self::List(id) => format!("list/{}", id),
+            Self::Tag(id) => format!("tag/{}", id),
+        };
+        Url::parse(&format!("{}/{}", WEB_URL, path)).expect("web urls are valid")
+    }
+}
+
+impl fmt::Display for WebResource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.url(), f)
+    }
+}
+
+/// A parsed link to the website.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum WebLink {
+    /// A link to a resource.
+    Resource(WebResource),
+    /// A link of the old website, with a numeric id.
+    Legacy {
+        r#type: MappingType,
+        id: u32,
+        /// The page, for chapters.
+        page: Option<u32>,
+    },
+}
+
+impl WebLink {
+    /// Get the linked resource, looking up legacy ids with the api.
+    ///
+    /// Returns `None` for legacy ids the api has no mapping for.
+    pub async fn resolve(&self, client: &Client) -> Result<Option<WebResource>> {
+        match self {
+            Self::Resource(resource) => Ok(Some(*resource)),
+            Self::Legacy { r#type, id, page } => {
+                let mappings = Self::legacy_mapping(r#type, *id).send(client).await?;
+                Self::mapped(mappings, *page)
+            }
+        }
+    }
+
+    /// Get the linked resource, looking up legacy ids with the api.
+    ///
+    /// Returns `None` for legacy ids the api has no mapping for.
+    #[cfg(feature = "blocking")]
+    pub fn resolve_blocking(
+        &self,
+        client: &crate::blocking::Client,
+    ) -> Result<Option<WebResource>> {
+        match self {
+            Self::Resource(resource) => Ok(Some(*resource)),
+            Self::Legacy { r#type, id, page } => {
+                let mappings = Self::legacy_mapping(r#type, *id).send_blocking(client)?;
+                Self::mapped(mappings, *page)
+            }
+        }
+    }
+
+    fn legacy_mapping(r#type: &MappingType, id: u32) -> LegacyMapping {
+        LegacyMapping {
+            r#type: r#type.clone(),
+            ids: vec![id],
+        }
+    }
+
+    fn mapped(mappings: Vec<MappingIdResponse>, page: Option<u32>) -> Result<Option<WebResource>> {
+        let mapping = match mappings.into_iter().next() {
+            Some(mapping) => mapping?.data.attributes,
+            None => return Ok(None),
+        };
+        let kind = match mapping.r#type {
+            MappingType::Manga => ResourceType::Manga,
+            MappingType::Chapter => ResourceType::Chapter,
+            MappingType::Group => ResourceType::ScanlationGroup,
+            MappingType::Tag => ResourceType::Tag,
+            MappingType::Unknown(_) => return Ok(None),
+        };
+        Ok(
+            WebResource::new(&kind, mapping.new_id).map(|resource| match resource {
+                WebResource::Chapter { id, .. } => WebResource::Chapter { id, page },
+                resource => resource,
+            }),
+        )
+    }
+}
+
+impl FromStr for WebLink {
+    type Err = ParseLinkError;
+
+    /// Parse a link to the website. The scheme may be omitted, e.g. `mangadex.org/title/<id>`.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.trim();
+        let url = match Url::parse(s) {
+            Err(url::ParseError::RelativeUrlWithoutBase) => Url::parse(&format!("https://{}", s)),
+            url => url,
+        }?;
+
+        let host = url.host_str().unwrap_or_default();
+        if host != "mangadex.org" && !host.ends_with(".mangadex.org") {
+            return Err(ParseLinkError::NotMangaDex(host.to_string()));
+        }
+
+        let unknown = || ParseLinkError::UnknownPath(url.path().to_string());
+        let mut segments = url.path_segments().ok_or_else(unknown)?;
+        let (kind, id) = match (segments.next(), segments.next()) {
+            (Some(kind), Some(id)) => (kind, id),
+            _ => return Err(unknown()),
+        };
+        let page = match kind {
+            "chapter" => segments.next().and_then(|page| page.parse().ok()),
+            _ => None,
+        };
+
+        if let Ok(id) = id.parse::<u32>() {
+            let r#type = match kind {
+                "title" | "manga" => MappingType::Manga,
+                "chapter" => MappingType::Chapter,
+                "group" => MappingType::Group,
+                "tag" | "genre" => MappingType::Tag,
+                _ => return Err(unknown()),
+            };
+            return Ok(Self::Legacy { r#type, id, page });
+        }
+
+        let id = Uuid::parse_str(id).map_err(|_| unknown())?;
+        let kind = match kind {
+            "title" | "manga" => ResourceType::Manga,
+            "chapter" => ResourceType::Chapter,
+            "author" => ResourceType::Author,
+            "group" => ResourceType::ScanlationGroup,
+            "user" => ResourceType::User,
+            "list" => ResourceType::CustomList,
+            "tag" => ResourceType::Tag,
+            _ => return Err(unknown()),
+        };
+        let resource = match WebResource::new(&kind, id).ok_or_else(unknown)? {
+            WebResource::Chapter { id, .. } => WebResource::Chapter { id, page },
+            resource => resource,
+        };
+        Ok(Self::Resource(resource))
+    }
+}
+
+/// The error returned when parsing an invalid [`WebLink`].
+#[derive(Debug, Error)]
+pub enum ParseLinkError {
+    /// Not a url
+    #[error("invalid url")]
+    InvalidUrl(#[from] url::ParseError),
+
+    /// A url of another website
+    #[error("not a mangadex.org url: {0:?}")]
+    NotMangaDex(String),
+
+    /// A url of the website that isn't a link to a resource
+    #[error("not a link to a resource: {0:?}")]
+    UnknownPath(String),
+}
+
+impl<A, T: ObjectType, I: Copy + Into<Uuid>> ApiObject<A, T, I> {
+    /// Get the link to the page of the object on the website, if it has one.
+    pub fn web_url(&self) -> Option<Url> {
+        WebResource::new(&self.r#type.resource_type(), self.id.into()).map(|r| r.url())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use httpmock::{Method::POST, MockServer};
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    const ID: &str = "32d76d19-8a05-4db0-9fc2-e0b0648fe9d0";
+
+    fn parse(link: &str) -> WebLink {
+        link.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_links() {
+        let id = Uuid::parse_str(ID).unwrap();
+        assert_eq!(
+            parse(&format!("https://mangadex.org/title/{}/solo-leveling", ID)),
+            WebLink::Resource(WebResource::Manga(id.into()))
+        );
+        assert_eq!(
+            parse(&format!("mangadex.org/chapter/{}/3", ID)),
+            WebLink::Resource(WebResource::Chapter {
+                id: id.into(),
+                page: Some(3)
+            })
+        );
+        assert_eq!(
+            parse(&format!("https://www.mangadex.org/group/{}", ID)),
+            WebLink::Resource(WebResource::Group(id.into()))
+        );
+        assert_eq!(
+            parse("https://mangadex.org/title/123"),
+            WebLink::Legacy {
+                r#type: MappingType::Manga,
+                id: 123,
+                page: None
+            }
+        );
+
+        assert!(matches!(
+            "https://example.com/title/123".parse::<WebLink>(),
+            Err(ParseLinkError::NotMangaDex(_))
+        ));
+        assert!(matches!(
+            "https://mangadex.org/about".parse::<WebLink>(),
+            Err(ParseLinkError::UnknownPath(_))
+        ));
+    }
+
+    #[test]
+    fn generate_links() {
+        let id = Uuid::parse_str(ID).unwrap();
+        let chapter = WebResource::Chapter {
+            id: id.into(),
+            page: Some(2),
+        };
+        assert_eq!(
+            chapter.to_string(),
+            format!("https://mangadex.org/chapter/{}/2", ID)
+        );
+        assert_eq!(parse(chapter.url().as_str()), WebLink::Resource(chapter));
+
+        let group = crate::schema::group::ScanlationGroupType::ScanlationGroup;
+        let object = ApiObject {
+            id: GroupId::from(id),
+            r#type: group,
+            attributes: (),
+        };
+        assert_eq!(
+            object.web_url().unwrap().as_str(),
+            format!("https://mangadex.org/group/{}", ID)
+        );
+    }
+
+    #[tokio::test]
+    async fn resolve_legacy_links() -> anyhow::Result<()> {
+        let server = MockServer::start_async().await;
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(POST)
+                    .path("/legacy/mapping")
+                    .json_body(json!({ "type": "chapter", "ids": [42] }));
+                then.header("Content-Type", "application/json")
+                    .json_body(json!([{
+                        "result": "ok",
+                        "data": {
+                            "id": "24b6d026-a7cb-498e-8717-26b2831cf318",
+                            "type": "mapping_id",
+                            "attributes": { "type": "chapter", "legacyId": 42, "newId": ID }
+                        }
+                    }]));
+            })
+            .await;
+
+        let client = Client::new(&server.base_url())?;
+        let resource = parse("https://mangadex.org/chapter/42/5")
+            .resolve(&client)
+            .await?;
+
+        mock.assert_async().await;
+        assert_eq!(
+            resource,
+            Some(WebResource::Chapter {
+                id: ID.parse()?,
+                page: Some(5)
+            })
+        );
+        Ok(())
+    }
+}
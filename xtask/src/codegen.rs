@@ -0,0 +1,796 @@
+//! Generating request and schema modules from the OpenAPI document.
+//!
+//! This is a pilot: only the modules listed in [`MODULES`] (the `Upload` tag) are generated, the
+//! rest of `src/api` and `src/schema` is written by hand until the generator covers the builders
+//! and typed ids those modules rely on (see the TODO list of the readme). Schemas that already
+//! exist in a hand-written schema module are reused instead of being generated again.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+use std::fs;
+use std::io::Write as _;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+
+use crate::spec::{path_template, Op, Schema, Spec};
+use crate::wrappers;
+
+/// A module generated from the operations of a tag of the spec.
+pub struct Module {
+    /// The tag of the operations in the spec.
+    pub tag: &'static str,
+    /// The name of the module, in both `src/api` and `src/schema`.
+    pub name: &'static str,
+    /// The title of the module docs.
+    pub title: &'static str,
+}
+
+pub const MODULES: &[Module] = &[Module {
+    tag: "Upload",
+    name: "upload",
+    title: "Upload sessions",
+}];
+
+const HEADER: &str =
+    "// This file is generated from `api.yaml` by `cargo xtask codegen`, do not edit it by hand.";
+
+/// Attributes that are typed more precisely than the spec says, with a type of `crate::schema`.
+/// For arrays, the type of the items is replaced.
+const FIELD_TYPES: &[(&str, &str)] = &[
+    ("translatedLanguage", "LanguageCode"),
+    ("originalLanguage", "LanguageCode"),
+    ("manga", "MangaId"),
+    ("groups", "GroupId"),
+];
+
+/// A generated file.
+pub struct File {
+    pub path: String,
+    pub contents: String,
+}
+
+/// Generate the files of every module in [`MODULES`].
+pub fn generate(spec: &Spec, root: &Path) -> Result<Vec<File>> {
+    let known = known_types(&root.join("src/schema"))?;
+    // Operations of a generated tag can still be wrapped by hand somewhere else.
+    let hand_written: Vec<_> = wrappers::find(&root.join("src/api"))?
+        .into_iter()
+        .filter(|w| {
+            !MODULES
+                .iter()
+                .any(|m| w.name.starts_with(&format!("{}::", m.name)))
+        })
+        .collect();
+
+    let mut files = Vec::new();
+    for module in MODULES {
+        let mut gen = Generator {
+            spec,
+            module,
+            known: &known,
+            schemas: BTreeMap::new(),
+            schema_imports: BTreeSet::new(),
+            api_imports: Imports::default(),
+            api: String::new(),
+        };
+        for op in spec.operations() {
+            let wrapped = hand_written
+                .iter()
+                .any(|w| w.method == op.method && path_template(&w.path) == path_template(op.path));
+            if !wrapped && op.operation.tags.iter().any(|t| t == module.tag) {
+                gen.operation(op)
+                    .with_context(|| format!("generating {}", op.display()))?;
+            }
+        }
+        let (api, schema) = gen.finish();
+        files.push(File {
+            path: format!("src/api/{}.rs", module.name),
+            contents: rustfmt(&api)?,
+        });
+        files.push(File {
+            path: format!("src/schema/{}.rs", module.name),
+            contents: rustfmt(&schema)?,
+        });
+    }
+    Ok(files)
+}
+
+/// The types defined by the hand-written schema modules, by name, with the module they're in.
+fn known_types(schema_dir: &Path) -> Result<BTreeMap<String, String>> {
+    let mut known = BTreeMap::new();
+    for entry in fs::read_dir(schema_dir)? {
+        let path = entry?.path();
+        let module = match path.file_stem().and_then(|s| s.to_str()) {
+            Some(module) if module != "mod" => module.to_string(),
+            _ => continue,
+        };
+        if MODULES.iter().any(|m| m.name == module) {
+            continue;
+        }
+        let source = fs::read_to_string(&path)?;
+        for line in source.lines() {
+            let name = ["pub struct ", "pub enum ", "pub type "]
+                .iter()
+                .find_map(|prefix| line.strip_prefix(prefix))
+                .and_then(|rest| {
+                    rest.split(|c: char| !c.is_alphanumeric() && c != '_')
+                        .next()
+                });
+            if let Some(name) = name {
+                // `common` is re-exported by `schema`.
+                let path = match module.as_str() {
+                    "common" => name.to_string(),
+                    _ => format!("{}::{}", module, name),
+                };
+                known.insert(name.to_string(), path);
+            }
+        }
+    }
+    Ok(known)
+}
+
+#[derive(Default)]
+struct Imports {
+    std: BTreeSet<&'static str>,
+    external: BTreeSet<&'static str>,
+    krate: BTreeSet<&'static str>,
+    schema: BTreeSet<String>,
+}
+
+struct Generator<'a> {
+    spec: &'a Spec,
+    module: &'a Module,
+    known: &'a BTreeMap<String, String>,
+    /// Generated schema items, by name.
+    schemas: BTreeMap<String, String>,
+    schema_imports: BTreeSet<&'static str>,
+    api_imports: Imports,
+    api: String,
+}
+
+/// Where a request field is sent.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Location {
+    Path,
+    Query,
+    Body,
+}
+
+/// The parts of an `Endpoint` implementation written without `impl_endpoint!`.
+struct ManualEndpoint<'a> {
+    name: &'a str,
+    path_format: &'a str,
+    path_args: &'a [String],
+    auth: bool,
+    response: &'a str,
+    response_attr: Option<&'static str>,
+}
+
+/// The payload of a request that `impl_endpoint!` doesn't support.
+enum Manual {
+    /// A multipart body, with the given fields as parts.
+    Multipart(Vec<String>),
+    /// A json body made of a single field (e.g. an array), and its type.
+    Body(String, String),
+}
+
+struct Field {
+    name: String,
+    rename: Option<String>,
+    doc: Option<String>,
+    ty: String,
+    optional: bool,
+    location: Location,
+}
+
+impl Generator<'_> {
+    fn operation(&mut self, op: Op<'_>) -> Result<()> {
+        let id = op
+            .operation
+            .operation_id
+            .as_deref()
+            .ok_or_else(|| anyhow!("missing operationId"))?;
+        let name = pascal_case(id);
+
+        let mut fields = Vec::new();
+        let mut path_format = op.path.to_string();
+        let mut path_args = Vec::new();
+        for param in op.parameters()? {
+            let schema = param.schema.clone().unwrap_or_default();
+            let location = match param.location.as_str() {
+                "path" => Location::Path,
+                "query" => Location::Query,
+                other => bail!("unsupported parameter location {:?}", other),
+            };
+            let field_name = snake_case(param.name.trim_end_matches("[]"));
+            let ty = self.field_type(&param.name, &schema, Side::Api)?;
+            if location == Location::Path {
+                let placeholder = if ty == "Uuid" { "{:x}" } else { "{}" };
+                path_format = path_format.replace(&format!("{{{}}}", param.name), placeholder);
+                path_args.push(field_name.clone());
+            }
+            fields.push(Field {
+                rename: rename(param.name.trim_end_matches("[]"), &field_name),
+                name: field_name,
+                doc: param.description.clone(),
+                ty,
+                optional: location == Location::Query && !param.required,
+                location,
+            });
+        }
+
+        let mut multipart = false;
+        let mut transparent = false;
+        if let Some((content_type, schema)) = op.body() {
+            let schema = self.spec.resolve(schema)?;
+            match (content_type, schema.kind.as_deref()) {
+                ("application/json", Some("array")) => {
+                    let item = schema.items.as_deref().cloned().unwrap_or_default();
+                    let item_ty = self.field_type("", &item, Side::Api)?;
+                    let name = if item_ty == "Uuid" { "ids" } else { "items" };
+                    transparent = true;
+                    fields.push(Field {
+                        name: name.to_string(),
+                        rename: None,
+                        doc: None,
+                        ty: format!("Vec<{}>", item_ty),
+                        optional: false,
+                        location: Location::Body,
+                    });
+                }
+                ("application/json", _) | ("multipart/form-data", _) => {
+                    multipart = content_type == "multipart/form-data";
+                    for (prop, prop_schema) in &schema.properties {
+                        let field_name = snake_case(prop);
+                        fields.push(Field {
+                            rename: None,
+                            name: field_name,
+                            doc: prop_schema.description.clone(),
+                            ty: self.field_type(prop, prop_schema, Side::Api)?,
+                            optional: is_optional(schema, prop, prop_schema),
+                            location: Location::Body,
+                        });
+                    }
+                }
+                (other, _) => bail!("unsupported request body {:?}", other),
+            }
+        }
+
+        let has_query = fields.iter().any(|f| f.location == Location::Query);
+        let has_body = fields.iter().any(|f| f.location == Location::Body);
+        ensure!(
+            !(has_query && has_body),
+            "requests with both a query and a body are not supported"
+        );
+        let payload = if multipart {
+            "multipart"
+        } else if has_query {
+            "query"
+        } else if has_body {
+            "body"
+        } else {
+            "no_data"
+        };
+        let auth = op.requires_auth();
+        let (response, response_attr) = self.response(op, &name)?;
+        // Path ids left out of a query or body sent by `impl_endpoint!`.
+        let path_ids = !path_args.is_empty() && !transparent && matches!(payload, "query" | "body");
+
+        // The request type.
+        let out = &mut self.api;
+        let summary = op.operation.summary.as_deref().unwrap_or(id);
+        writeln!(out)?;
+        write!(out, "/// {}", summary)?;
+        if auth {
+            write!(out, " (requires authentication)")?;
+        }
+        writeln!(out, "\n///\n/// Call to `{} {}`", op.method, op.path)?;
+
+        let builder = fields.iter().any(|f| f.optional);
+        write!(out, "#[derive(Debug, Serialize, Deserialize, Clone")?;
+        if builder {
+            write!(out, ", Builder")?;
+            self.api_imports.external.insert("derive_builder::Builder");
+        }
+        writeln!(out, ")]")?;
+        if !fields.is_empty() {
+            writeln!(out, "#[serde(rename_all = \"camelCase\")]")?;
+        }
+        if builder {
+            writeln!(out, "#[builder(setter(strip_option))]")?;
+        }
+        if fields.is_empty() {
+            writeln!(out, "pub struct {};", name)?;
+        } else {
+            writeln!(out, "pub struct {} {{", name)?;
+            for (i, field) in fields.iter().enumerate() {
+                if i > 0 {
+                    writeln!(out)?;
+                }
+                if let Some(doc) = &field.doc {
+                    for line in doc.lines() {
+                        writeln!(out, "/// {}", line)?;
+                    }
+                }
+                if field.location == Location::Path && path_ids {
+                    // Sent in the path only, persisted through `PathIds`.
+                    writeln!(out, "#[serde(skip_serializing)]")?;
+                }
+                if let Some(rename) = &field.rename {
+                    writeln!(out, "#[serde(rename = \"{}\")]", rename)?;
+                }
+                if field.optional {
+                    writeln!(out, "#[builder(default)]")?;
+                    writeln!(out, "pub {}: Option<{}>,", field.name, field.ty)?;
+                } else {
+                    writeln!(out, "pub {}: {},", field.name, field.ty)?;
+                }
+            }
+            writeln!(out, "}}")?;
+        }
+
+        let path = if path_args.is_empty() {
+            format!("\"{}\"", path_format)
+        } else {
+            format!("(\"{}\", {})", path_format, path_args.join(", "))
+        };
+
+        let mut body = fields.iter().filter(|f| f.location == Location::Body);
+        let manual = if multipart {
+            Some(Manual::Multipart(body.map(|f| f.name.clone()).collect()))
+        } else if transparent {
+            let field = body.next_back().expect("array body field");
+            Some(Manual::Body(field.name.clone(), field.ty.clone()))
+        } else {
+            None
+        };
+        if let Some(manual) = manual {
+            let endpoint = ManualEndpoint {
+                name: &name,
+                path_format: &path_format,
+                path_args: &path_args,
+                auth,
+                response: &response,
+                response_attr,
+            };
+            self.manual(op, endpoint, manual)?;
+            return Ok(());
+        }
+
+        let out = &mut self.api;
+        writeln!(out)?;
+        writeln!(out, "impl_endpoint! {{")?;
+        writeln!(out, "    {} {},", op.method, path)?;
+        writeln!(
+            out,
+            "    #[{}{}] {},",
+            payload,
+            if auth { " auth" } else { "" },
+            name
+        )?;
+        match response_attr {
+            Some(attr) => writeln!(out, "    #[{}] {}", attr, response)?,
+            None => writeln!(out, "    {}", response)?,
+        }
+        writeln!(out, "}}")?;
+
+        if path_ids {
+            let ids: Vec<String> = fields
+                .iter()
+                .filter(|f| f.location == Location::Path)
+                .map(|f| {
+                    let name = f.rename.clone().unwrap_or_else(|| camel_case(&f.name));
+                    format!("{}: \"{}\"", f.name, name)
+                })
+                .collect();
+            writeln!(out)?;
+            writeln!(out, "path_ids! {{ {} {{ {} }} }}", name, ids.join(", "))?;
+        }
+        Ok(())
+    }
+
+    /// An `Endpoint` implementation for a request that `impl_endpoint!` doesn't support.
+    fn manual(&mut self, op: Op<'_>, endpoint: ManualEndpoint<'_>, payload: Manual) -> Result<()> {
+        self.api_imports.std.insert("std::borrow::Cow");
+        self.api_imports.external.insert("reqwest::Method");
+        self.api_imports.krate.insert("crate::common::Endpoint");
+        self.api_imports.krate.insert("crate::Client");
+        if let Manual::Multipart(_) = payload {
+            self.api_imports.krate.insert("crate::common::Multipart");
+        }
+
+        let ManualEndpoint {
+            name,
+            path_format,
+            path_args,
+            auth,
+            response,
+            response_attr,
+        } = endpoint;
+        let args: String = path_args.iter().map(|a| format!(", self.{}", a)).collect();
+
+        let out = &mut self.api;
+        writeln!(out)?;
+        writeln!(out, "impl Endpoint for {} {{", name)?;
+        writeln!(out, "type Query = ();")?;
+        match &payload {
+            Manual::Multipart(_) => writeln!(out, "type Body = ();")?,
+            Manual::Body(_, ty) => writeln!(out, "type Body = {};", ty)?,
+        }
+        writeln!(out, "type Response = {};", response)?;
+        writeln!(out)?;
+        writeln!(out, "fn path(&self) -> Cow<'_, str> {{")?;
+        writeln!(out, "Cow::Owned(format!(\"{}\"{}))", path_format, args)?;
+        writeln!(out, "}}")?;
+        writeln!(
+            out,
+            "fn method(&self) -> Method {{ Method::{} }}",
+            op.method
+        )?;
+        if auth {
+            writeln!(out)?;
+            writeln!(out, "fn require_auth(&self) -> bool {{ true }}")?;
+        }
+        writeln!(out)?;
+        match &payload {
+            Manual::Multipart(parts) => {
+                writeln!(out, "fn multipart(&self) -> Option<Multipart> {{")?;
+                writeln!(out, "let form = Multipart::new()")?;
+                for part in parts {
+                    writeln!(out, ".bytes(\"{0}\", self.{0}.clone())", part)?;
+                }
+                writeln!(out, ";")?;
+                writeln!(out, "Some(form)")?;
+            }
+            Manual::Body(field, _) => {
+                writeln!(out, "fn body(&self) -> Option<&Self::Body> {{")?;
+                writeln!(out, "Some(&self.{})", field)?;
+            }
+        }
+        writeln!(out, "}}")?;
+        writeln!(out, "}}")?;
+
+        let (returned, send, send_blocking) = match response_attr {
+            Some("flatten_result") => (
+                response.to_string(),
+                "client.send(self).await?",
+                "client.send(self)?",
+            ),
+            Some("discard_result") => (
+                "Result<()>".to_string(),
+                "client.send(self).await??;\nOk(())",
+                "client.send(self)??;\nOk(())",
+            ),
+            other => bail!("unsupported response {:?} for {}", other, name),
+        };
+        writeln!(out)?;
+        writeln!(out, "impl {} {{", name)?;
+        writeln!(out, "/// Send the request")?;
+        writeln!(
+            out,
+            "pub async fn send(&self, client: &Client) -> {} {{",
+            returned
+        )?;
+        writeln!(out, "{}", send)?;
+        writeln!(out, "}}")?;
+        writeln!(out)?;
+        writeln!(out, "/// Send the request with a blocking client")?;
+        writeln!(out, "#[cfg(feature = \"blocking\")]")?;
+        writeln!(
+            out,
+            "pub fn send_blocking(&self, client: &crate::blocking::Client) -> {} {{",
+            returned
+        )?;
+        writeln!(out, "{}", send_blocking)?;
+        writeln!(out, "}}")?;
+        writeln!(out, "}}")?;
+        Ok(())
+    }
+
+    /// The response type of an operation, and the `impl_endpoint!` attribute it needs.
+    fn response(&mut self, op: Op<'_>, name: &str) -> Result<(String, Option<&'static str>)> {
+        let schema = op.response().ok_or_else(|| anyhow!("no json response"))?;
+
+        if let Some(reference) = schema.ref_name() {
+            if reference == "Response" {
+                self.api_imports.schema.insert("NoData".to_string());
+                self.api_imports.krate.insert("crate::Result");
+                return Ok(("Result<NoData>".to_string(), Some("discard_result")));
+            }
+            if self.known.contains_key(reference) && !is_entity(self.spec.schema(reference)?) {
+                self.import_schema(reference);
+                let attr = reference.ends_with("Response").then_some("flatten_result");
+                return Ok((reference.to_string(), attr));
+            }
+            // The spec refers to the entity, the api answers it in the `ApiData` envelope.
+            let entity = self.schema_type(reference)?;
+            let response = format!("{}Response", entity);
+            self.import_schema(&response);
+            return Ok((response, Some("flatten_result")));
+        }
+
+        // An inline envelope, like `{ "result": "ok", "data": [...] }`.
+        let data = schema
+            .properties
+            .get("data")
+            .ok_or_else(|| anyhow!("unsupported response schema"))?;
+        let data = self.field_type("data", data, Side::Schema)?;
+        let response = format!("{}Response", name);
+        self.schema_imports.insert("ApiData");
+        self.schema_imports.insert("crate::Result");
+        self.schemas.insert(
+            response.clone(),
+            format!(
+                "/// The response of [`{}`](crate::api::{}::{}).\npub type {} = Result<ApiData<{}>>;\n",
+                name, self.module.name, name, response, data
+            ),
+        );
+        self.import_schema(&response);
+        Ok((response, Some("flatten_result")))
+    }
+
+    fn import_schema(&mut self, name: &str) {
+        let path = match self.known.get(name) {
+            Some(path) => path.clone(),
+            None => format!("{}::*", self.module.name),
+        };
+        self.api_imports.schema.insert(path);
+    }
+
+    /// The rust type of a property, generating the schemas it refers to.
+    fn field_type(&mut self, name: &str, schema: &Schema, side: Side) -> Result<String> {
+        let precise = FIELD_TYPES.iter().find(|(field, _)| *field == name);
+        let plain = schema.ref_name().is_none() && schema.kind.as_deref() != Some("array");
+        if let (Some((_, ty)), true) = (precise, plain) {
+            match side {
+                Side::Api => self.api_imports.schema.insert(ty.to_string()),
+                Side::Schema => self.schema_imports.insert(ty),
+            };
+            return Ok(ty.to_string());
+        }
+        if let Some(reference) = schema.ref_name() {
+            let ty = self.schema_type(reference)?;
+            if side == Side::Api {
+                self.import_schema(&ty);
+            }
+            return Ok(ty);
+        }
+
+        let ty = match (schema.kind.as_deref(), schema.format.as_deref()) {
+            (Some("string"), Some("uuid")) => {
+                self.import("Uuid", side);
+                "Uuid".to_string()
+            }
+            (Some("string"), Some("date-time")) => {
+                self.import("DateTime", side);
+                "DateTime<Utc>".to_string()
+            }
+            (Some("string"), Some("binary")) => "Cow<'static, [u8]>".to_string(),
+            (Some("string"), _) => "String".to_string(),
+            (Some("integer"), _) => "i32".to_string(),
+            (Some("number"), _) => "f64".to_string(),
+            (Some("boolean"), _) => "bool".to_string(),
+            (Some("array"), _) => {
+                let item = schema.items.as_deref().cloned().unwrap_or_default();
+                format!("Vec<{}>", self.field_type(name, &item, side)?)
+            }
+            (kind, format) => bail!("unsupported type {:?} ({:?}) of {:?}", kind, format, name),
+        };
+        Ok(ty)
+    }
+
+    fn import(&mut self, ty: &str, side: Side) {
+        match (ty, side) {
+            ("Uuid", Side::Api) => self.api_imports.external.insert("uuid::Uuid"),
+            ("Uuid", Side::Schema) => self.schema_imports.insert("uuid::Uuid"),
+            ("DateTime", Side::Api) => self.api_imports.external.insert("chrono::{DateTime, Utc}"),
+            ("DateTime", Side::Schema) => self.schema_imports.insert("chrono::{DateTime, Utc}"),
+            _ => false,
+        };
+    }
+
+    /// The rust type of a component schema, generating it if it isn't hand-written.
+    fn schema_type(&mut self, name: &str) -> Result<String> {
+        if self.known.contains_key(name) || self.schemas.contains_key(name) {
+            return Ok(name.to_string());
+        }
+        let schema = self.spec.schema(name)?;
+        // Reserve the name, for recursive schemas.
+        self.schemas.insert(name.to_string(), String::new());
+
+        let mut out = String::new();
+        let props = &schema.properties;
+        if is_entity(schema) {
+            let attributes = props["attributes"].ref_name().unwrap_or_default();
+            let attributes = self.schema_type(attributes)?;
+            self.schema_imports.insert("ApiData");
+            self.schema_imports.insert("ApiObject");
+            self.schema_imports.insert("crate::Result");
+            writeln!(out, "pub type {} = ApiObject<{}>;", name, attributes)?;
+            writeln!(out, "pub type {0}Response = Result<ApiData<{0}>>;", name)?;
+        } else {
+            ensure!(
+                schema.kind.as_deref() == Some("object"),
+                "unsupported schema {:?}",
+                name
+            );
+            writeln!(out, "#[derive(Debug, Serialize, Deserialize, Clone)]")?;
+            writeln!(out, "#[serde(rename_all = \"camelCase\")]")?;
+            writeln!(out, "pub struct {} {{", name)?;
+            for (prop, prop_schema) in props {
+                if let Some(doc) = &prop_schema.description {
+                    writeln!(out, "/// {}", doc)?;
+                }
+                let ty = self.field_type(prop, prop_schema, Side::Schema)?;
+                if is_optional(schema, prop, prop_schema) {
+                    writeln!(out, "pub {}: Option<{}>,", snake_case(prop), ty)?;
+                } else {
+                    writeln!(out, "pub {}: {},", snake_case(prop), ty)?;
+                }
+            }
+            writeln!(out, "}}")?;
+        }
+
+        self.schemas.insert(name.to_string(), out);
+        Ok(name.to_string())
+    }
+
+    /// The contents of the api and schema modules.
+    fn finish(self) -> (String, String) {
+        let mut schema = format!("{}\n\n//! {}\n\n", HEADER, self.module.title);
+        let mut external = BTreeSet::new();
+        let mut local = BTreeSet::new();
+        let mut krate = BTreeSet::new();
+        for import in &self.schema_imports {
+            if let Some(path) = import.strip_prefix("crate::") {
+                krate.insert(path.to_string());
+            } else if import.contains("::") {
+                external.insert(*import);
+            } else {
+                local.insert(*import);
+            }
+        }
+        schema.push_str("use serde::{Deserialize, Serialize};\n");
+        for import in external {
+            writeln!(schema, "use {};", import).unwrap();
+        }
+        schema.push('\n');
+        if !local.is_empty() {
+            let local: Vec<_> = local.into_iter().collect();
+            writeln!(schema, "use super::{{{}}};", local.join(", ")).unwrap();
+        }
+        for import in krate {
+            writeln!(schema, "use crate::{};", import).unwrap();
+        }
+        for item in self.schemas.values() {
+            schema.push('\n');
+            schema.push_str(item);
+        }
+
+        let imports = self.api_imports;
+        let mut api = format!("{}\n\n//! {}\n\n", HEADER, self.module.title);
+        for import in &imports.std {
+            writeln!(api, "use {};", import).unwrap();
+        }
+        if !imports.std.is_empty() {
+            api.push('\n');
+        }
+        let mut external = imports.external;
+        external.insert("serde::{Deserialize, Serialize}");
+        for import in external {
+            writeln!(api, "use {};", import).unwrap();
+        }
+        api.push('\n');
+        if !imports.schema.is_empty() {
+            let schema: Vec<_> = imports.schema.into_iter().collect();
+            writeln!(api, "use crate::schema::{{{}}};", schema.join(", ")).unwrap();
+        }
+        for import in &imports.krate {
+            writeln!(api, "use {};", import).unwrap();
+        }
+        api.push_str(&self.api);
+
+        (api, schema)
+    }
+}
+
+/// Which module a type is used in, for its imports.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Api,
+    Schema,
+}
+
+/// Whether a schema is an api object, with an id, a type and attributes.
+fn is_entity(schema: &Schema) -> bool {
+    let props = &schema.properties;
+    props.len() == 3
+        && props.contains_key("id")
+        && props.contains_key("type")
+        && props.get("attributes").and_then(Schema::ref_name).is_some()
+}
+
+/// Whether a property of an object is optional.
+///
+/// Response schemas of the spec usually don't list their required properties, so properties are
+/// only optional if they're nullable, or if the schema lists the required ones without them.
+fn is_optional(object: &Schema, name: &str, property: &Schema) -> bool {
+    property.nullable || (!object.required.is_empty() && !object.required.iter().any(|r| r == name))
+}
+
+/// The serde rename a field needs, if `rename_all = "camelCase"` doesn't produce its name.
+fn rename(original: &str, field: &str) -> Option<String> {
+    let camel = camel_case(field);
+    (camel != original).then(|| original.to_string())
+}
+
+pub fn pascal_case(name: &str) -> String {
+    name.split(['-', '_'])
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            let first = chars.next().unwrap().to_ascii_uppercase();
+            std::iter::once(first).chain(chars).collect::<String>()
+        })
+        .collect()
+}
+
+pub fn snake_case(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len() + 4);
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            if !snake.is_empty() {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+        } else if c == '-' {
+            snake.push('_');
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+fn camel_case(name: &str) -> String {
+    let pascal = pascal_case(name);
+    let mut chars = pascal.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+        None => pascal,
+    }
+}
+
+/// Format generated code.
+fn rustfmt(source: &str) -> Result<String> {
+    let mut child = Command::new("rustfmt")
+        .args(["--edition", "2018", "--emit", "stdout", "--quiet"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .context("running rustfmt")?;
+    child.stdin.take().unwrap().write_all(source.as_bytes())?;
+    let output = child.wait_with_output()?;
+    ensure!(
+        output.status.success(),
+        "rustfmt failed on generated code:\n{}",
+        source
+    );
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names() {
+        assert_eq!(pascal_case("get-upload-session"), "GetUploadSession");
+        assert_eq!(snake_case("uploadSessionId"), "upload_session_id");
+        assert_eq!(camel_case("translated_language"), "translatedLanguage");
+        assert_eq!(rename("ids", "ids"), None);
+        assert_eq!(rename("forcePort443", "force_port443"), None);
+    }
+}
@@ -0,0 +1,115 @@
+//! Development tasks, run with `cargo xtask <task>`.
+//!
+//! - `codegen [--check]`: generate the modules of [`codegen::MODULES`] from `api.yaml`. With
+//!   `--check`, fail instead if the generated files are out of date.
+//! - `coverage`: fail if an operation of `api.yaml` has no request type in `src/api`.
+
+mod codegen;
+mod spec;
+mod wrappers;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+use anyhow::{bail, Result};
+
+use spec::{path_template, Spec};
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    let result = match args.as_slice() {
+        ["codegen"] => codegen(false),
+        ["codegen", "--check"] => codegen(true),
+        ["coverage"] => coverage(),
+        _ => {
+            eprintln!("usage: cargo xtask <codegen [--check] | coverage>");
+            exit(2);
+        }
+    };
+
+    if let Err(err) = result {
+        eprintln!("error: {:#}", err);
+        exit(1);
+    }
+}
+
+/// The root of the repository.
+fn root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("xtask is in the repository")
+        .to_path_buf()
+}
+
+fn codegen(check: bool) -> Result<()> {
+    let root = root();
+    let spec = Spec::load(&root.join("api.yaml"))?;
+
+    let mut stale = Vec::new();
+    for file in codegen::generate(&spec, &root)? {
+        let path = root.join(&file.path);
+        let current = fs::read_to_string(&path).unwrap_or_default();
+        if current == file.contents {
+            continue;
+        }
+        if check {
+            stale.push(file.path);
+        } else {
+            fs::write(&path, &file.contents)?;
+            println!("wrote {}", file.path);
+        }
+    }
+
+    if !stale.is_empty() {
+        bail!(
+            "generated files are out of date, run `cargo xtask codegen`: {}",
+            stale.join(", ")
+        );
+    }
+    Ok(())
+}
+
+fn coverage() -> Result<()> {
+    let root = root();
+    let spec = Spec::load(&root.join("api.yaml"))?;
+    let wrappers = wrappers::find(&root.join("src/api"))?;
+
+    let mut missing = Vec::new();
+    for op in spec.operations() {
+        let template = path_template(op.path);
+        let wrapped = wrappers
+            .iter()
+            .any(|w| w.method == op.method && path_template(&w.path) == template);
+        if !wrapped {
+            missing.push(op.display());
+        }
+    }
+
+    for wrapper in &wrappers {
+        let template = path_template(&wrapper.path);
+        let in_spec = spec
+            .operations()
+            .any(|op| op.method == wrapper.method && path_template(op.path) == template);
+        if !in_spec {
+            missing.push(format!(
+                "{} {} (wrapped by {}, but not in the spec)",
+                wrapper.method, wrapper.path, wrapper.name
+            ));
+        }
+    }
+
+    let total = spec.operations().count();
+    if !missing.is_empty() {
+        bail!(
+            "{} of {} operations are not covered:\n  {}",
+            missing.len(),
+            total,
+            missing.join("\n  ")
+        );
+    }
+    println!("all {} operations of the spec are wrapped", total);
+    Ok(())
+}
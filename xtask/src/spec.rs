@@ -0,0 +1,244 @@
+//! The parts of the OpenAPI document the generator and the checks use.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use indexmap::IndexMap;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct Spec {
+    pub paths: IndexMap<String, PathItem>,
+    pub components: Components,
+    #[serde(default)]
+    pub security: Vec<BTreeMap<String, Vec<String>>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Components {
+    #[serde(default)]
+    pub schemas: IndexMap<String, Schema>,
+    #[serde(default)]
+    pub parameters: IndexMap<String, Parameter>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PathItem {
+    #[serde(default)]
+    pub parameters: Vec<Parameter>,
+    pub get: Option<Operation>,
+    pub post: Option<Operation>,
+    pub put: Option<Operation>,
+    pub delete: Option<Operation>,
+}
+
+impl PathItem {
+    /// The operations of the path, with their http method.
+    pub fn operations(&self) -> impl Iterator<Item = (&'static str, &Operation)> {
+        IntoIterator::into_iter([
+            ("GET", &self.get),
+            ("POST", &self.post),
+            ("PUT", &self.put),
+            ("DELETE", &self.delete),
+        ])
+        .filter_map(|(method, op)| op.as_ref().map(|op| (method, op)))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Operation {
+    pub summary: Option<String>,
+    pub operation_id: Option<String>,
+    #[serde(default)]
+    pub parameters: Vec<Parameter>,
+    pub request_body: Option<RequestBody>,
+    #[serde(default)]
+    pub responses: IndexMap<String, Response>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub security: Option<Vec<BTreeMap<String, Vec<String>>>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Parameter {
+    #[serde(rename = "$ref")]
+    pub reference: Option<String>,
+    #[serde(default)]
+    pub name: String,
+    #[serde(rename = "in", default)]
+    pub location: String,
+    #[serde(default)]
+    pub required: bool,
+    pub description: Option<String>,
+    pub schema: Option<Schema>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RequestBody {
+    #[serde(default)]
+    pub content: IndexMap<String, MediaType>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Response {
+    #[serde(default)]
+    pub content: IndexMap<String, MediaType>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MediaType {
+    pub schema: Option<Schema>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Schema {
+    #[serde(rename = "$ref")]
+    pub reference: Option<String>,
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    pub format: Option<String>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub properties: IndexMap<String, Schema>,
+    pub items: Option<Box<Schema>>,
+    #[serde(default)]
+    pub nullable: bool,
+    #[serde(default)]
+    pub required: Vec<String>,
+}
+
+impl Schema {
+    /// The name of the referenced component schema, if this is a reference.
+    pub fn ref_name(&self) -> Option<&str> {
+        self.reference
+            .as_deref()
+            .and_then(|r| r.strip_prefix("#/components/schemas/"))
+    }
+}
+
+impl Spec {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path).with_context(|| format!("reading {:?}", path))?;
+        serde_yaml::from_str(&text).with_context(|| format!("parsing {:?}", path))
+    }
+
+    /// Every operation of the spec, in document order.
+    pub fn operations(&self) -> impl Iterator<Item = Op<'_>> {
+        self.paths.iter().flat_map(move |(path, item)| {
+            item.operations().map(move |(method, operation)| Op {
+                spec: self,
+                method,
+                path,
+                item,
+                operation,
+            })
+        })
+    }
+
+    pub fn schema(&self, name: &str) -> Result<&Schema> {
+        self.components
+            .schemas
+            .get(name)
+            .ok_or_else(|| anyhow!("unknown schema {:?}", name))
+    }
+
+    /// Follow a schema reference, if it is one.
+    pub fn resolve<'a>(&'a self, schema: &'a Schema) -> Result<&'a Schema> {
+        match schema.ref_name() {
+            Some(name) => self.schema(name),
+            None => Ok(schema),
+        }
+    }
+}
+
+/// An operation together with its path.
+#[derive(Clone, Copy)]
+pub struct Op<'a> {
+    pub spec: &'a Spec,
+    pub method: &'static str,
+    pub path: &'a str,
+    pub item: &'a PathItem,
+    pub operation: &'a Operation,
+}
+
+impl<'a> Op<'a> {
+    /// `GET /manga/{id}`, for messages.
+    pub fn display(&self) -> String {
+        format!("{} {}", self.method, self.path)
+    }
+
+    /// The parameters of the path and of the operation, with references resolved.
+    pub fn parameters(&self) -> Result<Vec<Parameter>> {
+        self.item
+            .parameters
+            .iter()
+            .chain(&self.operation.parameters)
+            .map(|p| match &p.reference {
+                Some(reference) => {
+                    let name = reference.trim_start_matches("#/components/parameters/");
+                    self.spec
+                        .components
+                        .parameters
+                        .get(name)
+                        .cloned()
+                        .ok_or_else(|| anyhow!("unknown parameter {:?}", reference))
+                }
+                None => Ok(p.clone()),
+            })
+            .collect()
+    }
+
+    /// Whether the operation requires a session token.
+    pub fn requires_auth(&self) -> bool {
+        !self
+            .operation
+            .security
+            .as_ref()
+            .unwrap_or(&self.spec.security)
+            .is_empty()
+    }
+
+    /// The request body schema, and its content type.
+    pub fn body(&self) -> Option<(&'a str, &'a Schema)> {
+        let body = self.operation.request_body.as_ref()?;
+        body.content
+            .iter()
+            .find_map(|(ty, media)| media.schema.as_ref().map(|s| (ty.as_str(), s)))
+    }
+
+    /// The schema of the successful response.
+    pub fn response(&self) -> Option<&'a Schema> {
+        let response = self
+            .operation
+            .responses
+            .iter()
+            .find(|(status, _)| status.starts_with('2'))?
+            .1;
+        response
+            .content
+            .get("application/json")
+            .and_then(|media| media.schema.as_ref())
+    }
+}
+
+/// A path with its parameters erased, e.g. `/manga/{}/list/{}`, to compare the paths of the spec
+/// to the ones of the wrappers.
+pub fn path_template(path: &str) -> String {
+    let mut template = String::with_capacity(path.len());
+    let mut in_param = false;
+    for c in path.chars() {
+        match c {
+            '{' => {
+                in_param = true;
+                template.push_str("{}");
+            }
+            '}' => in_param = false,
+            _ if !in_param => template.push(c),
+            _ => {}
+        }
+    }
+    template
+}
@@ -0,0 +1,173 @@
+//! Finding the endpoints wrapped by the crate, by reading its source.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+/// Operations that are wrapped without an `Endpoint` implementation, as (method, path, wrapper).
+pub const WRAPPED_ELSEWHERE: &[(&str, &str, &str)] = &[
+    // Doesn't answer json, so it goes through `Client::ping`.
+    ("GET", "/ping", "infrastructure::Ping"),
+];
+
+/// An `Endpoint` implementation found in `src/api`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wrapper {
+    /// The request type, e.g. `manga::GetManga`.
+    pub name: String,
+    pub method: String,
+    /// The path, as written in the format string, e.g. `/manga/{:x}`.
+    pub path: String,
+}
+
+/// All the endpoints implemented in the public modules of `src/api`.
+pub fn find(api_dir: &Path) -> Result<Vec<Wrapper>> {
+    let mod_rs = api_dir.join("mod.rs");
+    let modules = fs::read_to_string(&mod_rs).with_context(|| format!("reading {:?}", mod_rs))?;
+    let modules: Vec<&str> = modules
+        .lines()
+        .filter_map(|line| line.strip_prefix("pub mod "))
+        .map(|line| line.trim_end_matches(';'))
+        .collect();
+
+    let mut entries = fs::read_dir(api_dir)
+        .with_context(|| format!("reading {:?}", api_dir))?
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|e| e.path());
+
+    let mut wrappers = Vec::new();
+    for entry in entries {
+        let path = entry.path();
+        let module = match path.file_stem().and_then(|s| s.to_str()) {
+            Some(module) if modules.contains(&module) => module.to_string(),
+            _ => continue,
+        };
+        let source = fs::read_to_string(&path).with_context(|| format!("reading {:?}", path))?;
+        wrappers.extend(parse(&module, &source));
+    }
+
+    for (method, path, name) in WRAPPED_ELSEWHERE {
+        wrappers.push(Wrapper {
+            name: name.to_string(),
+            method: method.to_string(),
+            path: path.to_string(),
+        });
+    }
+    Ok(wrappers)
+}
+
+/// Find the `impl_endpoint!` invocations and `impl Endpoint for` blocks of a module.
+fn parse(module: &str, source: &str) -> Vec<Wrapper> {
+    let mut wrappers = Vec::new();
+
+    for (start, _) in source.match_indices("impl_endpoint! {") {
+        let block = block_at(source, start);
+        let mut words = block
+            .trim_start_matches("impl_endpoint! {")
+            .split_whitespace();
+        let method = words.next().unwrap_or_default().to_string();
+        let path = first_literal(block).unwrap_or_default();
+        let name = block
+            .split(']')
+            .nth(1)
+            .and_then(|rest| rest.split(',').next())
+            .unwrap_or_default()
+            .trim();
+        wrappers.push(Wrapper {
+            name: format!("{}::{}", module, name),
+            method,
+            path,
+        });
+    }
+
+    for (start, _) in source.match_indices("impl Endpoint for ") {
+        let block = block_at(source, start);
+        let name = block["impl Endpoint for ".len()..]
+            .split_whitespace()
+            .next()
+            .unwrap_or_default();
+        let method = block
+            .split("Method::")
+            .nth(1)
+            .map(|rest| rest.chars().take_while(char::is_ascii_uppercase).collect())
+            .unwrap_or_default();
+        let path = block
+            .split("fn path")
+            .nth(1)
+            .and_then(first_literal)
+            .unwrap_or_default();
+        wrappers.push(Wrapper {
+            name: format!("{}::{}", module, name),
+            method,
+            path,
+        });
+    }
+
+    wrappers
+}
+
+/// The text from `start` to the closing brace of the first block opened after it.
+fn block_at(source: &str, start: usize) -> &str {
+    let mut depth = 0;
+    for (i, c) in source[start..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 1 => return &source[start..start + i + 1],
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    &source[start..]
+}
+
+/// The first string literal starting with a `/`.
+fn first_literal(text: &str) -> Option<String> {
+    let start = text.find("\"/")? + 1;
+    let end = text[start..].find('"')? + start;
+    Some(text[start..end].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_wrappers() {
+        let source = r#"
+impl_endpoint! {
+    GET ("/manga/{:x}/feed", manga_id),
+    #[query] GetMangaFeed,
+    ChapterList
+}
+
+impl_endpoint! {
+    POST "/auth/logout",
+    #[no_data auth] Logout,
+    #[discard_result] Result<NoData>
+}
+
+impl Endpoint for UploadCover {
+    fn path(&self) -> Cow<'_, str> {
+        Cow::Owned(format!("/cover/{:x}", self.manga_id))
+    }
+    fn method(&self) -> Method {
+        Method::POST
+    }
+}
+"#;
+        let wrapper = |name: &str, method: &str, path: &str| Wrapper {
+            name: name.to_string(),
+            method: method.to_string(),
+            path: path.to_string(),
+        };
+        assert_eq!(
+            parse("test", source),
+            vec![
+                wrapper("test::GetMangaFeed", "GET", "/manga/{:x}/feed"),
+                wrapper("test::Logout", "POST", "/auth/logout"),
+                wrapper("test::UploadCover", "POST", "/cover/{:x}"),
+            ]
+        );
+    }
+}